@@ -1,69 +1,217 @@
-use std::sync::{Arc, Mutex, MutexGuard};
+mod key_log;
+mod pool;
+mod transcript;
+
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
 use anyhow::bail;
 use pcsc::{Card, Context, Protocols, Scope, ShareMode};
-use tracing::debug;
+use tracing::{debug, info, warn};
+
+use crate::config::{CasBackend, CasConfig};
+
+pub use self::key_log::{KeyLogStore, KeyLogWriter};
+pub use self::pool::CasPool;
+pub use self::transcript::{RecordingCasModule, ReplayCasModule};
+
+/// The CAS module the configuration selects.
+pub enum CasModule {
+    Pcsc(PcscCasModule),
+    Record(RecordingCasModule<PcscCasModule>),
+    Replay(ReplayCasModule),
+}
+
+impl CasModule {
+    /// Opens the card in the reader, or in the first reader found on `None`, through the backend.
+    pub fn open(config: &CasConfig, reader: Option<&str>) -> anyhow::Result<Self> {
+        let transcript = || {
+            config
+                .transcript
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("`cas.transcript` is required by this backend"))
+        };
+
+        Ok(match config.backend {
+            CasBackend::Pcsc => Self::Pcsc(PcscCasModule::open(reader)?),
+            CasBackend::Record => {
+                let path = transcript()?;
+                info!(path = %path.display(), "Recording the CAS transcript");
+                Self::Record(RecordingCasModule::create(
+                    PcscCasModule::open(reader)?,
+                    path,
+                )?)
+            }
+            CasBackend::Replay => {
+                let path = transcript()?;
+                info!(path = %path.display(), "Replaying the CAS transcript");
+                Self::Replay(ReplayCasModule::open(path)?)
+            }
+            CasBackend::KeyLog => bail!("The `key_log` backend descrambles without a CAS module"),
+        })
+    }
+
+    fn inner(&self) -> &dyn chibitv_b61::CasModule {
+        match self {
+            Self::Pcsc(module) => module,
+            Self::Record(module) => module,
+            Self::Replay(module) => module,
+        }
+    }
+}
+
+impl chibitv_b25::CasModule for CasModule {
+    fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        self.inner().transmit(command, response)
+    }
 
+    fn generation(&self) -> u64 {
+        self.inner().generation()
+    }
+}
+
+impl chibitv_b61::CasModule for CasModule {
+    fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        self.inner().transmit(command, response)
+    }
+
+    fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
+        self.inner().lock()
+    }
+
+    fn generation(&self) -> u64 {
+        self.inner().generation()
+    }
+
+    fn a0_init(&self, ecm: &[u8]) -> Option<[u8; 8]> {
+        self.inner().a0_init(ecm)
+    }
+}
+
+/// The card in a PC/SC reader.
+///
+/// A card that was reset or removed, or a PC/SC service that restarted, is connected to again on
+/// the next command. That command still fails, and [`generation`](chibitv_b25::CasModule::generation)
+/// tells the descramblers to make the initial setting again before sending it once more.
 pub struct PcscCasModule {
+    reader: Option<String>,
     card: Mutex<Card>,
+    generation: AtomicU64,
 }
 
 struct PcscCasModuleGuard<'a> {
+    module: &'a PcscCasModule,
     card: MutexGuard<'a, Card>,
 }
 
 impl PcscCasModuleGuard<'_> {
     fn transmit(&mut self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
-        Ok(self.card.transmit(command, response)?.len())
+        match self.card.transmit(command, response) {
+            Ok(response) => Ok(response.len()),
+            Err(error) if is_disconnected(error) => {
+                warn!(%error, "CAS card is gone, connecting to it again");
+                *self.card = connect(self.module.reader.as_deref())?;
+                self.module.generation.fetch_add(1, Ordering::Relaxed);
+                info!("Connected to the CAS card again");
+
+                Err(error.into())
+            }
+            Err(error) => Err(error.into()),
+        }
     }
 }
 
-impl PcscCasModule {
-    pub fn open() -> anyhow::Result<Self> {
-        let context = Context::establish(Scope::System)?;
-        let mut readers = vec![0u8; 4096];
-        let Some(reader_name) = context.list_readers(&mut readers)?.next() else {
-            bail!("CAS reader not found");
-        };
-
-        debug!(
-            reader = %String::from_utf8_lossy(reader_name.to_bytes()),
-            "Opening CAS module"
-        );
-        let card = context.connect(reader_name, ShareMode::Shared, Protocols::ANY)?;
+/// Whether the card has to be connected to again before it takes another command.
+fn is_disconnected(error: pcsc::Error) -> bool {
+    matches!(
+        error,
+        pcsc::Error::ResetCard
+            | pcsc::Error::RemovedCard
+            | pcsc::Error::NoSmartcard
+            | pcsc::Error::ReaderUnavailable
+            | pcsc::Error::NoService
+            | pcsc::Error::ServiceStopped
+            | pcsc::Error::InvalidHandle
+    )
+}
 
+impl PcscCasModule {
+    /// Connects to the card in the reader, or in the first reader found on `None`.
+    ///
+    /// A reader is named by its exact name, or else by a part of it.
+    pub fn open(reader: Option<&str>) -> anyhow::Result<Self> {
         Ok(Self {
-            card: Mutex::new(card),
+            reader: reader.map(str::to_string),
+            card: Mutex::new(connect(reader)?),
+            generation: AtomicU64::new(0),
         })
     }
 
-    pub fn open_shared() -> anyhow::Result<Arc<Self>> {
-        Ok(Arc::new(Self::open()?))
+    fn lock_card(&self) -> anyhow::Result<PcscCasModuleGuard<'_>> {
+        let card = self
+            .card
+            .lock()
+            .map_err(|_| anyhow::anyhow!("CAS module lock is poisoned"))?;
+
+        Ok(PcscCasModuleGuard { module: self, card })
     }
 }
 
+/// Establishes a PC/SC context of its own and connects to the card in the reader.
+fn connect(reader: Option<&str>) -> anyhow::Result<Card> {
+    let context = Context::establish(Scope::System)?;
+    let mut readers = vec![0u8; 4096];
+    let names = context.list_readers(&mut readers)?.collect::<Vec<_>>();
+    let Some(reader_name) = (match reader {
+        Some(reader) => find_reader(&names, reader),
+        None => names.first().copied(),
+    }) else {
+        match reader {
+            Some(reader) => bail!("CAS reader `{reader}` not found"),
+            None => bail!("CAS reader not found"),
+        }
+    };
+
+    debug!(
+        reader = %String::from_utf8_lossy(reader_name.to_bytes()),
+        "Opening CAS module"
+    );
+    Ok(context.connect(reader_name, ShareMode::Shared, Protocols::ANY)?)
+}
+
+/// The reader of the name, preferring one of the exact name to one merely containing it.
+fn find_reader<'a>(names: &[&'a CStr], name: &str) -> Option<&'a CStr> {
+    let lossy = |reader: &CStr| String::from_utf8_lossy(reader.to_bytes()).into_owned();
+
+    names
+        .iter()
+        .find(|reader| lossy(reader) == name)
+        .or_else(|| names.iter().find(|reader| lossy(reader).contains(name)))
+        .copied()
+}
+
 impl chibitv_b25::CasModule for PcscCasModule {
     fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
-        let card = self
-            .card
-            .lock()
-            .map_err(|_| anyhow::anyhow!("CAS module lock is poisoned"))?;
-        Ok(card.transmit(command, response)?.len())
+        self.lock_card()?.transmit(command, response)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
     }
 }
 
 impl chibitv_b61::CasModule for PcscCasModule {
     fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
-        let mut module = self.lock()?;
-        module.transmit(command, response)
+        self.lock_card()?.transmit(command, response)
     }
 
     fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
-        let card = self
-            .card
-            .lock()
-            .map_err(|_| anyhow::anyhow!("CAS module lock is poisoned"))?;
-        Ok(Box::new(PcscCasModuleGuard { card }))
+        Ok(Box::new(self.lock_card()?))
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
     }
 }
 
@@ -72,3 +220,22 @@ impl chibitv_b61::CasModuleGuard for PcscCasModuleGuard<'_> {
         PcscCasModuleGuard::transmit(self, command, response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_a_reader_by_its_exact_name_first() {
+        let names = [
+            c"SCM Microsystems Inc. SCR 3310 [CCID Interface] 00 00",
+            c"ACS ACR39U ICC Reader 00 00",
+            c"ACS ACR39U",
+        ];
+
+        assert_eq!(find_reader(&names, "ACS ACR39U"), Some(names[2]));
+        assert_eq!(find_reader(&names, "SCR 3310"), Some(names[0]));
+        assert_eq!(find_reader(&names, "ACR39U"), Some(names[1]));
+        assert_eq!(find_reader(&names, "Gemalto"), None);
+    }
+}
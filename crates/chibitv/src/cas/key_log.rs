@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::fmt::Arguments;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, bail};
+use tracing::warn;
+
+/// Appends everything the descramblers are given by the cards to a key log, in the spirit of
+/// `SSLKEYLOGFILE`, one line each:
+///
+/// ```text
+/// B25_SYSTEM <CA system id> <system key> <initial CBC>
+/// B25 <ECM> <odd key> <even key>
+/// B61 <ECM> <odd key> <even key>
+/// ```
+///
+/// Every field is in hex, and lines starting with `#` are comments. [`KeyLogStore`] reads the
+/// log back to descramble without a card.
+pub struct KeyLogWriter {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl KeyLogWriter {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Could not open the key log {}", path.display()))?;
+
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    /// Writes a line, which is not worth failing the descrambling over.
+    fn write_line(&self, line: Arguments) {
+        let mut writer = self.writer.lock().unwrap();
+        // A capture cut short by the process being killed keeps the keys it needs.
+        if let Err(error) = writer
+            .write_fmt(format_args!("{line}\n"))
+            .and_then(|()| writer.flush())
+        {
+            warn!(%error, "Could not write to the key log");
+        }
+    }
+}
+
+impl chibitv_b25::KeyLog for KeyLogWriter {
+    fn log_system_settings(&self, settings: &chibitv_b25::SystemSettings) {
+        self.write_line(format_args!(
+            "B25_SYSTEM {:04x} {} {}",
+            settings.ca_system_id,
+            hex::encode(settings.system_key),
+            hex::encode(settings.init_cbc),
+        ));
+    }
+
+    fn log_key(&self, ecm: &[u8], key: &chibitv_b25::DecryptionKey) {
+        self.write_line(format_args!(
+            "B25 {} {} {}",
+            hex::encode(ecm),
+            hex::encode(key.odd),
+            hex::encode(key.even),
+        ));
+    }
+}
+
+impl chibitv_b61::KeyLog for KeyLogWriter {
+    fn log_key(&self, ecm: &[u8; 148], key: &chibitv_b61::DecryptionKey) {
+        self.write_line(format_args!(
+            "B61 {} {} {}",
+            hex::encode(ecm),
+            hex::encode(key.odd),
+            hex::encode(key.even),
+        ));
+    }
+}
+
+/// The keys of a key log written by [`KeyLogWriter`], which the descramblers take in place of a
+/// card.
+#[derive(Debug, Default)]
+pub struct KeyLogStore {
+    b25_system_settings: Option<chibitv_b25::SystemSettings>,
+    b25_keys: BTreeMap<Vec<u8>, chibitv_b25::DecryptionKey>,
+    b61_keys: BTreeMap<[u8; 148], chibitv_b61::DecryptionKey>,
+}
+
+impl KeyLogStore {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open the key log {}", path.display()))?;
+        let store = Self::read(BufReader::new(file))
+            .with_context(|| format!("Could not read the key log {}", path.display()))?;
+
+        Ok(store)
+    }
+
+    fn read(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut store = Self::default();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_number = index + 1;
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields[..] {
+                [] => {}
+                [label, ..] if label.starts_with('#') => {}
+                ["B25_SYSTEM", ca_system_id, system_key, init_cbc] => {
+                    store.b25_system_settings = Some(chibitv_b25::SystemSettings {
+                        ca_system_id: u16::from_be_bytes(decode(ca_system_id)?),
+                        system_key: decode(system_key)?,
+                        init_cbc: decode(init_cbc)?,
+                    });
+                }
+                ["B25", ecm, odd, even] => {
+                    let key = chibitv_b25::DecryptionKey {
+                        odd: decode(odd)?,
+                        even: decode(even)?,
+                    };
+                    store.b25_keys.insert(hex::decode(ecm)?, key);
+                }
+                ["B61", ecm, odd, even] => {
+                    let key = chibitv_b61::DecryptionKey {
+                        odd: decode(odd)?,
+                        even: decode(even)?,
+                    };
+                    store.b61_keys.insert(decode(ecm)?, key);
+                }
+                _ => bail!("Line {line_number} is not a key log entry"),
+            }
+        }
+
+        Ok(store)
+    }
+}
+
+fn decode<const N: usize>(field: &str) -> anyhow::Result<[u8; N]> {
+    let bytes = hex::decode(field)?;
+    let Ok(bytes) = <[u8; N]>::try_from(bytes.as_slice()) else {
+        bail!("{field} is not {N} bytes long");
+    };
+
+    Ok(bytes)
+}
+
+impl chibitv_b25::KeyStore for KeyLogStore {
+    fn system_settings(&self) -> Option<chibitv_b25::SystemSettings> {
+        self.b25_system_settings
+    }
+
+    fn key(&self, ecm: &[u8]) -> Option<chibitv_b25::DecryptionKey> {
+        self.b25_keys.get(ecm).copied()
+    }
+}
+
+impl chibitv_b61::KeyStore for KeyLogStore {
+    fn key(&self, ecm: &[u8; 148]) -> Option<chibitv_b61::DecryptionKey> {
+        self.b61_keys.get(ecm).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chibitv_b25::{KeyLog as _, KeyStore as _};
+
+    use super::*;
+
+    #[test]
+    fn reads_back_the_keys_it_has_written() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("cas.keys");
+
+        let settings = chibitv_b25::SystemSettings {
+            ca_system_id: 0x0005,
+            system_key: [0x01; 32],
+            init_cbc: [0x02; 8],
+        };
+        let b25_key = chibitv_b25::DecryptionKey {
+            odd: [0x03; 8],
+            even: [0x04; 8],
+        };
+        let b61_key = chibitv_b61::DecryptionKey {
+            odd: [0x05; 16],
+            even: [0x06; 16],
+        };
+
+        let writer = KeyLogWriter::open(&path).unwrap();
+        writer.log_system_settings(&settings);
+        writer.log_key(&[0xAA, 0xBB][..], &b25_key);
+        drop(writer);
+        // Another run appends to the log.
+        let writer = KeyLogWriter::open(&path).unwrap();
+        chibitv_b61::KeyLog::log_key(&writer, &[0xCC; 148], &b61_key);
+        drop(writer);
+
+        let store = KeyLogStore::open(&path).unwrap();
+        assert_eq!(store.system_settings(), Some(settings));
+        assert_eq!(
+            chibitv_b25::KeyStore::key(&store, &[0xAA, 0xBB]),
+            Some(b25_key)
+        );
+        assert_eq!(chibitv_b25::KeyStore::key(&store, &[0xAA]), None);
+        assert_eq!(
+            chibitv_b61::KeyStore::key(&store, &[0xCC; 148]),
+            Some(b61_key)
+        );
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_key_log_entry() {
+        assert!(KeyLogStore::read("# comment\n\nB25 aa 0303030303030303 04\n".as_bytes()).is_err());
+        assert!(KeyLogStore::read("CLIENT_RANDOM aa bb\n".as_bytes()).is_err());
+        assert!(KeyLogStore::read("# comment\n\n".as_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use anyhow::{Context, bail};
+use chibitv_b25::B25Descrambler;
+use chibitv_b61::Descrambler;
+use chibitv_cas::KeyCache;
+use tracing::{debug, info};
+
+use super::{CasModule, KeyLogStore, KeyLogWriter};
+use crate::config::{CasBackend, CasConfig, CasStandard};
+
+/// The keys of the ECMs, shared by every descrambler of the standard in the process so that
+/// streams of the same network ask a card for each key once.
+static B25_KEY_CACHE: KeyCache<[u8; 16]> = KeyCache::new();
+static B61_KEY_CACHE: KeyCache<chibitv_b61::DecryptionKey> = KeyCache::new();
+
+struct PooledCard {
+    name: String,
+    module: Arc<CasModule>,
+    standards: Vec<CasStandard>,
+}
+
+/// The cards of the configured readers, handed out so that each session descrambles with a card
+/// of its own while there are enough of them.
+pub struct CasPool {
+    cards: Vec<PooledCard>,
+    master_key: [u8; 32],
+    /// Where the keys the cards give are appended to.
+    key_log: Option<Arc<KeyLogWriter>>,
+    /// The keys descrambling in place of the cards with the `key_log` backend.
+    keys: Option<Arc<KeyLogStore>>,
+}
+
+impl CasPool {
+    pub fn open(config: &CasConfig) -> anyhow::Result<Self> {
+        let master_key = config.master_key.into();
+        if config.backend == CasBackend::KeyLog {
+            let path = config
+                .key_log
+                .as_deref()
+                .context("`cas.key_log` is required by the `key_log` backend")?;
+            info!(path = %path.display(), "Descrambling with the keys of the key log");
+            return Ok(Self {
+                cards: Vec::new(),
+                master_key,
+                key_log: None,
+                keys: Some(Arc::new(KeyLogStore::open(path)?)),
+            });
+        }
+
+        let key_log = config
+            .key_log
+            .as_deref()
+            .map(KeyLogWriter::open)
+            .transpose()?
+            .map(Arc::new);
+
+        // A transcript answers for every card it was recorded from.
+        if config.backend == CasBackend::Replay || config.readers.is_empty() {
+            return Ok(Self {
+                cards: vec![PooledCard {
+                    name: "default".to_string(),
+                    module: Arc::new(CasModule::open(config, None)?),
+                    standards: CasStandard::ALL.to_vec(),
+                }],
+                master_key,
+                key_log,
+                keys: None,
+            });
+        }
+
+        if config.backend == CasBackend::Record && config.readers.len() > 1 {
+            bail!("A CAS transcript can only be recorded from a single reader");
+        }
+
+        let cards = config
+            .readers
+            .iter()
+            .map(|reader| {
+                info!(reader = %reader.name, standards = ?reader.standards, "Opening CAS reader");
+                Ok(PooledCard {
+                    name: reader.name.clone(),
+                    module: Arc::new(CasModule::open(config, Some(&reader.name))?),
+                    standards: reader.standards.clone(),
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            cards,
+            master_key,
+            key_log,
+            keys: None,
+        })
+    }
+
+    pub fn open_shared(config: &CasConfig) -> anyhow::Result<Arc<Self>> {
+        Ok(Arc::new(Self::open(config)?))
+    }
+
+    /// Hands out the card for a new session of the standard, the one fewest sessions hold first.
+    ///
+    /// A session keeps the card busy for as long as it holds the module, so that the commands of
+    /// one session do not wait behind those of another while a card is left.
+    fn get(&self, standard: CasStandard) -> anyhow::Result<Arc<CasModule>> {
+        let Some(card) = self
+            .cards
+            .iter()
+            .filter(|card| card.standards.contains(&standard))
+            .min_by_key(|card| Arc::strong_count(&card.module))
+        else {
+            bail!("No CAS reader is configured for {standard}");
+        };
+
+        debug!(reader = %card.name, %standard, "Using CAS card");
+        Ok(Arc::clone(&card.module))
+    }
+
+    /// A descrambler of a new ARIB STD-B25 session, with a card of the pool or the keys of the
+    /// key log.
+    pub fn b25_descrambler(&self) -> anyhow::Result<B25Descrambler> {
+        if let Some(keys) = &self.keys {
+            return B25Descrambler::init_keyless(Arc::clone(keys) as _, &B25_KEY_CACHE);
+        }
+
+        let key_log = self
+            .key_log
+            .clone()
+            .map(|key_log| -> Arc<dyn chibitv_b25::KeyLog> { key_log });
+        B25Descrambler::init(self.get(CasStandard::B25)?, key_log, &B25_KEY_CACHE)
+    }
+
+    /// A descrambler of a new ARIB STD-B61 session, with a card of the pool or the keys of the
+    /// key log.
+    pub fn b61_descrambler(&self, is_async: bool) -> anyhow::Result<Descrambler> {
+        if let Some(keys) = &self.keys {
+            return Ok(Descrambler::init_keyless(
+                Arc::clone(keys) as _,
+                &B61_KEY_CACHE,
+                is_async,
+            ));
+        }
+
+        let key_log = self
+            .key_log
+            .clone()
+            .map(|key_log| -> Arc<dyn chibitv_b61::KeyLog> { key_log });
+        Descrambler::init(
+            self.get(CasStandard::B61)?,
+            self.master_key,
+            key_log,
+            &B61_KEY_CACHE,
+            is_async,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cas::ReplayCasModule;
+
+    fn card(name: &str, standards: &[CasStandard]) -> PooledCard {
+        let transcript = tempfile::NamedTempFile::new().unwrap();
+
+        PooledCard {
+            name: name.to_string(),
+            module: Arc::new(CasModule::Replay(
+                ReplayCasModule::open(transcript.path()).unwrap(),
+            )),
+            standards: standards.to_vec(),
+        }
+    }
+
+    #[test]
+    fn hands_out_the_least_used_card_of_the_standard() {
+        let pool = CasPool {
+            cards: vec![
+                card("B-CAS", &[CasStandard::B25]),
+                card("ACAS", &CasStandard::ALL),
+            ],
+            master_key: [0; 32],
+            key_log: None,
+            keys: None,
+        };
+        let is =
+            |module: &Arc<CasModule>, index: usize| Arc::ptr_eq(module, &pool.cards[index].module);
+
+        let acas = pool.get(CasStandard::B61).unwrap();
+        assert!(is(&acas, 1));
+        let first = pool.get(CasStandard::B25).unwrap();
+        assert!(is(&first, 0));
+        // Both cards are held by a session, so the one configured first is shared.
+        let second = pool.get(CasStandard::B25).unwrap();
+        assert!(is(&second, 0));
+
+        drop(acas);
+        let third = pool.get(CasStandard::B25).unwrap();
+        assert!(is(&third, 1));
+    }
+
+    #[test]
+    fn fails_without_a_card_of_the_standard() {
+        let pool = CasPool {
+            cards: vec![card("B-CAS", &[CasStandard::B25])],
+            master_key: [0; 32],
+            key_log: None,
+            keys: None,
+        };
+
+        assert!(pool.get(CasStandard::B61).is_err());
+    }
+}
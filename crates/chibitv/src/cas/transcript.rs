@@ -0,0 +1,677 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{Context, bail};
+
+/// Every command sent to a CAS module and what it answered, one line each:
+///
+/// ```text
+/// > 90340000...
+/// < 0015...
+/// ```
+///
+/// A command the module failed is followed by `! <error>` instead of its response.
+struct TranscriptWriter {
+    writer: BufWriter<File>,
+}
+
+impl TranscriptWriter {
+    fn write(
+        &mut self,
+        command: &[u8],
+        response: Result<&[u8], &anyhow::Error>,
+    ) -> anyhow::Result<()> {
+        writeln!(self.writer, "> {}", hex::encode(command))?;
+        match response {
+            Ok(response) => writeln!(self.writer, "< {}", hex::encode(response))?,
+            Err(error) => writeln!(self.writer, "! {error}")?,
+        }
+        // A capture cut short by the process being killed keeps what it has exchanged so far.
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Passes every command to a CAS module, writing the exchange to a transcript that
+/// [`ReplayCasModule`] replays later without the card.
+pub struct RecordingCasModule<M> {
+    inner: M,
+    transcript: Mutex<TranscriptWriter>,
+}
+
+impl<M: chibitv_b61::CasModule> RecordingCasModule<M> {
+    pub fn create(inner: M, path: &Path) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Could not create the CAS transcript {}", path.display()))?;
+
+        Ok(Self {
+            inner,
+            transcript: Mutex::new(TranscriptWriter {
+                writer: BufWriter::new(file),
+            }),
+        })
+    }
+}
+
+fn record(
+    transcript: &Mutex<TranscriptWriter>,
+    command: &[u8],
+    response: &mut [u8],
+    result: anyhow::Result<usize>,
+) -> anyhow::Result<usize> {
+    let mut transcript = transcript
+        .lock()
+        .map_err(|_| anyhow::anyhow!("CAS transcript lock is poisoned"))?;
+    match result {
+        Ok(len) => {
+            transcript.write(command, Ok(&response[..len]))?;
+            Ok(len)
+        }
+        Err(error) => {
+            transcript.write(command, Err(&error))?;
+            Err(error)
+        }
+    }
+}
+
+impl<M: chibitv_b61::CasModule> chibitv_b25::CasModule for RecordingCasModule<M> {
+    fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        chibitv_b61::CasModule::transmit(self, command, response)
+    }
+
+    fn generation(&self) -> u64 {
+        self.inner.generation()
+    }
+}
+
+impl<M: chibitv_b61::CasModule> chibitv_b61::CasModule for RecordingCasModule<M> {
+    fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        let result = self.inner.transmit(command, response);
+        record(&self.transcript, command, response, result)
+    }
+
+    fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
+        Ok(Box::new(RecordingCasModuleGuard {
+            inner: self.inner.lock()?,
+            transcript: &self.transcript,
+        }))
+    }
+
+    fn generation(&self) -> u64 {
+        self.inner.generation()
+    }
+
+    fn a0_init(&self, ecm: &[u8]) -> Option<[u8; 8]> {
+        self.inner.a0_init(ecm)
+    }
+}
+
+struct RecordingCasModuleGuard<'a> {
+    inner: Box<dyn chibitv_b61::CasModuleGuard + 'a>,
+    transcript: &'a Mutex<TranscriptWriter>,
+}
+
+impl chibitv_b61::CasModuleGuard for RecordingCasModuleGuard<'_> {
+    fn transmit(&mut self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        let result = self.inner.transmit(command, response);
+        record(self.transcript, command, response, result)
+    }
+}
+
+/// The instruction of the STD-B61 command protecting the scrambling key of the ECM following it
+/// with an A0init of the descrambler.
+const SCRAMBLING_KEY_PROTECTION_SETTING: u8 = 0xA0;
+const ECM_RECEPTION: u8 = 0x34;
+
+/// The data of a command of the instruction, following its CLA, INS, P1, P2 and Lc.
+fn command_data(command: &[u8], instruction: u8) -> Option<&[u8]> {
+    let ([_, ins, _, _, lc], data) = command.split_first_chunk()?;
+    if *ins != instruction {
+        return None;
+    }
+
+    data.get(..usize::from(*lc))
+}
+
+/// What was recorded for one command, in the order the card was sent it.
+#[derive(Debug)]
+struct Recorded<T> {
+    queue: VecDeque<T>,
+}
+
+impl<T> Default for Recorded<T> {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl<T: Clone> Recorded<T> {
+    /// Takes the next one, repeating the last one once they run out, since a stream replayed for
+    /// longer than it was recorded sends the same ECM again.
+    fn next(&mut self) -> Option<T> {
+        if self.queue.len() > 1 {
+            self.queue.pop_front()
+        } else {
+            self.queue.front().cloned()
+        }
+    }
+}
+
+/// The responses recorded for one command.
+type Responses = Recorded<Result<Vec<u8>, String>>;
+
+/// Answers commands from a transcript written by [`RecordingCasModule`], without a card.
+///
+/// Each command is answered with the responses recorded for the same command in turn, so
+/// commands interleaved differently from the recording, as those of concurrent streams are,
+/// are still answered alike. A STD-B61 descrambler is given the A0inits its ECMs were recorded
+/// with, so that it sends the same commands again.
+pub struct ReplayCasModule {
+    responses: Mutex<BTreeMap<Vec<u8>, Responses>>,
+    /// The A0inits of the scrambling key protection settings preceding each ECM.
+    a0_inits: Mutex<BTreeMap<Vec<u8>, Recorded<[u8; 8]>>>,
+}
+
+impl ReplayCasModule {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Could not open the CAS transcript {}", path.display()))?;
+        let transcript = Self::read(BufReader::new(file))
+            .with_context(|| format!("Could not read the CAS transcript {}", path.display()))?;
+
+        Ok(transcript)
+    }
+
+    fn read(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut responses = BTreeMap::<Vec<u8>, Responses>::new();
+        let mut a0_inits = BTreeMap::<Vec<u8>, Recorded<[u8; 8]>>::new();
+        let mut command = None;
+        let mut a0_init = None;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_number = index + 1;
+            let response = match line.split_at_checked(2) {
+                Some(("> ", hex)) => {
+                    if command.is_some() {
+                        bail!("Line {line_number} follows a command without a response");
+                    }
+                    command = Some(hex::decode(hex)?);
+                    continue;
+                }
+                Some(("< ", hex)) => Ok(hex::decode(hex)?),
+                Some(("! ", error)) => Err(error.to_string()),
+                _ if line.is_empty() => continue,
+                _ => bail!("Line {line_number} is neither a command nor a response"),
+            };
+            let Some(command) = command.take() else {
+                bail!("Line {line_number} answers no command");
+            };
+
+            let setting_a0_init = a0_init.take();
+            if let Some(data) = command_data(&command, SCRAMBLING_KEY_PROTECTION_SETTING) {
+                a0_init = data.get(8..16).and_then(|a0_init| a0_init.try_into().ok());
+            } else if let Some(ecm) = command_data(&command, ECM_RECEPTION)
+                && let Some(a0_init) = setting_a0_init
+            {
+                a0_inits
+                    .entry(ecm.to_vec())
+                    .or_default()
+                    .queue
+                    .push_back(a0_init);
+            }
+
+            responses
+                .entry(command)
+                .or_default()
+                .queue
+                .push_back(response);
+        }
+        if command.is_some() {
+            bail!("The last command has no response");
+        }
+
+        Ok(Self {
+            responses: Mutex::new(responses),
+            a0_inits: Mutex::new(a0_inits),
+        })
+    }
+
+    fn responses(&self) -> anyhow::Result<MutexGuard<'_, BTreeMap<Vec<u8>, Responses>>> {
+        self.responses
+            .lock()
+            .map_err(|_| anyhow::anyhow!("CAS transcript lock is poisoned"))
+    }
+}
+
+fn replay(
+    responses: &mut BTreeMap<Vec<u8>, Responses>,
+    command: &[u8],
+    response: &mut [u8],
+) -> anyhow::Result<usize> {
+    let Some(recorded) = responses.get_mut(command).and_then(Responses::next) else {
+        bail!(
+            "The CAS transcript has no response to the command {}",
+            hex::encode(command)
+        );
+    };
+    let recorded = recorded.map_err(|error| anyhow::anyhow!(error))?;
+    if recorded.len() > response.len() {
+        bail!(
+            "The recorded response of {} bytes does not fit in {} bytes",
+            recorded.len(),
+            response.len()
+        );
+    }
+    response[..recorded.len()].copy_from_slice(&recorded);
+
+    Ok(recorded.len())
+}
+
+impl chibitv_b25::CasModule for ReplayCasModule {
+    fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        replay(&mut self.responses()?, command, response)
+    }
+}
+
+impl chibitv_b61::CasModule for ReplayCasModule {
+    fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        replay(&mut self.responses()?, command, response)
+    }
+
+    fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
+        Ok(Box::new(ReplayCasModuleGuard {
+            responses: self.responses()?,
+        }))
+    }
+
+    fn a0_init(&self, ecm: &[u8]) -> Option<[u8; 8]> {
+        self.a0_inits.lock().ok()?.get_mut(ecm)?.next()
+    }
+}
+
+struct ReplayCasModuleGuard<'a> {
+    responses: MutexGuard<'a, BTreeMap<Vec<u8>, Responses>>,
+}
+
+impl chibitv_b61::CasModuleGuard for ReplayCasModuleGuard<'_> {
+    fn transmit(&mut self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+        replay(&mut self.responses, command, response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    use chibitv_b25::B25Descrambler;
+    use chibitv_b61::{CasModule as _, CasModuleGuard as _, DecryptionKey, Descrambler, KeyLog};
+    use chibitv_cas::KeyCache;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::cas::{CasPool, KeyLogStore};
+    use crate::config::CasConfig;
+
+    /// Answers every command with a counter followed by the command reversed, failing on
+    /// empty commands.
+    #[derive(Default)]
+    struct CountingCasModule {
+        count: AtomicU8,
+    }
+
+    impl CountingCasModule {
+        fn answer(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            if command.is_empty() {
+                bail!("No command");
+            }
+            response[0] = self.count.fetch_add(1, Ordering::Relaxed);
+            for (index, byte) in command.iter().rev().enumerate() {
+                response[index + 1] = *byte;
+            }
+
+            Ok(command.len() + 1)
+        }
+    }
+
+    impl chibitv_b61::CasModule for CountingCasModule {
+        fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            self.answer(command, response)
+        }
+
+        fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
+            Ok(Box::new(CountingCasModuleGuard { module: self }))
+        }
+    }
+
+    struct CountingCasModuleGuard<'a> {
+        module: &'a CountingCasModule,
+    }
+
+    impl chibitv_b61::CasModuleGuard for CountingCasModuleGuard<'_> {
+        fn transmit(&mut self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            self.module.answer(command, response)
+        }
+    }
+
+    fn transmit(module: &impl chibitv_b61::CasModule, command: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut response = [0; 256];
+        let len = module.transmit(command, &mut response)?;
+
+        Ok(response[..len].to_vec())
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("cas.transcript");
+
+        let recorder = RecordingCasModule::create(CountingCasModule::default(), &path).unwrap();
+        assert_eq!(transmit(&recorder, &[1, 2]).unwrap(), [0, 2, 1]);
+        {
+            let mut guard = recorder.lock().unwrap();
+            let mut response = [0; 256];
+            assert_eq!(guard.transmit(&[3], &mut response).unwrap(), 2);
+        }
+        assert_eq!(transmit(&recorder, &[1, 2]).unwrap(), [2, 2, 1]);
+        assert!(transmit(&recorder, &[]).is_err());
+        drop(recorder);
+
+        let replay = ReplayCasModule::open(&path).unwrap();
+        assert_eq!(transmit(&replay, &[3]).unwrap(), [1, 3]);
+        assert_eq!(transmit(&replay, &[1, 2]).unwrap(), [0, 2, 1]);
+        assert_eq!(transmit(&replay, &[1, 2]).unwrap(), [2, 2, 1]);
+        // The last response is repeated once the recorded ones run out.
+        assert_eq!(transmit(&replay, &[1, 2]).unwrap(), [2, 2, 1]);
+        assert!(transmit(&replay, &[]).is_err());
+        assert!(transmit(&replay, &[4]).is_err());
+
+        let mut response = [0; 2];
+        let mut guard = replay.lock().unwrap();
+        assert_eq!(guard.transmit(&[3], &mut response).unwrap(), 2);
+        assert_eq!(response, [1, 3]);
+    }
+
+    const MASTER_KEY: [u8; 32] = [0x5A; 32];
+
+    fn key(ecm: &[u8; 148]) -> DecryptionKey {
+        DecryptionKey {
+            odd: [ecm[0]; 16],
+            even: [!ecm[0]; 16],
+        }
+    }
+
+    /// Answers as a STD-B61 CAS module does, protecting the key of each ECM with a new
+    /// A0response.
+    #[derive(Default)]
+    struct ProtectingCasModule {
+        a0_responses: AtomicU8,
+        kcl: Mutex<Vec<u8>>,
+    }
+
+    impl ProtectingCasModule {
+        fn answer(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            let payload = match command[1] {
+                0x30 => [
+                    &[0x00, 0x0F, 0x00, 0x30, 0x21, 0x00, 0x00, 0x05][..],
+                    &[0x01; 6],
+                    &[0x02, 0x00, 0x00],
+                ]
+                .concat(),
+                SCRAMBLING_KEY_PROTECTION_SETTING => {
+                    let a0_init =
+                        &command_data(command, SCRAMBLING_KEY_PROTECTION_SETTING).unwrap()[8..];
+                    let a0_response = [self.a0_responses.fetch_add(1, Ordering::Relaxed); 8];
+                    let kcl = Sha256::digest([&MASTER_KEY[..], a0_init, &a0_response].concat());
+                    let a0_hash = Sha256::digest([&kcl[..], a0_init].concat());
+                    *self.kcl.lock().unwrap() = kcl.to_vec();
+
+                    [
+                        &[0x00, 0x01, 0x00, 0xA0, 0x21, 0x00][..],
+                        &a0_response,
+                        &a0_hash,
+                    ]
+                    .concat()
+                }
+                ECM_RECEPTION => {
+                    let ecm: [u8; 148] =
+                        command_data(command, ECM_RECEPTION).unwrap().try_into()?;
+                    let key = key(&ecm);
+                    let mut ks =
+                        Sha256::digest([&self.kcl.lock().unwrap()[..], &ecm[0x04..0x1B]].concat())
+                            .to_vec();
+                    for (byte, key) in ks.iter_mut().zip([key.odd, key.even].concat()) {
+                        *byte ^= key;
+                    }
+
+                    [&[0x00, 0x27, 0x00, 0x34, 0x08, 0x00][..], &ks, &[0x00]].concat()
+                }
+                _ => bail!("Unexpected command"),
+            };
+            let payload = [&payload[..], &[0x90, 0x00]].concat();
+            response[..payload.len()].copy_from_slice(&payload);
+
+            Ok(payload.len())
+        }
+    }
+
+    impl chibitv_b61::CasModule for ProtectingCasModule {
+        fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            self.answer(command, response)
+        }
+
+        fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
+            Ok(Box::new(ProtectingCasModuleGuard { module: self }))
+        }
+    }
+
+    struct ProtectingCasModuleGuard<'a> {
+        module: &'a ProtectingCasModule,
+    }
+
+    impl chibitv_b61::CasModuleGuard for ProtectingCasModuleGuard<'_> {
+        fn transmit(&mut self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            self.module.answer(command, response)
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryKeyLog {
+        keys: Mutex<Vec<DecryptionKey>>,
+    }
+
+    impl KeyLog for MemoryKeyLog {
+        fn log_key(&self, _ecm: &[u8; 148], key: &DecryptionKey) {
+            self.keys.lock().unwrap().push(key.clone());
+        }
+    }
+
+    /// Decrypts the ECMs with a descrambler of the module, returning the keys it decrypted.
+    fn decrypt_ecms(
+        module: impl chibitv_b61::CasModule + 'static,
+        ecms: &[[u8; 148]],
+    ) -> anyhow::Result<Vec<DecryptionKey>> {
+        let key_log = Arc::new(MemoryKeyLog::default());
+        // A cache of its own, or the keys the recording decrypted would spare the replay.
+        let key_cache: &'static KeyCache<DecryptionKey> = Box::leak(Box::default());
+        let mut descrambler = Descrambler::init(
+            Arc::new(module),
+            MASTER_KEY,
+            Some(key_log.clone()),
+            key_cache,
+            false,
+        )?;
+        for ecm in ecms {
+            descrambler.push_ecm(*ecm)?;
+        }
+
+        Ok(key_log.keys.lock().unwrap().clone())
+    }
+
+    #[test]
+    fn replays_the_ecms_of_a_b61_descrambler() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("cas.transcript");
+        let ecms = [[0x01; 148], [0x02; 148]];
+
+        let recorder = RecordingCasModule::create(ProtectingCasModule::default(), &path).unwrap();
+        assert_eq!(
+            decrypt_ecms(recorder, &ecms).unwrap(),
+            [key(&ecms[0]), key(&ecms[1])]
+        );
+
+        // The ECMs are protected with the A0inits they were recorded with, in whatever order
+        // they come.
+        let replay = ReplayCasModule::open(&path).unwrap();
+        assert_eq!(
+            decrypt_ecms(replay, &[ecms[1], ecms[0], ecms[1]]).unwrap(),
+            [key(&ecms[1]), key(&ecms[0]), key(&ecms[1])]
+        );
+
+        let replay = ReplayCasModule::open(&path).unwrap();
+        assert!(decrypt_ecms(replay, &[[0x03; 148]]).is_err());
+    }
+
+    /// A replaying pool of the transcript, appending the keys its descramblers are given to the
+    /// key log.
+    fn replaying_pool(transcript: &Path, key_log: &Path) -> CasPool {
+        let config = toml::from_str::<CasConfig>(&format!(
+            "master_key = \"{}\"\nbackend = \"replay\"\ntranscript = {:?}\nkey_log = {:?}",
+            hex::encode(MASTER_KEY),
+            transcript,
+            key_log,
+        ))
+        .unwrap();
+
+        CasPool::open(&config).unwrap()
+    }
+
+    #[test]
+    fn replays_the_ecms_of_a_b61_descrambler_of_the_pool() {
+        let directory = tempfile::tempdir().unwrap();
+        let transcript = directory.path().join("cas.transcript");
+        let key_log = directory.path().join("cas.keys");
+        // The ECMs differ from those of the other tests, since the pool shares its key cache.
+        let ecms = [[0x11; 148], [0x12; 148]];
+
+        let recorder =
+            RecordingCasModule::create(ProtectingCasModule::default(), &transcript).unwrap();
+        decrypt_ecms(recorder, &ecms).unwrap();
+
+        let pool = replaying_pool(&transcript, &key_log);
+        let mut descrambler = pool.b61_descrambler(false).unwrap();
+        for ecm in ecms {
+            descrambler.push_ecm(ecm).unwrap();
+        }
+        drop(descrambler);
+
+        let keys = KeyLogStore::open(&key_log).unwrap();
+        for ecm in &ecms {
+            assert_eq!(chibitv_b61::KeyStore::key(&keys, ecm), Some(key(ecm)));
+        }
+    }
+
+    /// Answers as an ARIB STD-B25 CAS module does, giving keys taken from each ECM.
+    struct B25CasModule;
+
+    impl B25CasModule {
+        fn answer(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            let payload = match command[1] {
+                0x30 => [
+                    &[0x00, 0x39, 0x00, 0x30, 0x21, 0x00, 0x00, 0x05][..],
+                    &[0x01; 6],
+                    &[0x02, 0x00],
+                    &[0x03; 32],
+                    &[0x04; 8],
+                    &[0x00],
+                ]
+                .concat(),
+                ECM_RECEPTION => {
+                    let key = b25_key(command_data(command, ECM_RECEPTION).unwrap());
+                    [
+                        &[0x00, 0x15, 0x00, 0x34, 0x08, 0x00][..],
+                        &key.odd,
+                        &key.even,
+                        &[0x00],
+                    ]
+                    .concat()
+                }
+                _ => bail!("Unexpected command"),
+            };
+            let payload = [&payload[..], &[0x90, 0x00]].concat();
+            response[..payload.len()].copy_from_slice(&payload);
+
+            Ok(payload.len())
+        }
+    }
+
+    impl chibitv_b61::CasModule for B25CasModule {
+        fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            self.answer(command, response)
+        }
+
+        fn lock(&self) -> anyhow::Result<Box<dyn chibitv_b61::CasModuleGuard + '_>> {
+            bail!("A STD-B25 descrambler sends no sequence of commands")
+        }
+    }
+
+    fn b25_key(ecm: &[u8]) -> chibitv_b25::DecryptionKey {
+        chibitv_b25::DecryptionKey {
+            odd: [ecm[0]; 8],
+            even: [!ecm[0]; 8],
+        }
+    }
+
+    #[test]
+    fn replays_the_ecms_of_a_b25_descrambler() {
+        let directory = tempfile::tempdir().unwrap();
+        let transcript = directory.path().join("cas.transcript");
+        let key_log = directory.path().join("cas.keys");
+        // The ECMs differ from those of the other tests, since the pool shares its key cache.
+        let ecms = [[0x21; 16], [0x22; 16]];
+
+        let recorder = RecordingCasModule::create(B25CasModule, &transcript).unwrap();
+        // A cache of its own, or the keys the recording decrypted would spare the replay.
+        let key_cache: &'static KeyCache<[u8; 16]> = Box::leak(Box::default());
+        let mut descrambler = B25Descrambler::init(Arc::new(recorder), None, key_cache).unwrap();
+        for ecm in &ecms {
+            descrambler.push_ecm(ecm).unwrap();
+        }
+        drop(descrambler);
+
+        let pool = replaying_pool(&transcript, &key_log);
+        let mut descrambler = pool.b25_descrambler().unwrap();
+        assert_eq!(descrambler.ca_system_id(), 0x0005);
+        for ecm in &ecms {
+            descrambler.push_ecm(ecm).unwrap();
+        }
+        assert!(descrambler.push_ecm(&[0x23; 16]).is_err());
+        drop(descrambler);
+
+        let keys = KeyLogStore::open(&key_log).unwrap();
+        assert_eq!(
+            chibitv_b25::KeyStore::system_settings(&keys),
+            Some(chibitv_b25::SystemSettings {
+                ca_system_id: 0x0005,
+                system_key: [0x03; 32],
+                init_cbc: [0x04; 8],
+            })
+        );
+        for ecm in &ecms {
+            assert_eq!(chibitv_b25::KeyStore::key(&keys, ecm), Some(b25_key(ecm)));
+        }
+    }
+
+    #[test]
+    fn rejects_a_command_without_a_response() {
+        assert!(ReplayCasModule::read("> 0102\n< 0201\n> 03\n".as_bytes()).is_err());
+        assert!(ReplayCasModule::read("< 0201\n".as_bytes()).is_err());
+        assert!(ReplayCasModule::read("> 0102\n< 0201\n\n".as_bytes()).is_ok());
+    }
+}
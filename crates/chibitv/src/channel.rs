@@ -1,14 +1,87 @@
-use crate::config::ChannelConfigInner;
+use anyhow::Context;
+
+use crate::config::{ChannelConfig, ChannelConfigInner, DeliverySystem, IsdbTLayer};
+
+pub const FIRST_UHF_CHANNEL: u8 = 13;
+pub const LAST_UHF_CHANNEL: u8 = 52;
+const FIRST_UHF_FREQUENCY_HZ: u32 = 473_142_857;
+pub const UHF_CHANNEL_BANDWIDTH_HZ: u32 = 6_000_000;
+
+/// Every hierarchical layer, as the bit mask `DTV_ISDBT_LAYER_ENABLED` takes.
+pub const ALL_ISDB_T_LAYERS: u8 = 0b111;
+
+pub fn uhf_frequency(channel: u8) -> u32 {
+    FIRST_UHF_FREQUENCY_HZ + u32::from(channel - FIRST_UHF_CHANNEL) * UHF_CHANNEL_BANDWIDTH_HZ
+}
+
+/// The UHF physical channel whose centre is `frequency`.
+pub fn uhf_channel(frequency: u32) -> Option<u8> {
+    let offset = frequency.checked_sub(FIRST_UHF_FREQUENCY_HZ)?;
+    if !offset.is_multiple_of(UHF_CHANNEL_BANDWIDTH_HZ) {
+        return None;
+    }
+
+    let channel = u8::try_from(offset / UHF_CHANNEL_BANDWIDTH_HZ)
+        .ok()?
+        .checked_add(FIRST_UHF_CHANNEL)?;
+    (channel <= LAST_UHF_CHANNEL).then_some(channel)
+}
 
 #[derive(Clone, Debug)]
 pub enum ChannelInner {
-    IsdbS { frequency: u32, stream_id: u32 },
-    IsdbT { frequency: u32, bandwidth_hz: u32 },
+    IsdbS {
+        frequency: u32,
+        stream_id: u32,
+    },
+    IsdbSTs {
+        frequency: u32,
+        transport_stream_id: u16,
+    },
+    IsdbT {
+        frequency: u32,
+        bandwidth_hz: u32,
+        /// The hierarchical layers to receive, with layer A in the lowest bit.
+        layers: u8,
+        /// Frequencies to fall back on when `frequency` cannot be received.
+        alternate_frequencies: Vec<u32>,
+    },
 }
 
-impl From<&ChannelConfigInner> for ChannelInner {
-    fn from(value: &ChannelConfigInner) -> Self {
-        match value {
+impl ChannelInner {
+    pub fn delivery_system(&self) -> DeliverySystem {
+        match self {
+            Self::IsdbS { .. } | Self::IsdbSTs { .. } => DeliverySystem::IsdbS,
+            Self::IsdbT { .. } => DeliverySystem::IsdbT,
+        }
+    }
+
+    /// The frequencies to try tuning in order, the configured one first, each without alternates.
+    pub fn candidates(&self) -> Vec<Self> {
+        match self {
+            Self::IsdbT {
+                frequency,
+                bandwidth_hz,
+                layers,
+                alternate_frequencies,
+            } => std::iter::once(*frequency)
+                .chain(alternate_frequencies.iter().copied())
+                .map(|frequency| Self::IsdbT {
+                    frequency,
+                    bandwidth_hz: *bandwidth_hz,
+                    layers: *layers,
+                    alternate_frequencies: vec![],
+                })
+                .collect(),
+            _ => vec![self.clone()],
+        }
+    }
+}
+
+impl TryFrom<&ChannelConfig> for ChannelInner {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &ChannelConfig) -> anyhow::Result<Self> {
+        Ok(match &value.inner {
             ChannelConfigInner::IsdbS {
                 frequency,
                 stream_id,
@@ -16,14 +89,37 @@ impl From<&ChannelConfigInner> for ChannelInner {
                 frequency: *frequency,
                 stream_id: *stream_id,
             },
+            ChannelConfigInner::IsdbSTs { frequency } => Self::IsdbSTs {
+                frequency: *frequency,
+                transport_stream_id: value.transport_stream_id.with_context(|| {
+                    format!(
+                        "The ISDB-S-TS channel {} has no transport_stream_id",
+                        value.name
+                    )
+                })?,
+            },
             ChannelConfigInner::IsdbT {
                 frequency,
                 bandwidth_hz,
+                layers,
+                alternate_frequencies,
             } => Self::IsdbT {
                 frequency: *frequency,
                 bandwidth_hz: *bandwidth_hz,
+                layers: layers
+                    .iter()
+                    .fold(0, |mask, layer| mask | layer_bit(*layer)),
+                alternate_frequencies: alternate_frequencies.clone(),
             },
-        }
+        })
+    }
+}
+
+fn layer_bit(layer: IsdbTLayer) -> u8 {
+    match layer {
+        IsdbTLayer::A => 0b001,
+        IsdbTLayer::B => 0b010,
+        IsdbTLayer::C => 0b100,
     }
 }
 
@@ -31,5 +127,7 @@ impl From<&ChannelConfigInner> for ChannelInner {
 pub struct Channel {
     pub id: usize,
     pub name: String,
+    /// The transport stream id the channel is expected to carry, if configured.
+    pub transport_stream_id: Option<u16>,
     pub inner: ChannelInner,
 }
@@ -1,12 +1,10 @@
 use std::io::{BufReader, BufWriter, stdout};
 
-use chibitv_b25::B25Descrambler;
-use chibitv_b61::Descrambler;
 use clap::Parser;
 use mpeg2ts::ts::TsPacketWriter;
 use tracing::info;
 
-use crate::cas::PcscCasModule;
+use crate::cas::CasPool;
 use crate::channel::{Channel, ChannelInner};
 use crate::config::Config;
 use crate::demux::Demux;
@@ -14,7 +12,7 @@ use crate::m2ts::{M2tsDemuxer, M2tsMuxer};
 use crate::mmt::MmtDemuxer;
 use crate::remux::{Mux, Remuxer};
 use crate::service_information::{ServiceInformationProcessor, Signal};
-use crate::tuner::Tuners;
+use crate::tuner::{LeasePriority, Tuners};
 
 #[derive(Clone, Debug, Parser)]
 pub struct Options {
@@ -28,15 +26,17 @@ pub async fn live(options: &Options, config: &Config) -> anyhow::Result<()> {
         tuners.add_tuner_from_config(id as u32, tuner)?;
     }
 
-    let tuner = tuners.try_acquire_by_id(0)?;
-
-    let Some(channel) = config.channels.get(options.channel).map(|channel| Channel {
-        id: options.channel,
-        name: channel.name.to_string(),
-        inner: (&channel.inner).into(),
-    }) else {
+    let Some(channel_config) = config.channels.get(options.channel) else {
         anyhow::bail!("Could not find the channel in the config");
     };
+    let channel = Channel {
+        id: options.channel,
+        name: channel_config.name.to_string(),
+        transport_stream_id: channel_config.transport_stream_id,
+        inner: channel_config.try_into()?,
+    };
+
+    let tuner = tuners.acquire(channel.inner.delivery_system(), LeasePriority::Live)?;
 
     info!("Tuning to the channel: {:?}", channel);
 
@@ -48,7 +48,7 @@ pub async fn live(options: &Options, config: &Config) -> anyhow::Result<()> {
     let output = stdout();
     let writer = TsPacketWriter::new(BufWriter::new(output));
     let mux = M2tsMuxer::new(writer);
-    let cas = PcscCasModule::open_shared()?;
+    let cas = CasPool::open(&config.cas)?;
 
     let (signal_tx, mut signal_rx) = tokio::sync::broadcast::channel::<Signal>(1);
 
@@ -62,6 +62,9 @@ pub async fn live(options: &Options, config: &Config) -> anyhow::Result<()> {
                 Signal::EventChanged { event_id, .. } => {
                     info!(event_id, "Event changed");
                 }
+                Signal::Preempted { by } => {
+                    info!(%by, "Tuner was preempted");
+                }
             }
         }
     });
@@ -69,12 +72,12 @@ pub async fn live(options: &Options, config: &Config) -> anyhow::Result<()> {
     let service_information = ServiceInformationProcessor::new(channel.id, None, Some(signal_tx));
     match channel.inner {
         ChannelInner::IsdbS { .. } => {
-            let descrambler = Descrambler::init(cas, config.cas.master_key.into(), false)?;
+            let descrambler = cas.b61_descrambler(false)?;
             let demux = MmtDemuxer::new(BufReader::new(input), descrambler);
             run_live_remuxer(Remuxer::new(demux, mux)?, service_information)
         }
-        ChannelInner::IsdbT { .. } => {
-            let descrambler = B25Descrambler::init(cas)?;
+        ChannelInner::IsdbSTs { .. } | ChannelInner::IsdbT { .. } => {
+            let descrambler = cas.b25_descrambler()?;
             let demux = M2tsDemuxer::new(input, descrambler);
             run_live_remuxer(Remuxer::new(demux, mux)?, service_information)
         }
@@ -6,7 +6,7 @@ use tracing::info;
 
 use crate::channel::Channel;
 use crate::config::Config;
-use crate::tuner::Tuners;
+use crate::tuner::{LeasePriority, Tuners};
 
 #[derive(Clone, Debug, Parser)]
 pub struct Options {
@@ -24,15 +24,17 @@ pub async fn record(options: &Options, config: &Config) -> anyhow::Result<()> {
         tuners.add_tuner_from_config(id as u32, tuner)?;
     }
 
-    let tuner = tuners.try_acquire_by_id(0)?;
-
-    let Some(channel) = config.channels.get(options.channel).map(|channel| Channel {
-        id: options.channel,
-        name: channel.name.to_string(),
-        inner: (&channel.inner).into(),
-    }) else {
+    let Some(channel_config) = config.channels.get(options.channel) else {
         anyhow::bail!("Could not find the channel in the config");
     };
+    let channel = Channel {
+        id: options.channel,
+        name: channel_config.name.to_string(),
+        transport_stream_id: channel_config.transport_stream_id,
+        inner: channel_config.try_into()?,
+    };
+
+    let tuner = tuners.acquire(channel.inner.delivery_system(), LeasePriority::Recording)?;
 
     info!("Tuning to the channel: {:?}", channel);
 
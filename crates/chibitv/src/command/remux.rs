@@ -1,12 +1,10 @@
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write, stdin, stdout};
 
-use chibitv_b25::B25Descrambler;
-use chibitv_b61::Descrambler;
 use clap::{Parser, ValueEnum};
 use mpeg2ts::ts::TsPacketWriter;
 
-use crate::cas::PcscCasModule;
+use crate::cas::CasPool;
 use crate::config::Config;
 use crate::demux::Demux;
 use crate::m2ts::{M2tsDemuxer, M2tsMuxer};
@@ -57,7 +55,7 @@ pub async fn remux(options: &Options, config: &Config) -> anyhow::Result<()> {
 
     match options.input_format.unwrap_or_default() {
         InputFormat::Mmts => remux_mmts(input, options, config),
-        InputFormat::M2ts => remux_m2ts(input, options),
+        InputFormat::M2ts => remux_m2ts(input, options, config),
     }
 }
 
@@ -80,11 +78,7 @@ fn remux_mmts(
     options: &Options,
     config: &Config,
 ) -> anyhow::Result<()> {
-    let descrambler = Descrambler::init(
-        PcscCasModule::open_shared()?,
-        config.cas.master_key.into(),
-        false,
-    )?;
+    let descrambler = CasPool::open(&config.cas)?.b61_descrambler(false)?;
     let reader = BufReader::new(input);
     let demux = MmtDemuxer::new(reader, descrambler);
 
@@ -111,8 +105,12 @@ fn remux_mmts(
     }
 }
 
-fn remux_m2ts(input: Box<dyn Read + Send + Sync>, options: &Options) -> anyhow::Result<()> {
-    let descrambler = B25Descrambler::init(PcscCasModule::open_shared()?)?;
+fn remux_m2ts(
+    input: Box<dyn Read + Send + Sync>,
+    options: &Options,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let descrambler = CasPool::open(&config.cas)?.b25_descrambler()?;
     let demux = M2tsDemuxer::new(input, descrambler);
 
     match options.format.unwrap_or_default() {
@@ -1,3 +1,5 @@
+mod satellite;
+
 use std::collections::{BTreeMap, BTreeSet};
 use std::time::{Duration, Instant};
 
@@ -8,19 +10,19 @@ use tracing::{info, warn};
 use chibitv_b10::descriptor::Descriptor;
 use chibitv_b10::table::{Nit, Sdt, ServiceInformation, Table};
 use chibitv_b24::decode as decode_b24;
-use chibitv_b25::B25Descrambler;
 
-use crate::cas::PcscCasModule;
-use crate::channel::{Channel, ChannelInner};
-use crate::config::{ChannelConfig, ChannelConfigInner, Config, ServiceConfig};
+use crate::cas::CasPool;
+use crate::channel::{
+    ALL_ISDB_T_LAYERS, Channel, ChannelInner, FIRST_UHF_CHANNEL, LAST_UHF_CHANNEL,
+    UHF_CHANNEL_BANDWIDTH_HZ, uhf_frequency,
+};
+use crate::config::{
+    ChannelConfig, ChannelConfigInner, Config, DeliverySystem, IsdbTLayer, ServiceConfig,
+};
 use crate::demux::{Demux, Packet, SignalingEvent};
 use crate::m2ts::M2tsDemuxer;
-use crate::tuner::Tuners;
-
-const FIRST_UHF_CHANNEL: u8 = 13;
-const LAST_UHF_CHANNEL: u8 = 52;
-const FIRST_UHF_FREQUENCY_HZ: u32 = 473_142_857;
-const UHF_CHANNEL_BANDWIDTH_HZ: u32 = 6_000_000;
+use crate::registry::partial_reception_service_ids;
+use crate::tuner::{LeasePriority, Tuners};
 
 #[derive(Clone, Debug, Parser)]
 pub struct Options {
@@ -32,9 +34,13 @@ pub struct Options {
     #[clap(long, default_value_t = LAST_UHF_CHANNEL)]
     end_channel: u8,
 
-    /// Maximum time in seconds to wait on each UHF channel.
+    /// Maximum time in seconds to wait on each UHF channel or satellite stream.
     #[clap(long, default_value_t = 12)]
     timeout: u64,
+
+    /// Scan the BS and CS110 transponders instead of the UHF channels.
+    #[clap(long)]
+    satellite: bool,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -49,6 +55,28 @@ struct ScanState {
 }
 
 pub async fn scan(options: &Options, config: &Config) -> anyhow::Result<()> {
+    let mut tuners = Tuners::default();
+    for (id, tuner) in config.tuners.iter().enumerate() {
+        tuners.add_tuner_from_config(id as u32, tuner)?;
+    }
+
+    let cas = CasPool::open(&config.cas)?;
+    let channels = if options.satellite {
+        satellite::scan(options, &tuners, &cas)?
+    } else {
+        scan_terrestrial(options, &tuners, &cas)?
+    };
+
+    print!("{}", format_scan_output(&channels));
+
+    Ok(())
+}
+
+fn scan_terrestrial(
+    options: &Options,
+    tuners: &Tuners,
+    cas: &CasPool,
+) -> anyhow::Result<Vec<ChannelConfig>> {
     if options.start_channel < FIRST_UHF_CHANNEL
         || options.end_channel > LAST_UHF_CHANNEL
         || options.start_channel > options.end_channel
@@ -60,31 +88,24 @@ pub async fn scan(options: &Options, config: &Config) -> anyhow::Result<()> {
         );
     }
 
-    let mut tuners = Tuners::default();
-    for (id, tuner) in config.tuners.iter().enumerate() {
-        tuners.add_tuner_from_config(id as u32, tuner)?;
-    }
-
-    if tuners.is_in_use(0).is_none() {
-        anyhow::bail!("No tuners are configured");
-    }
-
     let mut channels = Vec::new();
-    let cas = PcscCasModule::open_shared()?;
     for physical_channel in options.start_channel..=options.end_channel {
         let frequency = uhf_frequency(physical_channel);
         let channel = Channel {
             id: usize::from(physical_channel),
             name: format!("UHF {}", physical_channel),
+            transport_stream_id: None,
             inner: ChannelInner::IsdbT {
                 frequency,
                 bandwidth_hz: UHF_CHANNEL_BANDWIDTH_HZ,
+                layers: ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![],
             },
         };
 
         info!(physical_channel, frequency, "Scanning UHF channel");
 
-        let tuner = tuners.try_acquire_by_id(0)?;
+        let tuner = tuners.acquire(DeliverySystem::IsdbT, LeasePriority::Crawl)?;
         if let Err(error) = tuner.tune(channel) {
             warn!(
                 physical_channel,
@@ -93,12 +114,43 @@ pub async fn scan(options: &Options, config: &Config) -> anyhow::Result<()> {
             continue;
         }
 
-        let descrambler = B25Descrambler::init(cas.clone())?;
+        let descrambler = cas.b25_descrambler()?;
         let mut demux = M2tsDemuxer::new(tuner.open()?, descrambler);
         let mut state = ScanState::default();
         let deadline = Instant::now() + Duration::from_secs(options.timeout);
+        state.read_until_ready(&mut demux, physical_channel, frequency, deadline);
+
+        let Some(name) = state.channel_name() else {
+            continue;
+        };
+
+        channels.push(ChannelConfig {
+            name,
+            transport_stream_id: state.transport_stream_id,
+            services: state.service_configs(),
+            inner: ChannelConfigInner::IsdbT {
+                frequency,
+                bandwidth_hz: UHF_CHANNEL_BANDWIDTH_HZ,
+                layers: IsdbTLayer::ALL.to_vec(),
+                alternate_frequencies: vec![],
+            },
+        });
+    }
+
+    Ok(channels)
+}
 
-        while Instant::now() < deadline && !state.is_ready() {
+impl ScanState {
+    /// Reads the tables of the transport stream until the NIT and the whole SDT are there, or
+    /// the deadline passes.
+    fn read_until_ready(
+        &mut self,
+        demux: &mut impl Demux,
+        physical_channel: u8,
+        frequency: u32,
+        deadline: Instant,
+    ) {
+        while Instant::now() < deadline && !self.is_ready() {
             let packet = match demux.next_packet() {
                 Ok(Some(packet)) => packet,
                 Ok(None) => break,
@@ -115,34 +167,10 @@ pub async fn scan(options: &Options, config: &Config) -> anyhow::Result<()> {
                 continue;
             };
 
-            state.read_table(physical_channel, table_id, table);
-
-            if state.is_ready() {
-                break;
-            }
+            self.read_table(physical_channel, table_id, table);
         }
-
-        let Some(name) = state.channel_name() else {
-            continue;
-        };
-
-        channels.push(ChannelConfig {
-            name,
-            transport_stream_id: state.transport_stream_id,
-            services: state.service_configs(),
-            inner: ChannelConfigInner::IsdbT {
-                frequency,
-                bandwidth_hz: UHF_CHANNEL_BANDWIDTH_HZ,
-            },
-        });
     }
 
-    print!("{}", format_scan_output(&channels));
-
-    Ok(())
-}
-
-impl ScanState {
     fn is_ready(&self) -> bool {
         self.nit.is_some()
             && self.sdt_last_section_number.is_some_and(|last_section| {
@@ -205,11 +233,22 @@ impl ScanState {
     }
 
     fn service_configs(&self) -> Vec<ServiceConfig> {
+        let one_seg_service_ids = self
+            .nit
+            .iter()
+            .flat_map(partial_reception_service_ids)
+            .collect::<BTreeSet<_>>();
+
         self.services
             .values()
             .filter_map(|service| {
                 let descriptor = service_descriptor(service)?;
-                (descriptor.service_type == Some(0x01)).then_some(ServiceConfig {
+                let is_television = match descriptor.service_type {
+                    Some(0x01) => true,
+                    Some(0xC0) => one_seg_service_ids.contains(&service.service_id),
+                    _ => false,
+                };
+                is_television.then_some(ServiceConfig {
                     id: service.service_id,
                     name: descriptor.service_name,
                     provider_name: descriptor.provider_name,
@@ -226,10 +265,6 @@ struct ServiceDescriptor {
     provider_name: String,
 }
 
-fn uhf_frequency(channel: u8) -> u32 {
-    FIRST_UHF_FREQUENCY_HZ + u32::from(channel - FIRST_UHF_CHANNEL) * UHF_CHANNEL_BANDWIDTH_HZ
-}
-
 fn network_name(nit: &Nit) -> Option<String> {
     nit.descriptors.iter().find_map(|descriptor| {
         let Descriptor::NetworkName(descriptor) = descriptor else {
@@ -287,9 +322,16 @@ fn format_scan_output(channels: &[ChannelConfig]) -> String {
                 table["frequency"] = toml_edit::value(i64::from(frequency));
                 table["stream_id"] = toml_edit::value(i64::from(stream_id));
             }
+            ChannelConfigInner::IsdbSTs { frequency } => {
+                table["delivery_system"] = toml_edit::value("ISDB-S-TS");
+                table["frequency"] = toml_edit::value(i64::from(frequency));
+            }
+            // A scan receives every layer on the frequency it found, which is what `layers` and
+            // `alternate_frequencies` default to.
             ChannelConfigInner::IsdbT {
                 frequency,
                 bandwidth_hz,
+                ..
             } => {
                 table["delivery_system"] = toml_edit::value("ISDB-T");
                 table["frequency"] = toml_edit::value(i64::from(frequency));
@@ -338,6 +380,8 @@ mod tests {
             inner: ChannelConfigInner::IsdbT {
                 frequency: 515_142_857,
                 bandwidth_hz: 6_000_000,
+                layers: IsdbTLayer::ALL.to_vec(),
+                alternate_frequencies: vec![],
             },
         }];
 
@@ -0,0 +1,561 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::io::{BufReader, Chain, Cursor, Read};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use chibitv_b10::descriptor::Descriptor;
+use chibitv_b10::table::Nit;
+use chibitv_b60::descriptor::Descriptor as B60Descriptor;
+use chibitv_b60::message::Message;
+use chibitv_b60::table::{MhSdt, ServiceInformation as MhServiceInformation, Table as B60Table};
+use chibitv_b60::tlv_si::{TlvNit, TlvSiTable};
+
+use super::{Options, ScanState, text_bytes};
+use crate::cas::CasPool;
+use crate::channel::{Channel, ChannelInner};
+use crate::config::{ChannelConfig, ChannelConfigInner, DeliverySystem, ServiceConfig};
+use crate::demux::{Demux, Packet, SignalingEvent};
+use crate::m2ts::M2tsDemuxer;
+use crate::mmt::MmtDemuxer;
+use crate::tuner::{LeasePriority, TunerInput, Tuners};
+
+/// The local oscillator of the LNB receiving the right-hand circular polarisation of BS and
+/// CS110, which turns the downlink frequency into the intermediate one tuners are tuned to.
+const LNB_LOCAL_FREQUENCY_KHZ: u32 = 10_678_000;
+
+const FIRST_BS_FREQUENCY_KHZ: u32 = 1_049_480;
+const BS_TRANSPONDER_SPACING_KHZ: u32 = 38_360;
+const FIRST_CS_FREQUENCY_KHZ: u32 = 1_613_000;
+const CS_TRANSPONDER_SPACING_KHZ: u32 = 40_000;
+
+const MH_SDT_ACTUAL_TABLE_ID: u8 = 0x9F;
+const TLV_NIT_ACTUAL_TABLE_ID: u8 = 0x40;
+
+const TS_PACKET_SIZE: usize = 188;
+const TS_SYNC_BYTE: u8 = 0x47;
+/// Enough of the stream to find the sync bytes of a few TS packets in.
+const PROBE_SIZE: usize = TS_PACKET_SIZE * 4;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Transponder {
+    Bs(u8),
+    Cs(u8),
+}
+
+impl Transponder {
+    /// The odd transponders of BS and the even ones of CS110, which are the right-hand circular
+    /// ones.
+    fn all() -> impl Iterator<Item = Self> {
+        (1..=23)
+            .step_by(2)
+            .map(Self::Bs)
+            .chain((2..=24).step_by(2).map(Self::Cs))
+    }
+
+    fn number(self) -> u8 {
+        match self {
+            Self::Bs(number) | Self::Cs(number) => number,
+        }
+    }
+
+    /// The intermediate frequency in kHz, which is what ISDB-S channels are configured with.
+    fn frequency(self) -> u32 {
+        match self {
+            Self::Bs(number) => {
+                FIRST_BS_FREQUENCY_KHZ + u32::from(number - 1) / 2 * BS_TRANSPONDER_SPACING_KHZ
+            }
+            Self::Cs(number) => {
+                FIRST_CS_FREQUENCY_KHZ + u32::from(number - 2) / 2 * CS_TRANSPONDER_SPACING_KHZ
+            }
+        }
+    }
+}
+
+impl Display for Transponder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bs(number) => write!(f, "BS-{number}"),
+            Self::Cs(number) => write!(f, "ND{number}"),
+        }
+    }
+}
+
+enum StreamFormat {
+    Ts,
+    Tlv,
+}
+
+/// Tunes to each transponder and lists the channels on it: the transport streams the NIT places
+/// there, or the TLV streams the TLV-NIT places there for 4K.
+pub(super) fn scan(
+    options: &Options,
+    tuners: &Tuners,
+    cas: &CasPool,
+) -> anyhow::Result<Vec<ChannelConfig>> {
+    let timeout = Duration::from_secs(options.timeout);
+    let mut channels = Vec::new();
+
+    for transponder in Transponder::all() {
+        info!(
+            %transponder,
+            frequency = transponder.frequency(),
+            "Scanning satellite transponder"
+        );
+
+        // The first stream of the transponder, by its relative number. The tuner does not tell a
+        // transport stream from a TLV stream, so either is tuned alike.
+        let first = ChannelInner::IsdbS {
+            frequency: transponder.frequency(),
+            stream_id: 0,
+        };
+        let Some(input) = open_stream(tuners, transponder, first)? else {
+            continue;
+        };
+        let Some((format, input)) = probe(transponder, input) else {
+            continue;
+        };
+
+        match format {
+            StreamFormat::Ts => {
+                let descrambler = cas.b25_descrambler()?;
+                let mut demux = M2tsDemuxer::new(input, descrambler);
+                let mut state = ScanState::default();
+                state.read_until_ready(
+                    &mut demux,
+                    transponder.number(),
+                    transponder.frequency(),
+                    Instant::now() + timeout,
+                );
+                // Releases the tuner for the other streams of the transponder.
+                drop(demux);
+
+                channels.extend(scan_transport_streams(
+                    tuners,
+                    cas,
+                    transponder,
+                    state,
+                    timeout,
+                )?);
+            }
+            StreamFormat::Tlv => {
+                let descrambler = cas.b61_descrambler(false)?;
+                let mut demux = MmtDemuxer::new(BufReader::new(input), descrambler);
+                let mut state = TlvScanState::default();
+                state.read_until_ready(&mut demux, transponder, Instant::now() + timeout);
+                // Releases the tuner for the other streams of the transponder.
+                drop(demux);
+
+                channels.extend(scan_tlv_streams(tuners, cas, transponder, state, timeout)?);
+            }
+        }
+    }
+
+    Ok(channels)
+}
+
+/// Lists the transport streams of the transponder, given the state read from the first of them.
+fn scan_transport_streams(
+    tuners: &Tuners,
+    cas: &CasPool,
+    transponder: Transponder,
+    first: ScanState,
+    timeout: Duration,
+) -> anyhow::Result<Vec<ChannelConfig>> {
+    let Some(first_transport_stream_id) = first.transport_stream_id else {
+        return Ok(vec![]);
+    };
+
+    let others = first
+        .nit
+        .iter()
+        .flat_map(|nit| transport_stream_ids_at(nit, transponder.frequency()))
+        .filter(|transport_stream_id| *transport_stream_id != first_transport_stream_id)
+        .collect::<BTreeSet<_>>();
+
+    let mut channels = Vec::from_iter(ts_channel_config(transponder, &first));
+    for transport_stream_id in others {
+        let inner = ChannelInner::IsdbSTs {
+            frequency: transponder.frequency(),
+            transport_stream_id,
+        };
+        let Some(input) = open_stream(tuners, transponder, inner)? else {
+            continue;
+        };
+
+        let descrambler = cas.b25_descrambler()?;
+        let mut demux = M2tsDemuxer::new(input, descrambler);
+        let mut state = ScanState::default();
+        state.read_until_ready(
+            &mut demux,
+            transponder.number(),
+            transponder.frequency(),
+            Instant::now() + timeout,
+        );
+
+        if state.transport_stream_id != Some(transport_stream_id) {
+            warn!(
+                %transponder,
+                transport_stream_id, "Could not receive the transport stream in the NIT"
+            );
+            continue;
+        }
+
+        channels.extend(ts_channel_config(transponder, &state));
+    }
+
+    Ok(channels)
+}
+
+/// Lists the TLV streams of the transponder, given the state read from the first of them.
+fn scan_tlv_streams(
+    tuners: &Tuners,
+    cas: &CasPool,
+    transponder: Transponder,
+    first: TlvScanState,
+    timeout: Duration,
+) -> anyhow::Result<Vec<ChannelConfig>> {
+    let Some(first_tlv_stream_id) = first.tlv_stream_id else {
+        return Ok(vec![]);
+    };
+
+    let others = first
+        .nit
+        .iter()
+        .flat_map(|nit| tlv_stream_ids_at(nit, transponder.frequency()))
+        .filter(|tlv_stream_id| *tlv_stream_id != first_tlv_stream_id)
+        .collect::<BTreeSet<_>>();
+
+    let mut channels = Vec::from_iter(first.channel_config(transponder));
+    for tlv_stream_id in others {
+        let inner = ChannelInner::IsdbS {
+            frequency: transponder.frequency(),
+            stream_id: u32::from(tlv_stream_id),
+        };
+        let Some(input) = open_stream(tuners, transponder, inner)? else {
+            continue;
+        };
+
+        let descrambler = cas.b61_descrambler(false)?;
+        let mut demux = MmtDemuxer::new(BufReader::new(input), descrambler);
+        let mut state = TlvScanState::default();
+        state.read_until_ready(&mut demux, transponder, Instant::now() + timeout);
+
+        if state.tlv_stream_id != Some(tlv_stream_id) {
+            warn!(
+                %transponder,
+                tlv_stream_id, "Could not receive the TLV stream in the TLV-NIT"
+            );
+            continue;
+        }
+
+        channels.extend(state.channel_config(transponder));
+    }
+
+    Ok(channels)
+}
+
+fn ts_channel_config(transponder: Transponder, state: &ScanState) -> Option<ChannelConfig> {
+    let transport_stream_id = state.transport_stream_id?;
+
+    Some(ChannelConfig {
+        name: state.channel_name()?,
+        transport_stream_id: Some(transport_stream_id),
+        services: state.service_configs(),
+        inner: ChannelConfigInner::IsdbSTs {
+            frequency: transponder.frequency(),
+        },
+    })
+}
+
+/// The transport streams the NIT places at the intermediate frequency.
+fn transport_stream_ids_at(nit: &Nit, frequency: u32) -> impl Iterator<Item = u16> + '_ {
+    nit.transport_streams
+        .iter()
+        .filter(move |transport_stream| is_at(&transport_stream.descriptors, frequency))
+        .map(|transport_stream| transport_stream.transport_stream_id)
+}
+
+/// The TLV streams the TLV-NIT places at the intermediate frequency.
+fn tlv_stream_ids_at(nit: &TlvNit, frequency: u32) -> impl Iterator<Item = u16> + '_ {
+    nit.tlv_streams
+        .iter()
+        .filter(move |tlv_stream| is_at(&tlv_stream.descriptors, frequency))
+        .map(|tlv_stream| tlv_stream.tlv_stream_id)
+}
+
+/// Whether the satellite delivery system descriptors of a stream place it at the intermediate
+/// frequency.
+fn is_at(descriptors: &[Descriptor], frequency: u32) -> bool {
+    descriptors.iter().any(|descriptor| {
+        let Descriptor::SatelliteDeliverySystem(descriptor) = descriptor else {
+            return false;
+        };
+
+        intermediate_frequency(descriptor.frequency) == Some(frequency)
+    })
+}
+
+/// Converts the downlink frequency of a delivery system descriptor, in 10 kHz, to the
+/// intermediate frequency in kHz.
+fn intermediate_frequency(downlink_frequency: u32) -> Option<u32> {
+    downlink_frequency
+        .checked_mul(10)?
+        .checked_sub(LNB_LOCAL_FREQUENCY_KHZ)
+}
+
+fn open_stream(
+    tuners: &Tuners,
+    transponder: Transponder,
+    inner: ChannelInner,
+) -> anyhow::Result<Option<TunerInput>> {
+    let tuner = tuners.acquire(DeliverySystem::IsdbS, LeasePriority::Crawl)?;
+
+    let channel = Channel {
+        id: 0,
+        name: transponder.to_string(),
+        transport_stream_id: None,
+        inner,
+    };
+    if let Err(error) = tuner.tune(channel.clone()) {
+        warn!(
+            %transponder,
+            stream = ?channel.inner,
+            error = %error,
+            "Could not tune to satellite stream"
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(tuner.open()?))
+}
+
+/// Reads the head of the stream to tell whether it is a transport stream or a TLV stream, and
+/// returns the whole stream again.
+fn probe(
+    transponder: Transponder,
+    mut input: TunerInput,
+) -> Option<(StreamFormat, Chain<Cursor<Vec<u8>>, TunerInput>)> {
+    let mut head = vec![0; PROBE_SIZE];
+    if let Err(error) = input.read_exact(&mut head) {
+        warn!(%transponder, error = %error, "Could not read satellite stream");
+        return None;
+    }
+
+    let format = if has_ts_sync(&head) {
+        StreamFormat::Ts
+    } else {
+        StreamFormat::Tlv
+    };
+
+    Some((format, Cursor::new(head).chain(input)))
+}
+
+fn has_ts_sync(head: &[u8]) -> bool {
+    (0..TS_PACKET_SIZE).any(|offset| {
+        (0..3).all(|index| head.get(offset + index * TS_PACKET_SIZE) == Some(&TS_SYNC_BYTE))
+    })
+}
+
+/// What a TLV stream tells about itself in its MH-SDT, and about the network in its TLV-NIT.
+#[derive(Default)]
+struct TlvScanState {
+    nit: Option<TlvNit>,
+    tlv_stream_id: Option<u16>,
+    services: BTreeMap<u16, MhServiceInformation>,
+    sdt_sections: BTreeSet<u8>,
+    sdt_last_section_number: Option<u8>,
+}
+
+impl TlvScanState {
+    fn read_until_ready(
+        &mut self,
+        demux: &mut impl Demux,
+        transponder: Transponder,
+        deadline: Instant,
+    ) {
+        while Instant::now() < deadline && !self.is_ready() {
+            let packet = match demux.next_packet() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(error) => {
+                    warn!(%transponder, error = %error, "Could not read TLV stream");
+                    continue;
+                }
+            };
+
+            match packet {
+                Packet::Signaling(SignalingEvent::B60Message(Message::M2Section(message))) => {
+                    if let B60Table::MhSdt(table) = message.table
+                        && table.table_id == MH_SDT_ACTUAL_TABLE_ID
+                    {
+                        self.read_mh_sdt(transponder, table);
+                    }
+                }
+                Packet::Signaling(SignalingEvent::TlvSiTable(TlvSiTable::TlvNit(nit)))
+                    if nit.table_id == TLV_NIT_ACTUAL_TABLE_ID && self.nit.is_none() =>
+                {
+                    self.nit = Some(nit);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nit.is_some()
+            && self.sdt_last_section_number.is_some_and(|last_section| {
+                self.sdt_sections.len() == usize::from(last_section) + 1
+            })
+            && !self.services.is_empty()
+    }
+
+    fn read_mh_sdt(&mut self, transponder: Transponder, sdt: MhSdt) {
+        self.tlv_stream_id = Some(sdt.tlv_stream_id);
+        self.sdt_sections.insert(sdt.section_number);
+        self.sdt_last_section_number = Some(sdt.last_section_number);
+
+        for service in sdt.services {
+            if !self.services.contains_key(&service.service_id) {
+                let (service_type, service_name, _) =
+                    mh_service_descriptor(&service).unwrap_or_default();
+
+                info!(
+                    %transponder,
+                    tlv_stream_id = sdt.tlv_stream_id,
+                    service_id = service.service_id,
+                    service_type,
+                    service_name,
+                    "Service found"
+                );
+            }
+
+            self.services.insert(service.service_id, service);
+        }
+    }
+
+    fn channel_config(&self, transponder: Transponder) -> Option<ChannelConfig> {
+        let tlv_stream_id = self.tlv_stream_id?;
+        let services = self
+            .services
+            .values()
+            .filter_map(|service| {
+                let (service_type, name, provider_name) = mh_service_descriptor(service)?;
+                (service_type == 0x01).then_some(ServiceConfig {
+                    id: service.service_id,
+                    name,
+                    provider_name,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Some(ChannelConfig {
+            name: services
+                .iter()
+                .map(|service| service.name.clone())
+                .find(|name| !name.is_empty())?,
+            transport_stream_id: None,
+            services,
+            inner: ChannelConfigInner::IsdbS {
+                frequency: transponder.frequency(),
+                stream_id: u32::from(tlv_stream_id),
+            },
+        })
+    }
+}
+
+/// The type, name and provider name of the service from its MH-service descriptor.
+fn mh_service_descriptor(service: &MhServiceInformation) -> Option<(u8, String, String)> {
+    service.descriptors.iter().find_map(|descriptor| {
+        let B60Descriptor::MhService(descriptor) = descriptor else {
+            return None;
+        };
+
+        Some((
+            descriptor.service_type,
+            text_bytes(&descriptor.service_name),
+            text_bytes(&descriptor.service_provider_name),
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_the_intermediate_frequency_of_transponders() {
+        assert_eq!(Transponder::Bs(1).frequency(), 1_049_480);
+        assert_eq!(Transponder::Bs(15).frequency(), 1_318_000);
+        assert_eq!(Transponder::Cs(2).frequency(), 1_613_000);
+        assert_eq!(Transponder::Cs(24).frequency(), 2_053_000);
+        assert_eq!(Transponder::all().count(), 24);
+    }
+
+    #[test]
+    fn converts_the_downlink_frequency_of_the_nit() {
+        // BS-1 at 11.72748 GHz, ND2 at 12.291 GHz.
+        assert_eq!(intermediate_frequency(1_172_748), Some(1_049_480));
+        assert_eq!(intermediate_frequency(1_229_100), Some(1_613_000));
+        assert_eq!(intermediate_frequency(0), None);
+    }
+
+    #[test]
+    fn lists_the_tlv_streams_of_a_transponder() {
+        use chibitv_b10::descriptor::SatelliteDeliverySystemDescriptor;
+        use chibitv_b60::tlv_si::TlvStreamInformation;
+
+        let tlv_stream = |tlv_stream_id, frequency| TlvStreamInformation {
+            tlv_stream_id,
+            original_network_id: 0x000B,
+            descriptors: vec![Descriptor::SatelliteDeliverySystem(
+                SatelliteDeliverySystemDescriptor {
+                    frequency,
+                    orbital_position: 1100,
+                    west_east_flag: true,
+                    polarisation: 2,
+                    modulation: 9,
+                    symbol_rate: 337_600,
+                    fec_inner: 3,
+                },
+            )],
+        };
+        let nit = TlvNit {
+            table_id: TLV_NIT_ACTUAL_TABLE_ID,
+            section_syntax_indicator: true,
+            section_length: 0,
+            network_id: 0x000B,
+            version_number: 0,
+            current_next_indicator: true,
+            section_number: 0,
+            last_section_number: 0,
+            network_descriptors: vec![],
+            tlv_streams: vec![
+                tlv_stream(0xB110, 1_199_600),
+                tlv_stream(0xB111, 1_199_600),
+                tlv_stream(0xB070, 1_184_256),
+            ],
+            crc_32: 0,
+        };
+
+        // BS-15 at 11.996 GHz, and BS-7 at 11.84256 GHz.
+        assert_eq!(
+            tlv_stream_ids_at(&nit, Transponder::Bs(15).frequency()).collect::<Vec<_>>(),
+            [0xB110, 0xB111]
+        );
+    }
+
+    #[test]
+    fn tells_a_transport_stream_from_a_tlv_stream() {
+        let mut ts = vec![0xFF; PROBE_SIZE];
+        for offset in (5..PROBE_SIZE).step_by(TS_PACKET_SIZE) {
+            ts[offset] = TS_SYNC_BYTE;
+        }
+        assert!(has_ts_sync(&ts));
+
+        let mut tlv = vec![0x00; PROBE_SIZE];
+        tlv[0] = 0x7F;
+        assert!(!has_ts_sync(&tlv));
+    }
+}
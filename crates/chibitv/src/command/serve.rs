@@ -1,13 +1,12 @@
 use std::sync::Arc;
 
 use anyhow::Context;
-use chibitv_b61::Descrambler;
 use chrono::{Local, Offset};
 use clap::Parser;
 use tracing::warn;
 
-use crate::cas::PcscCasModule;
-use crate::channel::{Channel, ChannelInner};
+use crate::cas::CasPool;
+use crate::channel::Channel;
 use crate::config::{ChannelConfig, Config};
 use crate::event_crawler::EventCrawler;
 use crate::registry::Registry;
@@ -53,26 +52,17 @@ pub async fn serve(_options: &Options, config: &Config) -> anyhow::Result<()> {
         .channels
         .iter()
         .enumerate()
-        .map(|(id, channel)| Channel {
-            id,
-            name: channel.name.to_string(),
-            inner: (&channel.inner).into(),
+        .map(|(id, channel)| {
+            Ok(Channel {
+                id,
+                name: channel.name.to_string(),
+                transport_stream_id: channel.transport_stream_id,
+                inner: channel.try_into()?,
+            })
         })
-        .collect::<Vec<_>>();
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    let cas = PcscCasModule::open_shared()?;
-    let b61_descrambler = if channels
-        .iter()
-        .any(|channel| matches!(channel.inner, ChannelInner::IsdbS { .. }))
-    {
-        Some(Descrambler::init(
-            cas.clone(),
-            config.cas.master_key.into(),
-            true,
-        )?)
-    } else {
-        None
-    };
+    let cas = CasPool::open_shared(&config.cas)?;
 
     let tuners = Arc::new({
         let mut tuners = Tuners::default();
@@ -86,17 +76,14 @@ pub async fn serve(_options: &Options, config: &Config) -> anyhow::Result<()> {
 
     // No channel is tuned yet: a tuner is occupied only while at least one
     // client keeps a stream open.
-    let streams = Streams::new(
-        registry.clone(),
-        Arc::clone(&tuners),
-        cas.clone(),
-        b61_descrambler,
-    );
+    let streams = Streams::new(registry.clone(), Arc::clone(&tuners), Arc::clone(&cas));
 
     let address = config.server.address;
-    let event_crawler = EventCrawler::new(tuners, cas, config.cas.master_key.into());
+    let event_crawler = EventCrawler::new(Arc::clone(&tuners), cas);
     let state = Arc::new(
-        Workspace::new(registry, channels, Some(streams)).with_event_crawler(event_crawler),
+        Workspace::new(registry, channels, Some(streams))
+            .with_event_crawler(event_crawler)
+            .with_tuners(tuners),
     );
 
     crate::server::serve(address, state).await
@@ -121,7 +108,7 @@ fn seed_registry(registry: &Registry, channels: &[ChannelConfig]) {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::{ChannelConfigInner, ServiceConfig};
+    use crate::config::{ChannelConfigInner, IsdbTLayer, ServiceConfig};
 
     use super::*;
 
@@ -139,6 +126,8 @@ mod tests {
                 inner: ChannelConfigInner::IsdbT {
                     frequency: 515_142_857,
                     bandwidth_hz: 6_000_000,
+                    layers: IsdbTLayer::ALL.to_vec(),
+                    alternate_frequencies: vec![],
                 },
             },
             ChannelConfig {
@@ -152,6 +141,8 @@ mod tests {
                 inner: ChannelConfigInner::IsdbT {
                     frequency: 521_142_857,
                     bandwidth_hz: 6_000_000,
+                    layers: IsdbTLayer::ALL.to_vec(),
+                    alternate_frequencies: vec![],
                 },
             },
         ];
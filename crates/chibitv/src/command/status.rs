@@ -7,14 +7,13 @@ use tracing::info;
 use chibitv_b10::descriptor::Descriptor;
 use chibitv_b10::table::{Eit, EventInformation, Nit, Sdt, ServiceInformation, Table};
 use chibitv_b24::decode as decode_b24;
-use chibitv_b25::B25Descrambler;
 
-use crate::cas::PcscCasModule;
+use crate::cas::CasPool;
 use crate::channel::{Channel, ChannelInner};
 use crate::config::Config;
 use crate::demux::{Demux, Packet, SignalingEvent};
 use crate::m2ts::M2tsDemuxer;
-use crate::tuner::Tuners;
+use crate::tuner::{LeasePriority, Tuners};
 
 #[derive(Clone, Debug, Parser)]
 pub struct Options {
@@ -39,25 +38,27 @@ pub async fn status(options: &Options, config: &Config) -> anyhow::Result<()> {
         tuners.add_tuner_from_config(id as u32, tuner)?;
     }
 
-    let tuner = tuners.try_acquire_by_id(0)?;
-
-    let Some(channel) = config.channels.get(options.channel).map(|channel| Channel {
-        id: options.channel,
-        name: channel.name.to_string(),
-        inner: (&channel.inner).into(),
-    }) else {
+    let Some(channel_config) = config.channels.get(options.channel) else {
         anyhow::bail!("Could not find the channel in the config");
     };
+    let channel = Channel {
+        id: options.channel,
+        name: channel_config.name.to_string(),
+        transport_stream_id: channel_config.transport_stream_id,
+        inner: channel_config.try_into()?,
+    };
 
     let ChannelInner::IsdbT { .. } = channel.inner else {
         anyhow::bail!("ISDB-T channels are only supported");
     };
 
+    let tuner = tuners.acquire(channel.inner.delivery_system(), LeasePriority::Live)?;
+
     info!("Tuning to the channel: {:?}", channel);
 
     tuner.tune(channel.clone())?;
 
-    let descrambler = B25Descrambler::init(PcscCasModule::open_shared()?)?;
+    let descrambler = CasPool::open(&config.cas)?.b25_descrambler()?;
     let mut demux = M2tsDemuxer::new(tuner.open()?, descrambler);
     let mut state = StatusState::default();
 
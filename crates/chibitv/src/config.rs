@@ -1,5 +1,6 @@
+use std::collections::BTreeMap;
 use std::net::{Ipv6Addr, SocketAddr};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
@@ -28,6 +29,72 @@ impl<'de> Deserialize<'de> for CasMasterKey {
 #[derive(Clone, Debug, Deserialize)]
 pub struct CasConfig {
     pub master_key: CasMasterKey,
+
+    /// Where the commands to the CAS module go. Defaults to the card in the PC/SC reader.
+    #[serde(default)]
+    pub backend: CasBackend,
+
+    /// The transcript the `record` backend writes and the `replay` backend reads.
+    #[serde(default)]
+    pub transcript: Option<PathBuf>,
+
+    /// The key log every key the cards give is appended to, and the `key_log` backend takes the
+    /// keys from.
+    #[serde(default)]
+    pub key_log: Option<PathBuf>,
+
+    /// The readers of the cards to use. Defaults to the first reader found, for every standard.
+    #[serde(default)]
+    pub readers: Vec<CasReaderConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CasReaderConfig {
+    /// The name of the reader, or a part of it.
+    pub name: String,
+
+    /// The standards the card in the reader descrambles. Defaults to both.
+    #[serde(default = "default_cas_standards")]
+    pub standards: Vec<CasStandard>,
+}
+
+fn default_cas_standards() -> Vec<CasStandard> {
+    CasStandard::ALL.to_vec()
+}
+
+/// The scrambling a card is used for: ARIB STD-B25 for MPEG-2 TS, which B-CAS cards descramble,
+/// or ARIB STD-B61 for MMT/TLV, which needs an ACAS card.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub enum CasStandard {
+    B25,
+    B61,
+}
+
+impl CasStandard {
+    pub const ALL: [Self; 2] = [Self::B25, Self::B61];
+}
+
+impl std::fmt::Display for CasStandard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::B25 => write!(f, "B25"),
+            Self::B61 => write!(f, "B61"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CasBackend {
+    /// Talks to the card in the PC/SC reader.
+    #[default]
+    Pcsc,
+    /// Talks to the card in the PC/SC reader, writing every exchange to the transcript.
+    Record,
+    /// Answers from the transcript alone, so that no reader is needed.
+    Replay,
+    /// Descrambles with the keys of the key log instead of a card, so that no reader is needed.
+    KeyLog,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -60,11 +127,74 @@ impl Default for DatabaseConfig {
     }
 }
 
+/// The broadcast standard a channel is transmitted in. ISDB-S covers both the TLV and the
+/// MPEG-2 TS streams of satellites, which any ISDB-S tuner receives alike.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum DeliverySystem {
+    #[serde(rename = "ISDB-S")]
+    IsdbS,
+
+    #[serde(rename = "ISDB-T")]
+    IsdbT,
+}
+
+impl DeliverySystem {
+    pub const ALL: [Self; 2] = [Self::IsdbS, Self::IsdbT];
+}
+
+impl std::fmt::Display for DeliverySystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IsdbS => write!(f, "ISDB-S"),
+            Self::IsdbT => write!(f, "ISDB-T"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TunerConfig {
+    /// The delivery systems the tuner can receive. Defaults to every one of them.
+    #[serde(default = "default_delivery_systems")]
+    pub delivery_systems: Vec<DeliverySystem>,
+
+    #[serde(flatten)]
+    pub inner: TunerConfigInner,
+}
+
+fn default_delivery_systems() -> Vec<DeliverySystem> {
+    DeliverySystem::ALL.to_vec()
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
-pub enum TunerConfig {
+pub enum TunerConfigInner {
     Stdin,
-    Dvb { adapter_num: u8, frontend_num: u8 },
+    Dvb {
+        adapter_num: u8,
+        frontend_num: u8,
+    },
+
+    /// Replays captures written by `record`, keyed by the name of the channel they stand in for.
+    File {
+        channels: BTreeMap<String, PathBuf>,
+    },
+
+    /// Receives channels from a remote server speaking the HTTP API of Mirakurun.
+    Mirakurun {
+        url: String,
+
+        /// The Mirakurun channels by the name of the channel they stand for, such as `BS/BS15_0`.
+        #[serde(default)]
+        channels: BTreeMap<String, String>,
+    },
+
+    /// Runs a command writing the raw stream to its standard output, such as `recpt1`.
+    ///
+    /// `{name}`, `{frequency}`, `{stream_id}`, `{bandwidth_hz}` and `{uhf_channel}` in the
+    /// arguments are replaced by those of the channel.
+    Command {
+        tune_command: Vec<String>,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -73,6 +203,14 @@ pub enum ChannelConfigInner {
     #[serde(rename = "ISDB-S")]
     IsdbS { frequency: u32, stream_id: u32 },
 
+    /// An MPEG-2 transport stream on ISDB-S, which carries the 2K services of BS and CS110.
+    ///
+    /// The stream is the one of the `transport_stream_id` of the channel, such as `0x4010` for
+    /// NHK BS1. Its lowest 3 bits are the relative number of the stream on the transponder, which
+    /// is what the tuner is given.
+    #[serde(rename = "ISDB-S-TS")]
+    IsdbSTs { frequency: u32 },
+
     #[serde(rename = "ISDB-T")]
     IsdbT {
         frequency: u32,
@@ -82,6 +220,21 @@ pub enum ChannelConfigInner {
             skip_serializing_if = "is_default_isdb_t_bandwidth_hz"
         )]
         bandwidth_hz: u32,
+
+        /// The hierarchical layers to receive, such as `["A"]` for the one-seg service alone.
+        /// Defaults to every layer.
+        #[serde(
+            default = "default_isdb_t_layers",
+            deserialize_with = "deserialize_isdb_t_layers",
+            skip_serializing_if = "is_default_isdb_t_layers"
+        )]
+        layers: Vec<IsdbTLayer>,
+
+        /// Frequencies of relay stations carrying the same transport stream, tried in order when
+        /// `frequency` cannot be received. With `transport_stream_id` set, a frequency is kept
+        /// only when its PAT reports that id.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        alternate_frequencies: Vec<u32>,
     },
 }
 
@@ -93,6 +246,40 @@ fn is_default_isdb_t_bandwidth_hz(value: &u32) -> bool {
     *value == default_isdb_t_bandwidth_hz()
 }
 
+/// A hierarchical layer of an ISDB-T transmission. Layer A carries the partial reception
+/// (one-seg) segment, and layers B and C the full-seg services.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum IsdbTLayer {
+    A,
+    B,
+    C,
+}
+
+impl IsdbTLayer {
+    pub const ALL: [Self; 3] = [Self::A, Self::B, Self::C];
+}
+
+fn default_isdb_t_layers() -> Vec<IsdbTLayer> {
+    IsdbTLayer::ALL.to_vec()
+}
+
+/// Rejects an empty list, which would receive nothing at all.
+fn deserialize_isdb_t_layers<'de, D>(deserializer: D) -> Result<Vec<IsdbTLayer>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let layers = Vec::<IsdbTLayer>::deserialize(deserializer)?;
+    if layers.is_empty() {
+        return Err(Error::custom("layers must name at least one layer"));
+    }
+
+    Ok(layers)
+}
+
+fn is_default_isdb_t_layers(value: &[IsdbTLayer]) -> bool {
+    IsdbTLayer::ALL.iter().all(|layer| value.contains(layer))
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ChannelConfig {
     pub name: String,
@@ -179,6 +366,47 @@ mod tests {
         assert_eq!(channel.services[0].name, "TOKYO MX1");
     }
 
+    #[derive(Deserialize)]
+    struct TunerList {
+        tuners: Vec<TunerConfig>,
+    }
+
+    #[test]
+    fn reads_the_delivery_systems_of_tuners() {
+        let config = toml::from_str::<TunerList>(
+            r#"
+                [[tuners]]
+                type = "dvb"
+                adapter_num = 0
+                frontend_num = 0
+                delivery_systems = ["ISDB-T"]
+
+                [[tuners]]
+                type = "stdin"
+
+                [[tuners]]
+                type = "command"
+                tune_command = ["recpt1", "{uhf_channel}", "-", "-"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.tuners[0].delivery_systems, [DeliverySystem::IsdbT]);
+        assert!(matches!(
+            config.tuners[0].inner,
+            TunerConfigInner::Dvb {
+                adapter_num: 0,
+                frontend_num: 0
+            }
+        ));
+        assert_eq!(config.tuners[1].delivery_systems, DeliverySystem::ALL);
+        assert!(matches!(config.tuners[1].inner, TunerConfigInner::Stdin));
+        assert!(matches!(
+            &config.tuners[2].inner,
+            TunerConfigInner::Command { tune_command } if tune_command[1] == "{uhf_channel}"
+        ));
+    }
+
     #[test]
     fn keeps_legacy_channel_config_compatible() {
         let config = toml::from_str::<ChannelList>(
@@ -194,5 +422,150 @@ mod tests {
         let channel = &config.channels[0];
         assert_eq!(channel.transport_stream_id, None);
         assert!(channel.services.is_empty());
+        assert!(matches!(
+            &channel.inner,
+            ChannelConfigInner::IsdbT { layers, .. } if layers == &IsdbTLayer::ALL
+        ));
+    }
+
+    #[test]
+    fn reads_a_satellite_transport_stream_channel() {
+        let config = toml::from_str::<ChannelList>(
+            r#"
+                [[channels]]
+                name = "BS1"
+                delivery_system = "ISDB-S-TS"
+                frequency = 1049480
+                transport_stream_id = 0x4010
+
+                [[channels]]
+                name = "BS unknown"
+                delivery_system = "ISDB-S-TS"
+                frequency = 1049480
+            "#,
+        )
+        .unwrap();
+
+        let channel = crate::channel::ChannelInner::try_from(&config.channels[0]).unwrap();
+        assert!(matches!(
+            channel,
+            crate::channel::ChannelInner::IsdbSTs {
+                frequency: 1_049_480,
+                transport_stream_id: 0x4010
+            }
+        ));
+        assert_eq!(channel.delivery_system(), DeliverySystem::IsdbS);
+
+        assert!(crate::channel::ChannelInner::try_from(&config.channels[1]).is_err());
+    }
+
+    #[test]
+    fn reads_the_layers_of_a_one_seg_channel() {
+        let config = toml::from_str::<ChannelList>(
+            r#"
+                [[channels]]
+                name = "UHF 27 one-seg"
+                delivery_system = "ISDB-T"
+                frequency = 557142857
+                layers = ["A"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &config.channels[0].inner,
+            ChannelConfigInner::IsdbT { layers, .. } if layers == &[IsdbTLayer::A]
+        ));
+    }
+
+    #[test]
+    fn rejects_a_channel_of_no_layers() {
+        let result = toml::from_str::<ChannelList>(
+            r#"
+                [[channels]]
+                name = "UHF 27"
+                delivery_system = "ISDB-T"
+                frequency = 557142857
+                layers = []
+            "#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn tries_the_alternate_frequencies_after_the_main_one() {
+        let config = toml::from_str::<ChannelList>(
+            r#"
+                [[channels]]
+                name = "UHF 19"
+                delivery_system = "ISDB-T"
+                frequency = 509142857
+                alternate_frequencies = [515142857, 521142857]
+            "#,
+        )
+        .unwrap();
+
+        let channel = crate::channel::ChannelInner::try_from(&config.channels[0]).unwrap();
+        let frequencies = channel
+            .candidates()
+            .into_iter()
+            .map(|candidate| match candidate {
+                crate::channel::ChannelInner::IsdbT {
+                    frequency,
+                    alternate_frequencies,
+                    ..
+                } => {
+                    assert!(alternate_frequencies.is_empty());
+                    frequency
+                }
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(frequencies, [509_142_857, 515_142_857, 521_142_857]);
+    }
+
+    #[test]
+    fn reads_the_cas_backend() {
+        let key = "00".repeat(32);
+        let config = toml::from_str::<CasConfig>(&format!(
+            "master_key = \"{key}\"\nbackend = \"replay\"\ntranscript = \"cas.transcript\""
+        ))
+        .unwrap();
+        assert_eq!(config.backend, CasBackend::Replay);
+        assert_eq!(config.transcript, Some(PathBuf::from("cas.transcript")));
+
+        let config = toml::from_str::<CasConfig>(&format!(
+            "master_key = \"{key}\"\nbackend = \"key_log\"\nkey_log = \"cas.keys\""
+        ))
+        .unwrap();
+        assert_eq!(config.backend, CasBackend::KeyLog);
+        assert_eq!(config.key_log, Some(PathBuf::from("cas.keys")));
+
+        let config = toml::from_str::<CasConfig>(&format!("master_key = \"{key}\"")).unwrap();
+        assert_eq!(config.backend, CasBackend::Pcsc);
+    }
+
+    #[test]
+    fn reads_the_cas_readers() {
+        let key = "00".repeat(32);
+        let config = toml::from_str::<CasConfig>(&format!(
+            r#"
+                master_key = "{key}"
+
+                [[readers]]
+                name = "SCR3310"
+                standards = ["B25"]
+
+                [[readers]]
+                name = "ACR39U"
+            "#
+        ))
+        .unwrap();
+
+        assert_eq!(config.readers.len(), 2);
+        assert_eq!(config.readers[0].standards, [CasStandard::B25]);
+        assert_eq!(config.readers[1].name, "ACR39U");
+        assert_eq!(config.readers[1].standards, CasStandard::ALL);
     }
 }
@@ -1,13 +1,19 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
+use tracing::warn;
 
 use chibitv_b10::table::Table as B10Table;
+use chibitv_b60::ip::UdpDatagram;
 use chibitv_b60::message::Message;
+use chibitv_b60::ntp::NtpPacket;
+use chibitv_b60::tlv_si::TlvSiTable;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum TrackType {
     Mpeg2Video,
+    H264,
     AacAdts,
     H265,
     AacLatm,
@@ -29,8 +35,17 @@ pub enum MediaPacket {
 
 #[derive(Clone, Debug)]
 pub enum SignalingEvent {
-    B10Table { table_id: u8, table: B10Table },
+    B10Table {
+        table_id: u8,
+        table: B10Table,
+    },
     B60Message(Message),
+    /// A table of the TLV stream, sent in a transmission control signal.
+    TlvSiTable(TlvSiTable),
+    /// The time the broadcaster sends to synchronise receivers.
+    Ntp(NtpPacket),
+    /// A UDP datagram sent outside of MMTP and NTP, such as one of data broadcasting.
+    Udp(UdpDatagram),
 }
 
 #[derive(Clone, Debug)]
@@ -39,8 +54,98 @@ pub enum Packet {
     Signaling(SignalingEvent),
 }
 
+/// What a demuxer has dropped of a corrupted input.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct DropCounts {
+    /// Octets skipped to find the start of the next packet.
+    pub octets: u64,
+    /// Packets that could not be read.
+    pub packets: u64,
+    /// Sections that could not be read, those failing their CRC_32 check included.
+    pub sections: u64,
+    /// Sections whose CRC_32 did not match their bytes.
+    pub crc_mismatches: u64,
+}
+
+impl DropCounts {
+    /// What has been dropped since the counts were `earlier`.
+    pub fn since(self, earlier: Self) -> Self {
+        Self {
+            octets: self.octets.saturating_sub(earlier.octets),
+            packets: self.packets.saturating_sub(earlier.packets),
+            sections: self.sections.saturating_sub(earlier.sections),
+            crc_mismatches: self.crc_mismatches.saturating_sub(earlier.crc_mismatches),
+        }
+    }
+}
+
+/// How often a demuxer of a tuner input reports what it has dropped.
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Logs what a demuxer of a tuner input drops every so often, since the input of a served stream
+/// may never end for the remuxer to report it then.
+#[derive(Debug)]
+pub struct DropReporter {
+    channel_id: usize,
+    reported: DropCounts,
+    reported_at: Instant,
+}
+
+impl DropReporter {
+    pub fn new(channel_id: usize) -> Self {
+        Self {
+            channel_id,
+            reported: DropCounts::default(),
+            reported_at: Instant::now(),
+        }
+    }
+
+    /// Logs what has been dropped since the last report, once the interval has passed since it.
+    pub fn report(&mut self, drop_counts: DropCounts) {
+        let now = Instant::now();
+        if now.duration_since(self.reported_at) >= DROP_REPORT_INTERVAL {
+            self.report_at(drop_counts, now);
+        }
+    }
+
+    /// Logs what has been dropped since the last report, as the input ends.
+    pub fn finish(mut self, drop_counts: DropCounts) {
+        self.report_at(drop_counts, Instant::now());
+    }
+
+    fn report_at(&mut self, drop_counts: DropCounts, now: Instant) -> Option<DropCounts> {
+        let dropped = drop_counts.since(self.reported);
+        self.reported = drop_counts;
+        self.reported_at = now;
+        if dropped == DropCounts::default() {
+            return None;
+        }
+
+        warn!(
+            channel_id = self.channel_id,
+            octets = dropped.octets,
+            packets = dropped.packets,
+            sections = dropped.sections,
+            crc_mismatches = dropped.crc_mismatches,
+            "Dropped what could not be read of the input"
+        );
+        Some(dropped)
+    }
+}
+
 pub trait Demux {
     fn next_packet(&mut self) -> anyhow::Result<Option<Packet>>;
+
+    /// What the demuxer has dropped of the input so far.
+    fn drop_counts(&self) -> DropCounts {
+        DropCounts::default()
+    }
+
+    /// The PIDs of the input the demuxer reads so far, or `None` when it needs
+    /// all of them.
+    fn wanted_pids(&self) -> Option<BTreeSet<u16>> {
+        None
+    }
 }
 
 #[derive(Debug, Default)]
@@ -57,3 +162,29 @@ impl PacketQueue {
         self.packets.extend(packets);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_what_was_dropped_since_the_last_report() {
+        let mut reporter = DropReporter::new(1);
+        let start = reporter.reported_at;
+        let dropped = |packets, crc_mismatches| DropCounts {
+            packets,
+            crc_mismatches,
+            ..DropCounts::default()
+        };
+
+        assert_eq!(
+            reporter.report_at(dropped(3, 1), start),
+            Some(dropped(3, 1))
+        );
+        assert_eq!(reporter.report_at(dropped(3, 1), start), None);
+        assert_eq!(
+            reporter.report_at(dropped(5, 1), start),
+            Some(dropped(2, 0))
+        );
+    }
+}
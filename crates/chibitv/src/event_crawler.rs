@@ -5,19 +5,17 @@ use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
 use chibitv_b10::table::Table as B10Table;
-use chibitv_b25::B25Descrambler;
 use chibitv_b60::message::Message;
 use chibitv_b60::table::Table as B60Table;
-use chibitv_b61::Descrambler;
 
-use crate::cas::PcscCasModule;
+use crate::cas::CasPool;
 use crate::channel::{Channel, ChannelInner};
-use crate::demux::{Demux, Packet, SignalingEvent};
+use crate::demux::{Demux, DropReporter, Packet, SignalingEvent};
 use crate::m2ts::M2tsDemuxer;
 use crate::mmt::MmtDemuxer;
 use crate::registry::{Event, Registry};
 use crate::service_information::ServiceInformationProcessor;
-use crate::tuner::Tuners;
+use crate::tuner::{AcquireError, LeasePriority, Preemption, TunerLease, Tuners};
 
 const READ_BUFFER_SIZE: usize = 188 * 8192;
 const EIT_ACTUAL_PRESENT_FOLLOWING_TABLE_ID: u8 = 0x4E;
@@ -30,19 +28,18 @@ pub struct CrawledEvent {
 
 pub struct EventCrawler {
     tuners: Arc<Tuners>,
-    cas: Arc<PcscCasModule>,
-    cas_master_key: [u8; 32],
+    cas: Arc<CasPool>,
 }
 
 impl EventCrawler {
-    pub fn new(tuners: Arc<Tuners>, cas: Arc<PcscCasModule>, cas_master_key: [u8; 32]) -> Self {
-        Self {
-            tuners,
-            cas,
-            cas_master_key,
-        }
+    pub fn new(tuners: Arc<Tuners>, cas: Arc<CasPool>) -> Self {
+        Self { tuners, cas }
     }
 
+    /// Crawls the channels, leasing one tuner for each delivery system they are transmitted in.
+    ///
+    /// Channels no configured tuner can receive are skipped. Crawling gives way to every other use
+    /// of the tuners, and fails with [`Preempted`](crate::tuner::Preempted) once it has to.
     pub fn crawl(
         &self,
         channels: &[Channel],
@@ -50,10 +47,53 @@ impl EventCrawler {
         dwell_time: Duration,
         mut emit: impl FnMut(CrawledEvent) -> bool,
     ) -> anyhow::Result<()> {
-        let tuner = self.tuners.try_acquire()?;
-        info!(tuner_id = tuner.id(), "Acquired tuner for event crawling");
+        let mut delivery_systems = Vec::new();
+        for channel in channels {
+            let delivery_system = channel.inner.delivery_system();
+            if !delivery_systems.contains(&delivery_system) {
+                delivery_systems.push(delivery_system);
+            }
+        }
+
+        for delivery_system in delivery_systems {
+            let tuner = match self.tuners.acquire(delivery_system, LeasePriority::Crawl) {
+                Ok(tuner) => tuner,
+                Err(error @ AcquireError::Unsupported(_)) => {
+                    warn!(%error, "Skipping channels while crawling events");
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
+            info!(
+                tuner_id = tuner.id(),
+                %delivery_system,
+                "Acquired tuner for event crawling"
+            );
+
+            let channels = channels
+                .iter()
+                .filter(|channel| channel.inner.delivery_system() == delivery_system);
+            if !self.crawl_with(&tuner, channels, &registry, dwell_time, &mut emit)? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn crawl_with<'a>(
+        &self,
+        tuner: &TunerLease,
+        channels: impl Iterator<Item = &'a Channel>,
+        registry: &Arc<Registry>,
+        dwell_time: Duration,
+        emit: &mut impl FnMut(CrawledEvent) -> bool,
+    ) -> anyhow::Result<bool> {
+        let preemption = tuner.preemption();
 
         for channel in channels {
+            preemption.check()?;
+
             info!(channel_id = channel.id, channel = %channel.name, "Crawling events");
             if let Err(error) = tuner.tune(channel.clone()) {
                 warn!(channel_id = channel.id, %error, "Could not tune while crawling events");
@@ -69,28 +109,27 @@ impl EventCrawler {
             };
             let deadline = Instant::now() + dwell_time;
             let keep_crawling = match channel.inner {
-                ChannelInner::IsdbT { .. } => {
-                    let descrambler = B25Descrambler::init(self.cas.clone())?;
+                ChannelInner::IsdbSTs { .. } | ChannelInner::IsdbT { .. } => {
+                    let descrambler = self.cas.b25_descrambler()?;
                     let mut demux = M2tsDemuxer::new(reader, descrambler);
-                    crawl_channel(&mut demux, channel, &registry, deadline, &mut emit)?
+                    crawl_channel(&mut demux, channel, registry, &preemption, deadline, emit)?
                 }
                 ChannelInner::IsdbS { .. } => {
-                    let descrambler =
-                        Descrambler::init(self.cas.clone(), self.cas_master_key, false)?;
+                    let descrambler = self.cas.b61_descrambler(false)?;
                     let mut demux = MmtDemuxer::new(
                         BufReader::with_capacity(READ_BUFFER_SIZE, reader),
                         descrambler,
                     );
-                    crawl_channel(&mut demux, channel, &registry, deadline, &mut emit)?
+                    crawl_channel(&mut demux, channel, registry, &preemption, deadline, emit)?
                 }
             };
 
             if !keep_crawling {
-                break;
+                return Ok(false);
             }
         }
 
-        Ok(())
+        Ok(true)
     }
 }
 
@@ -98,13 +137,17 @@ fn crawl_channel<D: Demux>(
     demux: &mut D,
     channel: &Channel,
     registry: &Arc<Registry>,
+    preemption: &Preemption,
     deadline: Instant,
     emit: &mut impl FnMut(CrawledEvent) -> bool,
 ) -> anyhow::Result<bool> {
     let mut processor =
         ServiceInformationProcessor::new(channel.id, Some(Arc::clone(registry)), None);
+    let mut drop_reporter = DropReporter::new(channel.id);
 
     while Instant::now() < deadline {
+        preemption.check()?;
+
         let packet = match demux.next_packet() {
             Ok(Some(packet)) => packet,
             Ok(None) => break,
@@ -113,6 +156,7 @@ fn crawl_channel<D: Demux>(
                 continue;
             }
         };
+        drop_reporter.report(demux.drop_counts());
         let Packet::Signaling(signaling) = packet else {
             continue;
         };
@@ -135,11 +179,13 @@ fn crawl_channel<D: Demux>(
                 continue;
             };
             if !emit(CrawledEvent { service_id, event }) {
+                drop_reporter.finish(demux.drop_counts());
                 return Ok(false);
             }
         }
     }
 
+    drop_reporter.finish(demux.drop_counts());
     Ok(true)
 }
 
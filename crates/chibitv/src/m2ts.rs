@@ -14,13 +14,15 @@ use mpeg2ts::ts::{
     TransportScramblingControl, TsHeader, TsPacket, TsPacketReader, TsPayload, VersionNumber,
     WriteTsPacket,
 };
-use tracing::warn;
+use tracing::{debug, error, warn};
 
-use chibitv_b10::descriptor::Descriptor as B10Descriptor;
-use chibitv_b10::table::Table as B10Table;
+use chibitv_b10::descriptor::{CaDescriptor, Descriptor as B10Descriptor};
+use chibitv_b10::table::{Cat, Table as B10Table};
 use chibitv_b25::{B25Descrambler, NoDecryptionKeyError};
 
-use crate::demux::{Demux, MediaPacket, Packet, PacketQueue, SignalingEvent, TrackType};
+use crate::demux::{
+    Demux, DropCounts, MediaPacket, Packet, PacketQueue, SignalingEvent, TrackType,
+};
 use crate::remux::Mux;
 
 #[derive(Debug, Default)]
@@ -40,10 +42,13 @@ pub struct M2tsDemuxer<R> {
     reader: TsPacketReader<AlignedTsReader<R>>,
     descrambler: Arc<Mutex<B25Descrambler>>,
     target_service_id: Option<u16>,
+    pmt_pid: Option<Pid>,
     ecm_pids: BTreeSet<Pid>,
+    emm_pids: BTreeSet<Pid>,
     tracks: BTreeMap<Pid, TrackState>,
     section_buffers: BTreeMap<Pid, Vec<u8>>,
     pending_packets: PacketQueue,
+    drop_counts: DropCounts,
 }
 
 impl<R: Read> M2tsDemuxer<R> {
@@ -67,10 +72,13 @@ impl<R: Read> M2tsDemuxer<R> {
             reader,
             descrambler,
             target_service_id,
+            pmt_pid: None,
             ecm_pids: BTreeSet::new(),
+            emm_pids: BTreeSet::new(),
             tracks: BTreeMap::new(),
             section_buffers: BTreeMap::new(),
             pending_packets: PacketQueue::default(),
+            drop_counts: DropCounts::default(),
         }
     }
 
@@ -86,7 +94,23 @@ impl<R: Read> M2tsDemuxer<R> {
         }
 
         let ecm_payload = &section[data_offset..section.len() - 4];
-        self.descrambler.lock().unwrap().push_ecm(ecm_payload)
+        // The card may be back by the next ECM, so the stream keeps going without a new key.
+        if let Err(error) = self.descrambler.lock().unwrap().push_ecm(ecm_payload) {
+            error!(%error, "Could not decrypt ECM");
+        }
+
+        Ok(())
+    }
+
+    fn read_emm(&self, section: Bytes) {
+        if section.first().copied() != Some(0x84) || section.len() < 8 + 4 {
+            return;
+        }
+
+        let emm_payload = &section[8..section.len() - 4];
+        if let Err(error) = self.descrambler.lock().unwrap().push_emm(emm_payload) {
+            error!(%error, "Could not give the EMM to the CAS module");
+        }
     }
 
     fn add_ecm_pid(&mut self, pid: Pid) {
@@ -94,6 +118,24 @@ impl<R: Read> M2tsDemuxer<R> {
             self.reader.add_section_pid(pid);
         }
     }
+
+    /// Reads the EMMs of the card from the PID the CAT announces for them.
+    fn read_cat(&mut self, cat: &Cat) -> anyhow::Result<()> {
+        let ca_system_id = self.descrambler.lock().unwrap().ca_system_id();
+        for descriptor in &cat.descriptors {
+            let B10Descriptor::Ca(descriptor) = descriptor else {
+                continue;
+            };
+
+            if let Some(pid) = ca_pid(descriptor, ca_system_id)?
+                && self.emm_pids.insert(pid)
+            {
+                self.reader.add_section_pid(pid);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<R: Read> M2tsDemuxer<R> {
@@ -105,6 +147,7 @@ impl<R: Read> M2tsDemuxer<R> {
                 Ok(None) => break,
                 Err(error) => {
                     warn!(error = %error, "Failed to parse MPEG-TS packet");
+                    self.drop_counts.packets += 1;
 
                     if out.is_empty() {
                         continue;
@@ -137,6 +180,17 @@ impl<R: Read> M2tsDemuxer<R> {
             };
 
             match payload {
+                TsPayload::Pat(pat) => {
+                    let Some(service_id) = self.target_service_id else {
+                        continue;
+                    };
+
+                    self.pmt_pid = pat
+                        .table
+                        .iter()
+                        .find(|association| association.program_num == service_id)
+                        .map(|association| association.program_map_pid);
+                }
                 TsPayload::Pmt(pmt) => {
                     if self
                         .target_service_id
@@ -171,7 +225,14 @@ impl<R: Read> M2tsDemuxer<R> {
                         }
 
                         if is_video_track_type(track_type) {
-                            if matches!(track_type, TrackType::Mpeg2Video) {
+                            // A one-seg service carries H.264 only, and is asked for by its
+                            // service ID, since no other service of the TS uses it.
+                            let wanted = match track_type {
+                                TrackType::Mpeg2Video => true,
+                                TrackType::H264 => self.target_service_id.is_some(),
+                                _ => false,
+                            };
+                            if wanted && selected_video.is_none() {
                                 selected_video = Some((pid, track_type));
                             }
                         } else if is_audio_track_type(track_type) && selected_audio.is_none() {
@@ -214,13 +275,21 @@ impl<R: Read> M2tsDemuxer<R> {
                             self.read_ecm(Bytes::from(section))?;
                             continue;
                         }
+                        if self.emm_pids.contains(&pid) {
+                            self.read_emm(Bytes::from(section));
+                            continue;
+                        }
 
                         let Some(table_id) = section.first().copied() else {
                             continue;
                         };
 
-                        let mut bytes = Bytes::from(section);
-                        let table = B10Table::read(&mut bytes)?;
+                        let Some(table) = read_table(section, &mut self.drop_counts) else {
+                            continue;
+                        };
+                        if let B10Table::Cat(cat) = &table {
+                            self.read_cat(cat)?;
+                        }
                         if !matches!(table, B10Table::Unknown(_, _)) {
                             out.push(Packet::Signaling(SignalingEvent::B10Table {
                                 table_id,
@@ -290,6 +359,75 @@ impl<R: Read> Demux for M2tsDemuxer<R> {
             self.pending_packets.extend(packets);
         }
     }
+
+    fn drop_counts(&self) -> DropCounts {
+        self.drop_counts
+    }
+
+    /// The set grows as the PAT and the PMT of the service are read, and stays
+    /// `None` unless a single service is demultiplexed.
+    fn wanted_pids(&self) -> Option<BTreeSet<u16>> {
+        self.target_service_id?;
+
+        Some(service_pids(
+            self.pmt_pid?,
+            self.ecm_pids.union(&self.emm_pids),
+            self.tracks.keys().copied(),
+        ))
+    }
+}
+
+/// Reads the transport stream id the first PAT reports, giving up after `max_packets` packets.
+pub fn read_transport_stream_id(reader: impl Read, max_packets: usize) -> Option<u16> {
+    let mut reader = TsPacketReader::new(AlignedTsReader::new(reader));
+    for _ in 0..max_packets {
+        match reader.read_ts_packet() {
+            Ok(Some(TsPacket {
+                payload: Some(TsPayload::Pat(pat)),
+                ..
+            })) => return Some(pat.transport_stream_id),
+            Ok(Some(_)) => {}
+            Ok(None) => break,
+            Err(error) => warn!(error = %error, "Failed to parse MPEG-TS packet"),
+        }
+    }
+
+    None
+}
+
+/// A few packets repeating a PAT of the transport stream id, enough to be synchronised to.
+#[cfg(test)]
+pub(crate) fn pat_packets(transport_stream_id: u16) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut writer = mpeg2ts::ts::TsPacketWriter::new(&mut bytes);
+    let mut continuity_counter = ContinuityCounter::new();
+    for _ in 0..3 {
+        writer
+            .write_ts_packet(&TsPacket {
+                header: TsHeader {
+                    pid: pat_pid(),
+                    continuity_counter,
+                    transport_error_indicator: false,
+                    transport_priority: false,
+                    payload_unit_start_indicator: true,
+                    transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                },
+                payload: Some(TsPayload::Pat(Pat {
+                    transport_stream_id,
+                    version_number: VersionNumber::default(),
+                    table: vec![ProgramAssociation {
+                        program_num: PROGRAM_NUM,
+                        program_map_pid: pmt_pid(),
+                    }],
+                })),
+                adaptation_field: None,
+            })
+            .unwrap();
+        continuity_counter.increment();
+    }
+    drop(writer);
+
+    bytes
 }
 
 fn flush_pes_buffers(tracks: &mut BTreeMap<Pid, TrackState>) -> Vec<Packet> {
@@ -435,6 +573,24 @@ impl<R: Read> Read for AlignedTsReader<R> {
     }
 }
 
+/// Reads the table of a section, dropping the section when it is corrupted, as those
+/// reassembled across packet loss are.
+fn read_table(section: Vec<u8>, drop_counts: &mut DropCounts) -> Option<B10Table> {
+    let table_id = section.first().copied();
+    match B10Table::read(&mut Bytes::from(section)) {
+        Ok(table) => Some(table),
+        Err(error) => {
+            debug!(?table_id, %error, "Dropped a section that could not be read");
+            drop_counts.sections += 1;
+            if error.is_crc_mismatch() {
+                drop_counts.crc_mismatches += 1;
+            }
+
+            None
+        }
+    }
+}
+
 fn parse_pes_payload(packet: &mut TsPacket) -> anyhow::Result<()> {
     let Some(TsPayload::Raw(payload)) = packet.payload.take() else {
         return Ok(());
@@ -459,6 +615,12 @@ fn ca_descriptor_pid(descriptor: &Descriptor, ca_system_id: u16) -> anyhow::Resu
         return Ok(None);
     };
 
+    ca_pid(&descriptor, ca_system_id)
+}
+
+/// The PID the CA descriptor points to for the CA system, which is that of the ECMs in a PMT and
+/// that of the EMMs in the CAT.
+fn ca_pid(descriptor: &CaDescriptor, ca_system_id: u16) -> anyhow::Result<Option<Pid>> {
     if descriptor.ca_system_id != ca_system_id
         || descriptor.ca_pid == 0
         || descriptor.ca_pid == 0x1fff
@@ -469,6 +631,22 @@ fn ca_descriptor_pid(descriptor: &Descriptor, ca_system_id: u16) -> anyhow::Resu
     Ok(Some(Pid::new(descriptor.ca_pid)?))
 }
 
+const PAT_PID: u16 = 0x0000;
+
+/// Every PID a single service is read from: the PAT and the SI everyone needs,
+/// the PMT, ECMs and tracks of the service, and the EMMs of the card.
+fn service_pids<'a>(
+    pmt_pid: Pid,
+    ca_pids: impl Iterator<Item = &'a Pid>,
+    track_pids: impl Iterator<Item = Pid>,
+) -> BTreeSet<u16> {
+    let mut pids = BTreeSet::from([PAT_PID, pmt_pid.as_u16()]);
+    pids.extend(B10_SECTION_PIDS);
+    pids.extend(ca_pids.map(|pid| pid.as_u16()));
+    pids.extend(track_pids.map(|pid| pid.as_u16()));
+    pids
+}
+
 const B10_SECTION_PIDS: &[u16] = &[
     0x0001, // CAT
     0x0010, // NIT
@@ -501,6 +679,7 @@ fn find_sync_offset(buffer: &[u8]) -> Option<usize> {
 fn track_type_from_stream_type(stream_type: StreamType) -> Option<TrackType> {
     match stream_type {
         StreamType::Mpeg2Video => Some(TrackType::Mpeg2Video),
+        StreamType::H264 => Some(TrackType::H264),
         StreamType::H265 => Some(TrackType::H265),
         StreamType::AdtsAac => Some(TrackType::AacAdts),
         StreamType::Mpeg4LoasMultiFormatFramedAudio => Some(TrackType::AacLatm),
@@ -509,7 +688,10 @@ fn track_type_from_stream_type(stream_type: StreamType) -> Option<TrackType> {
 }
 
 fn is_video_track_type(track_type: TrackType) -> bool {
-    matches!(track_type, TrackType::Mpeg2Video | TrackType::H265)
+    matches!(
+        track_type,
+        TrackType::Mpeg2Video | TrackType::H264 | TrackType::H265
+    )
 }
 
 fn is_audio_track_type(track_type: TrackType) -> bool {
@@ -748,6 +930,20 @@ impl<W: WriteTsPacket + Send + Sync> Mux for M2tsMuxer<W> {
                     },
                 );
             }
+            TrackType::H264 => {
+                let pid = Pid::new(0x0100).unwrap();
+
+                self.track_map.insert(track_id, pid);
+                self.add_stream(
+                    pid,
+                    StreamId::new_video(0xe0).unwrap(),
+                    EsInfo {
+                        elementary_pid: pid,
+                        stream_type: StreamType::H264,
+                        descriptors: vec![],
+                    },
+                );
+            }
             TrackType::AacAdts => {
                 let pid = Pid::new(0x0110).unwrap();
 
@@ -816,6 +1012,33 @@ impl<W: WriteTsPacket + Send + Sync> Mux for M2tsMuxer<W> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn wants_the_pids_of_the_service_only() {
+        let pid = |pid| Pid::new(pid).unwrap();
+
+        let pids = service_pids(
+            pid(0x01F0),
+            [pid(0x01F1), pid(0x0901)].iter(),
+            [pid(0x0111), pid(0x0112)].into_iter(),
+        );
+
+        for wanted in [
+            0x0000, 0x0012, 0x0027, 0x01F0, 0x01F1, 0x0901, 0x0111, 0x0112,
+        ] {
+            assert!(pids.contains(&wanted), "{wanted:#06X} is not wanted");
+        }
+        assert!(!pids.contains(&0x0121));
+    }
+
+    #[test]
+    fn reads_the_transport_stream_id_of_the_pat() {
+        let mut bytes = vec![0xFF; 5];
+        bytes.extend(pat_packets(0x7FE0));
+
+        assert_eq!(read_transport_stream_id(&bytes[..], 16), Some(0x7FE0));
+        assert_eq!(read_transport_stream_id(&[][..], 16), None);
+    }
+
     #[test]
     fn flush_pes_buffers_drains_each_track_once() {
         let mut tracks = BTreeMap::from([
@@ -910,4 +1133,34 @@ mod tests {
         );
         assert!(buffers.get(&pid).is_none_or(Vec::is_empty));
     }
+
+    #[test]
+    fn drops_the_sections_that_cannot_be_read() {
+        let mut drop_counts = DropCounts::default();
+        let mut section = vec![
+            0x73, 0x70, 0x0F, // table_id, section_syntax_indicator, section_length
+            0xC0, 0x79, 0x12, 0x45, 0x00, // JST_time
+            0xF0, 0x00, // descriptors_loop_length
+        ];
+        section.extend(chibitv_b10::crc_32(&section).to_be_bytes());
+
+        assert!(matches!(
+            read_table(section.clone(), &mut drop_counts),
+            Some(B10Table::Tot(_))
+        ));
+
+        let mut corrupted = section.clone();
+        corrupted[5] = 0x46;
+        assert!(read_table(corrupted, &mut drop_counts).is_none());
+        assert!(read_table(section[..3].to_vec(), &mut drop_counts).is_none());
+
+        assert_eq!(
+            drop_counts,
+            DropCounts {
+                sections: 2,
+                crc_mismatches: 1,
+                ..DropCounts::default()
+            }
+        );
+    }
 }
@@ -1,30 +1,31 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::{BufRead, Cursor, ErrorKind, Read};
 use std::sync::{Arc, Mutex};
 
-use anyhow::anyhow;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
-use tracing::{debug, warn};
+use tracing::{debug, error, warn};
 
 use chibitv_b60::compressed_ip::HcfbPacket;
 use chibitv_b60::deflag::{Defragmenter, State};
 use chibitv_b60::descriptor::{Descriptor, MpuExtendedTimestamp};
+use chibitv_b60::ip::UdpDatagram;
 use chibitv_b60::message::Message;
 use chibitv_b60::mfu::MfuPayload;
 use chibitv_b60::mmtp::{
     FragmentationIndicator, MmtpPacket, MmtpPayload, MpuFragment, MpuFragmentType,
     SignalingMessage, SignalingMessagePayload,
 };
-use chibitv_b60::table::Table;
+use chibitv_b60::ntp::{NTP_PORT, NtpPacket};
+use chibitv_b60::table::{Ecm, MmtGeneralLocation, Mpt, Table};
 use chibitv_b60::tlv::{TlvPacket, TlvPacketType};
-use chibitv_b61::Descrambler;
+use chibitv_b60::tlv_si::TlvSiTable;
+use chibitv_b61::{Descrambler, NoDecryptionKeyError};
 
-use crate::demux::{Demux, MediaPacket, Packet, PacketQueue, SignalingEvent, TrackType};
+use crate::demux::{
+    Demux, DropCounts, MediaPacket, Packet, PacketQueue, SignalingEvent, TrackType,
+};
 use crate::hevc::HevcParser;
 
-// TODO: parse the MMTP packet to get the ECM header
-const ECM_HEADER: [u8; 6] = [0x00, 0x00, 0x93, 0x2D, 0x1E, 0x01];
-
 const MAX_TIMESTAMP_DESCRIPTOR: usize = 64;
 
 #[derive(Clone, Debug)]
@@ -45,20 +46,86 @@ pub struct MmtStream {
 pub struct MmtDemuxer<R: BufRead> {
     reader: R,
     descrambler: Arc<Mutex<Descrambler>>,
+    target_service_id: Option<u16>,
     streams: BTreeMap<u16, Mutex<MmtStream>>,
+    ecm_packets: Mutex<EcmPackets>,
     pending_packets: PacketQueue,
+    drop_counts: DropCounts,
 }
 
 impl<R: BufRead> MmtDemuxer<R> {
     pub fn new(reader: R, descrambler: Descrambler) -> Self {
+        Self::new_inner(reader, descrambler, None)
+    }
+
+    /// Demultiplexes only the assets of the package of the service, out of a
+    /// TLV stream carrying several services.
+    pub fn new_for_service(reader: R, descrambler: Descrambler, service_id: u16) -> Self {
+        Self::new_inner(reader, descrambler, Some(service_id))
+    }
+
+    fn new_inner(reader: R, descrambler: Descrambler, target_service_id: Option<u16>) -> Self {
         Self {
             reader,
             descrambler: Arc::new(Mutex::new(descrambler)),
+            target_service_id,
             streams: BTreeMap::new(),
+            ecm_packets: Mutex::new(EcmPackets::default()),
             pending_packets: PacketQueue::default(),
+            drop_counts: DropCounts::default(),
+        }
+    }
+
+    /// Reads the table of a transmission control signal, which describes the whole TLV stream
+    /// rather than a service of it.
+    fn read_tlv_si_table(&mut self, tlv_packet: TlvPacket) -> Vec<Packet> {
+        let mut bytes = tlv_packet.data;
+        match TlvSiTable::read(&mut bytes) {
+            Ok(TlvSiTable::Unknown(_, _)) => vec![],
+            Ok(table) => vec![Packet::Signaling(SignalingEvent::TlvSiTable(table))],
+            Err(error) => {
+                debug!(%error, "Dropped a transmission control signal that could not be read");
+                count_dropped_section(&mut self.drop_counts, &error);
+
+                vec![]
+            }
         }
     }
 
+    /// Reads the UDP datagram of an IP packet sent without header compression, which carries
+    /// the NTP time or data broadcasting rather than MMTP.
+    fn read_ip_packet(&mut self, tlv_packet: TlvPacket) -> Vec<Packet> {
+        let mut bytes = tlv_packet.data;
+        let datagram = match tlv_packet.packet_type {
+            TlvPacketType::IPv4 => UdpDatagram::read_ipv4(&mut bytes),
+            _ => UdpDatagram::read_ipv6(&mut bytes),
+        };
+        let datagram = match datagram {
+            Ok(Some(datagram)) => datagram,
+            Ok(None) => return vec![],
+            Err(error) => {
+                debug!(%error, "Dropped an IP packet that could not be read");
+                self.drop_counts.packets += 1;
+                return vec![];
+            }
+        };
+
+        let signaling = if datagram.destination.port() == NTP_PORT {
+            match NtpPacket::read(&mut datagram.payload.clone()) {
+                Ok(packet) => SignalingEvent::Ntp(packet),
+                Err(error) => {
+                    debug!(%error, "Dropped an NTP packet that could not be read");
+                    self.drop_counts.packets += 1;
+                    return vec![];
+                }
+            }
+        } else {
+            SignalingEvent::Udp(datagram)
+        };
+
+        vec![Packet::Signaling(signaling)]
+    }
+
     fn read_packets(&mut self) -> anyhow::Result<Option<Vec<Packet>>> {
         let len = self.reader.skip_until(0x7F)?;
         if len == 0 {
@@ -66,35 +133,43 @@ impl<R: BufRead> MmtDemuxer<R> {
             return Ok(None);
         } else if len > 1 {
             debug!("Skipped {} octets.", len - 1);
+            self.drop_counts.octets += len as u64 - 1;
         }
 
         let mut reader = Read::chain(Cursor::new(&[0x7F]), self.reader.by_ref());
 
         let tlv_packet = match TlvPacket::try_read(&mut reader) {
-            Ok(Some(packet)) if packet.packet_type == TlvPacketType::CompressedIP => packet,
-            Ok(_) => return Ok(Some(vec![])),
+            Ok(Some(packet)) => match packet.packet_type {
+                TlvPacketType::CompressedIP => packet,
+                TlvPacketType::IPv4 | TlvPacketType::IPv6 => {
+                    return Ok(Some(self.read_ip_packet(packet)));
+                }
+                TlvPacketType::TransmissionControlSignal => {
+                    return Ok(Some(self.read_tlv_si_table(packet)));
+                }
+                TlvPacketType::Null => return Ok(Some(vec![])),
+            },
+            Ok(None) => {
+                // Not a packet type at all, so the sync byte was part of something else and the
+                // next one is searched for from here.
+                self.drop_counts.packets += 1;
+                return Ok(Some(vec![]));
+            }
             Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
             Err(e) => Err(e)?,
         };
 
-        if let Some(ecm_index) = tlv_packet
-            .data
-            .as_ref()
-            .windows(size_of_val(&ECM_HEADER))
-            .position(|b| b == ECM_HEADER)
-        {
-            self.descrambler.lock().unwrap().push_ecm(
-                (&tlv_packet.data[ecm_index + 2..ecm_index + 150])
-                    .try_into()
-                    .unwrap(),
-            )?;
-
-            return Ok(Some(vec![]));
-        }
-
         let mut bytes = tlv_packet.data;
-        let _hcfb_packet = HcfbPacket::read(&mut bytes)?;
-        let mmtp_packet = MmtpPacket::read(&mut bytes)?;
+        let mmtp_packet = match HcfbPacket::read(&mut bytes)
+            .and_then(|_hcfb_packet| MmtpPacket::read(&mut bytes))
+        {
+            Ok(mmtp_packet) => mmtp_packet,
+            Err(error) => {
+                debug!(%error, "Dropped a TLV packet that could not be read");
+                self.drop_counts.packets += 1;
+                return Ok(Some(vec![]));
+            }
+        };
 
         #[allow(clippy::map_entry)]
         if !self.streams.contains_key(&mmtp_packet.packet_id) {
@@ -122,7 +197,14 @@ impl<R: BufRead> MmtDemuxer<R> {
             .lock()
             .unwrap();
 
-        let mmtp_payload = MmtpPayload::try_from(&mmtp_packet)?;
+        let mmtp_payload = match MmtpPayload::try_from(&mmtp_packet) {
+            Ok(mmtp_payload) => mmtp_payload,
+            Err(error) => {
+                debug!(%error, "Dropped an MMTP packet whose payload could not be read");
+                self.drop_counts.packets += 1;
+                return Ok(Some(vec![]));
+            }
+        };
 
         Ok(Some(match mmtp_payload {
             MmtpPayload::MpuFragment(mut mpu_fragment) => {
@@ -130,10 +212,18 @@ impl<R: BufRead> MmtDemuxer<R> {
                     return Ok(Some(vec![]));
                 }
 
-                assert!(
-                    mpu_fragment.fragmentation_indicator == FragmentationIndicator::NotFragmented
-                        || !mpu_fragment.aggregation_flag
-                );
+                if self.target_service_id.is_some() && stream.asset_type.is_none() {
+                    // Not an asset of the service, so it is not worth descrambling.
+                    return Ok(Some(vec![]));
+                }
+
+                if mpu_fragment.fragmentation_indicator != FragmentationIndicator::NotFragmented
+                    && mpu_fragment.aggregation_flag
+                {
+                    debug!("Dropped an aggregated MPU fragment that is fragmented");
+                    self.drop_counts.packets += 1;
+                    return Ok(Some(vec![]));
+                }
 
                 match stream.deflagmenter.state() {
                     State::Init if !mmtp_packet.rap_flag => {
@@ -143,7 +233,9 @@ impl<R: BufRead> MmtDemuxer<R> {
                     State::Init => {
                         stream.last_sequence_number = mpu_fragment.mpu_sequence_number;
                     }
-                    _ if mpu_fragment.mpu_sequence_number == stream.last_sequence_number + 1 => {
+                    _ if mpu_fragment.mpu_sequence_number
+                        == stream.last_sequence_number.wrapping_add(1) =>
+                    {
                         stream.last_sequence_number = mpu_fragment.mpu_sequence_number;
                         stream.au_count = 0;
                     }
@@ -161,18 +253,36 @@ impl<R: BufRead> MmtDemuxer<R> {
 
                 stream.deflagmenter.sync(mmtp_packet.packet_sequence_number);
 
-                self.descrambler
+                let result = self
+                    .descrambler
                     .lock()
                     .unwrap()
-                    .descramble(&mmtp_packet, mpu_fragment.payload.as_mut_slice())
-                    .map_err(|e| anyhow!("Could not descramble the payload: {}", e))?;
+                    .descramble(&mmtp_packet, mpu_fragment.payload.as_mut_slice());
+                if let Err(error) = result {
+                    // Packets before the key of the first ECM arrives are skipped as those before
+                    // the first RAP are.
+                    if !error.is::<NoDecryptionKeyError>() {
+                        debug!(%error, "Dropped an MPU fragment that could not be descrambled");
+                        self.drop_counts.packets += 1;
+                    }
+                    return Ok(Some(vec![]));
+                }
 
-                Self::read_mfu(&mut stream, mpu_fragment)?
+                match Self::read_mfu(&mut stream, mpu_fragment) {
+                    Ok(packets) => packets,
+                    Err(error) => {
+                        debug!(%error, "Dropped an MFU that could not be read");
+                        self.drop_counts.packets += 1;
+                        vec![]
+                    }
+                }
             }
             MmtpPayload::SignalingMessage(message) => {
                 stream.deflagmenter.sync(mmtp_packet.packet_sequence_number);
 
-                self.read_message(&mut stream, message)
+                let messages =
+                    Self::reassemble_messages(&mut stream, message, &mut self.drop_counts);
+                self.read_messages(&mut stream, messages)
             }
         }))
     }
@@ -212,9 +322,11 @@ impl<R: BufRead> MmtDemuxer<R> {
             .filter_map(|data| {
                 let mut bytes = Bytes::from(data);
 
+                // A corrupted MPU can have more access units than it has timestamps of.
                 if stream.dts_pts.is_none()
                     && let (Some(presentation_time), Some(ext_timestamp), Some(timescale)) =
                         (&timestamp, &ext_timestamp, stream.timescale)
+                    && stream.au_count < ext_timestamp.offsets.len()
                 {
                     // See page 208 of the STD-B60 for this calculation.
 
@@ -230,8 +342,6 @@ impl<R: BufRead> MmtDemuxer<R> {
                     let mut dts_sec = presentation_time
                         - (ext_timestamp.mpu_decoding_time_offset as f64) / timescale;
 
-                    assert!(stream.au_count < ext_timestamp.num_of_au as usize);
-
                     for i in 0..stream.au_count {
                         dts_sec += (ext_timestamp.offsets[i].pts_offset as f64) / timescale;
                     }
@@ -248,8 +358,15 @@ impl<R: BufRead> MmtDemuxer<R> {
                 let data = match &stream.asset_type? {
                     b"hev1" => {
                         // HEVC
-                        let size = bytes.get_u32();
-                        assert_eq!(size as usize, bytes.remaining(), "insufficient buffer size");
+                        let size = bytes.try_get_u32().ok()?;
+                        if size as usize != bytes.remaining() {
+                            warn!(
+                                size,
+                                remaining = bytes.remaining(),
+                                "Dropped a truncated NAL unit"
+                            );
+                            return None;
+                        }
 
                         let mut data = BytesMut::new();
                         data.put_slice(&[0x00, 0x00, 0x01][..]);
@@ -284,8 +401,14 @@ impl<R: BufRead> MmtDemuxer<R> {
             .collect())
     }
 
-    fn read_message(&self, stream: &mut MmtStream, message: SignalingMessage) -> Vec<Packet> {
-        let messages: Vec<_> = match message.payload {
+    /// Reads the messages the signaling message completes, counting those that cannot be read as
+    /// dropped sections.
+    fn reassemble_messages(
+        stream: &mut MmtStream,
+        message: SignalingMessage,
+        drop_counts: &mut DropCounts,
+    ) -> Vec<Message> {
+        let data: Vec<_> = match message.payload {
             SignalingMessagePayload::Aggregated(payloads) => payloads
                 .into_iter()
                 .filter_map(|payload| {
@@ -293,16 +416,27 @@ impl<R: BufRead> MmtDemuxer<R> {
                         .deflagmenter
                         .push(message.fragmentation_indicator, &payload)
                 })
-                .filter_map(|data| Message::read(Cursor::new(data)).ok())
                 .collect(),
             SignalingMessagePayload::Default(payload) => stream
                 .deflagmenter
                 .push(message.fragmentation_indicator, payload.as_slice())
                 .into_iter()
-                .filter_map(|data| Message::read(Cursor::new(data)).ok())
                 .collect(),
         };
 
+        data.into_iter()
+            .filter_map(|data| match Message::read(Cursor::new(data)) {
+                Ok(message) => Some(message),
+                Err(error) => {
+                    debug!(%error, "Dropped a signaling message that could not be read");
+                    count_dropped_section(drop_counts, &error);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn read_messages(&self, stream: &mut MmtStream, messages: Vec<Message>) -> Vec<Packet> {
         let mut packets = Vec::new();
 
         for message in messages {
@@ -312,11 +446,25 @@ impl<R: BufRead> MmtDemuxer<R> {
                         continue;
                     };
 
+                    if let Some(target_service_id) = self.target_service_id
+                        && package_service_id(&mpt.mmt_package_id) != Some(target_service_id)
+                    {
+                        continue;
+                    }
+
+                    self.ecm_packets.lock().unwrap().update(mpt);
+
                     let mut has_video = false;
                     let mut has_audio = false;
 
                     for asset in &mpt.assets {
-                        let packet_id = asset.locations.last().unwrap().packet_id().unwrap();
+                        let Some(packet_id) = asset
+                            .locations
+                            .last()
+                            .and_then(MmtGeneralLocation::packet_id)
+                        else {
+                            continue;
+                        };
 
                         let Some(stream) = self.streams.get(&packet_id) else {
                             continue;
@@ -392,6 +540,23 @@ impl<R: BufRead> MmtDemuxer<R> {
                 }
             }
 
+            if let Message::Ca(message) = &message {
+                match &message.table {
+                    Table::Ecm(ecm)
+                        if self.ecm_packets.lock().unwrap().contains(stream.packet_id) =>
+                    {
+                        self.push_ecm(ecm);
+                    }
+                    Table::Emm(emm) => {
+                        if let Err(error) = self.descrambler.lock().unwrap().push_emm(&emm.emm_data)
+                        {
+                            error!(%error, "Could not give the EMM to the CAS module");
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
             packets.push(Packet::Signaling(SignalingEvent::B60Message(message)));
         }
 
@@ -399,6 +564,23 @@ impl<R: BufRead> MmtDemuxer<R> {
     }
 }
 
+impl<R: BufRead> MmtDemuxer<R> {
+    fn push_ecm(&self, ecm: &Ecm) {
+        let Ok(ecm) = <[u8; 148]>::try_from(ecm.ecm_data.as_slice()) else {
+            warn!(
+                length = ecm.ecm_data.len(),
+                "ECM is not of the length the CAS module takes"
+            );
+            return;
+        };
+
+        // The card may be back by the next ECM, so the stream keeps going without a new key.
+        if let Err(error) = self.descrambler.lock().unwrap().push_ecm(ecm) {
+            error!(%error, "Could not decrypt ECM");
+        }
+    }
+}
+
 impl<R: BufRead> Demux for MmtDemuxer<R> {
     fn next_packet(&mut self) -> anyhow::Result<Option<Packet>> {
         loop {
@@ -412,4 +594,335 @@ impl<R: BufRead> Demux for MmtDemuxer<R> {
             self.pending_packets.extend(packets);
         }
     }
+
+    fn drop_counts(&self) -> DropCounts {
+        self.drop_counts
+    }
+}
+
+/// The service a package belongs to, which ARIB STD-B60 places in the last two
+/// octets of its MMT package id.
+fn package_service_id(mmt_package_id: &[u8]) -> Option<u16> {
+    let [.., high, low] = mmt_package_id else {
+        return None;
+    };
+
+    Some(u16::from_be_bytes([*high, *low]))
+}
+
+/// Counts a section that could not be read, telling those failing their CRC_32 check apart.
+fn count_dropped_section(drop_counts: &mut DropCounts, error: &std::io::Error) {
+    drop_counts.sections += 1;
+    if error
+        .get_ref()
+        .and_then(|error| error.downcast_ref::<chibitv_b10::Error>())
+        .is_some_and(chibitv_b10::Error::is_crc_mismatch)
+    {
+        drop_counts.crc_mismatches += 1;
+    }
+}
+
+/// The packets the access control descriptors of the current MPT of each package send the ECMs
+/// in.
+#[derive(Debug, Default)]
+struct EcmPackets {
+    packet_ids: BTreeMap<Vec<u8>, BTreeSet<u16>>,
+}
+
+impl EcmPackets {
+    /// Takes the packets of the MPT in place of those of the last version of its package, so
+    /// that the packets an MPT no longer announces stop sending ECMs.
+    fn update(&mut self, mpt: &Mpt) {
+        self.packet_ids
+            .insert(mpt.mmt_package_id.clone(), ecm_packet_ids(mpt).collect());
+    }
+
+    fn contains(&self, packet_id: u16) -> bool {
+        self.packet_ids
+            .values()
+            .any(|packet_ids| packet_ids.contains(&packet_id))
+    }
+}
+
+/// The packets the access control descriptors of the package and its assets send the ECMs in.
+fn ecm_packet_ids(mpt: &Mpt) -> impl Iterator<Item = u16> + '_ {
+    mpt.mmt_descriptors
+        .iter()
+        .chain(mpt.assets.iter().flat_map(|asset| &asset.asset_descriptors))
+        .filter_map(|descriptor| match descriptor {
+            Descriptor::AccessControl(descriptor) => descriptor.location.packet_id(),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use chibitv_b60::descriptor::AccessControlDescriptor;
+    use chibitv_b60::table::{MmtAsset, MptMode};
+    use chibitv_cas::KeyCache;
+
+    use super::*;
+
+    #[test]
+    fn reads_the_service_of_a_package() {
+        assert_eq!(
+            package_service_id(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x65]),
+            Some(0x0065)
+        );
+        assert_eq!(package_service_id(&[0x01, 0x01]), Some(0x0101));
+        assert_eq!(package_service_id(&[0x01]), None);
+    }
+
+    /// An MPT of the package sending the ECMs of the package and of its video asset in the
+    /// packets.
+    fn mpt(mmt_package_id: &[u8], version: u8, ecm_packet_ids: [u16; 2]) -> Mpt {
+        let access_control = |packet_id| {
+            Descriptor::AccessControl(AccessControlDescriptor {
+                ca_system_id: 0x0005,
+                location: MmtGeneralLocation::None { packet_id },
+                private_data: vec![],
+            })
+        };
+
+        Mpt {
+            version,
+            mpt_mode: MptMode::Ordered,
+            mmt_package_id: mmt_package_id.to_vec(),
+            mmt_descriptors: vec![access_control(ecm_packet_ids[0])],
+            assets: vec![MmtAsset {
+                identifier_type: 0,
+                asset_id_scheme: [0; 4],
+                asset_id: vec![],
+                asset_type: *b"hev1",
+                asset_clock_relation_flag: false,
+                locations: vec![MmtGeneralLocation::None { packet_id: 0x0100 }],
+                asset_descriptors: vec![access_control(ecm_packet_ids[1])],
+            }],
+        }
+    }
+
+    #[test]
+    fn reads_the_ecm_packets_of_the_access_control_descriptors() {
+        let mpt = mpt(&[0x00, 0x65], 0, [0x8301, 0x8302]);
+
+        assert_eq!(ecm_packet_ids(&mpt).collect::<Vec<_>>(), [0x8301, 0x8302]);
+    }
+
+    #[test]
+    fn takes_the_ecm_packets_of_the_current_mpt_of_each_package() {
+        let mut ecm_packets = EcmPackets::default();
+        ecm_packets.update(&mpt(&[0x00, 0x65], 0, [0x8301, 0x8302]));
+        ecm_packets.update(&mpt(&[0x00, 0x66], 0, [0x8401, 0x8402]));
+        assert!(ecm_packets.contains(0x8301));
+        assert!(ecm_packets.contains(0x8401));
+
+        // The new version of the MPT of one package leaves those of the other alone.
+        ecm_packets.update(&mpt(&[0x00, 0x65], 1, [0x8303, 0x8303]));
+        assert!(!ecm_packets.contains(0x8301));
+        assert!(!ecm_packets.contains(0x8302));
+        assert!(ecm_packets.contains(0x8303));
+        assert!(ecm_packets.contains(0x8401));
+    }
+
+    static KEY_CACHE: KeyCache<chibitv_b61::DecryptionKey> = KeyCache::new();
+
+    struct NoKeys;
+
+    impl chibitv_b61::KeyStore for NoKeys {
+        fn key(&self, _ecm: &[u8; 148]) -> Option<chibitv_b61::DecryptionKey> {
+            None
+        }
+    }
+
+    #[test]
+    fn resyncs_on_the_next_tlv_packet() {
+        let input = [
+            // Octets before the first sync byte.
+            &[0x00, 0x11][..],
+            // A sync byte followed by no packet type.
+            &[0x7F, 0x42],
+            // A null packet.
+            &[0x7F, 0xFF, 0x00, 0x00],
+            // A compressed IP packet too short for its header.
+            &[0x7F, 0x03, 0x00, 0x02, 0xAB, 0xCD],
+        ]
+        .concat();
+        let descrambler = Descrambler::init_keyless(Arc::new(NoKeys), &KEY_CACHE, false);
+        let mut demux = MmtDemuxer::new(Cursor::new(input), descrambler);
+
+        assert!(demux.next_packet().unwrap().is_none());
+        assert_eq!(
+            demux.drop_counts(),
+            DropCounts {
+                octets: 2,
+                packets: 2,
+                ..DropCounts::default()
+            }
+        );
+    }
+
+    /// A TLV packet of an MMTP packet of the first octet, sent with no header compressed.
+    fn mmtp_packet(head: u8, extension_header: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x00, 0x10, 0x61];
+        data.extend([head, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        data.extend([0x00, 0x00, 0x00, 0x00]);
+        data.extend(extension_header);
+        data.extend(payload);
+
+        let mut packet = vec![0x7F, 0x03];
+        packet.extend((data.len() as u16).to_be_bytes());
+        packet.extend(data);
+
+        packet
+    }
+
+    #[test]
+    fn drops_the_mpu_fragments_that_cannot_be_read() {
+        const RAP: u8 = 0x01;
+        const EXTENSION_HEADER: u8 = 0x02;
+        let mfu = [0x00, 0x06, 0x28, 0x00, 0x00, 0x00, 0x00, 0x01];
+        let input = [
+            // An MPU fragment shorter than its header.
+            mmtp_packet(RAP, &[], &[0x00, 0x02, 0x28, 0x00, 0x00, 0x00, 0x00, 0x01]),
+            // An aggregated MPU fragment that is the head of a fragmented one.
+            mmtp_packet(RAP, &[], &[0x00, 0x06, 0x2B, 0x00, 0x00, 0x00, 0x00, 0x01]),
+            // A scrambling extension header of an unknown type.
+            mmtp_packet(RAP | EXTENSION_HEADER, &[0x00, 0x01, 0x00, 0x00], &mfu),
+            // A timed MFU without its header.
+            mmtp_packet(RAP, &[], &mfu),
+        ]
+        .concat();
+        let descrambler = Descrambler::init_keyless(Arc::new(NoKeys), &KEY_CACHE, false);
+        let mut demux = MmtDemuxer::new(Cursor::new(input), descrambler);
+
+        assert!(demux.next_packet().unwrap().is_none());
+        assert_eq!(demux.drop_counts().packets, 4);
+    }
+
+    /// A TLV packet of an MMTP packet of the packet id, carrying a CA message of the section
+    /// that is not fragmented.
+    fn ca_message_packet(packet_id: u16, section: &[u8]) -> Vec<u8> {
+        let mut data = vec![0x00, 0x10, 0x61];
+        data.extend([0x00, 0x02]);
+        data.extend(packet_id.to_be_bytes());
+        data.extend([0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        data.extend([0x00, 0x00, 0x80, 0x01, 0x00]);
+        data.extend((section.len() as u16).to_be_bytes());
+        data.extend(section);
+
+        let mut packet = vec![0x7F, 0x03];
+        packet.extend((data.len() as u16).to_be_bytes());
+        packet.extend(data);
+
+        packet
+    }
+
+    #[test]
+    fn drops_the_ecms_failing_their_crc_check() {
+        let mut ecm = [
+            &[0x82, 0xB0, 0x0D, 0x00, 0x00, 0xC1, 0x00, 0x00][..],
+            &[0x93, 0x2D, 0x1E, 0x01],
+        ]
+        .concat();
+        ecm.extend(chibitv_b10::crc_32(&ecm).to_be_bytes());
+        // The same ECM with a bit flipped on the way.
+        let mut corrupted = ecm.clone();
+        corrupted[9] ^= 0x01;
+        let input = [
+            ca_message_packet(0x8301, &ecm),
+            ca_message_packet(0x8301, &corrupted),
+        ]
+        .concat();
+        let descrambler = Descrambler::init_keyless(Arc::new(NoKeys), &KEY_CACHE, false);
+        let mut demux = MmtDemuxer::new(Cursor::new(input), descrambler);
+
+        assert!(matches!(
+            demux.next_packet().unwrap(),
+            Some(Packet::Signaling(SignalingEvent::B60Message(Message::Ca(message))))
+                if matches!(message.table, Table::Ecm(_))
+        ));
+        assert!(demux.next_packet().unwrap().is_none());
+        assert_eq!(demux.drop_counts().sections, 1);
+        assert_eq!(demux.drop_counts().crc_mismatches, 1);
+    }
+
+    /// A TLV packet of an IPv4 packet carrying a UDP datagram to the port.
+    fn ipv4_udp_packet(destination_port: u16, payload: &[u8]) -> Vec<u8> {
+        let udp_length = 8 + payload.len() as u16;
+        let total_length = 20 + udp_length;
+
+        let mut packet = vec![0x7F, 0x01];
+        packet.extend(total_length.to_be_bytes());
+        packet.extend([0x45, 0x00]);
+        packet.extend(total_length.to_be_bytes());
+        packet.extend([0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00]);
+        packet.extend([192, 168, 0, 1, 239, 0, 0, 1]);
+        packet.extend(1234_u16.to_be_bytes());
+        packet.extend(destination_port.to_be_bytes());
+        packet.extend(udp_length.to_be_bytes());
+        packet.extend([0x00, 0x00]);
+        packet.extend(payload);
+
+        packet
+    }
+
+    #[test]
+    fn exposes_the_udp_datagrams_sent_outside_of_mmtp() {
+        let mut ntp = vec![0x25, 0x01, 0x04, 0xEC];
+        ntp.extend([0; 44]);
+        let input = [
+            ipv4_udp_packet(123, &ntp),
+            ipv4_udp_packet(5678, &[0xAA]),
+            // An NTP packet too short for its header.
+            ipv4_udp_packet(123, &[0x25]),
+        ]
+        .concat();
+        let descrambler = Descrambler::init_keyless(Arc::new(NoKeys), &KEY_CACHE, false);
+        let mut demux = MmtDemuxer::new(Cursor::new(input), descrambler);
+
+        assert!(matches!(
+            demux.next_packet().unwrap(),
+            Some(Packet::Signaling(SignalingEvent::Ntp(packet))) if packet.mode == 5
+        ));
+        assert!(matches!(
+            demux.next_packet().unwrap(),
+            Some(Packet::Signaling(SignalingEvent::Udp(datagram)))
+                if datagram.destination.port() == 5678 && datagram.payload.as_ref() == [0xAA]
+        ));
+        assert!(demux.next_packet().unwrap().is_none());
+        assert_eq!(demux.drop_counts().packets, 1);
+    }
+
+    #[test]
+    fn reads_the_tables_of_transmission_control_signals() {
+        let amt = [
+            &[0xFE, 0xB0, 0x19, 0xFF, 0xFF, 0xC1, 0x00, 0x00, 0x00, 0x40][..],
+            &[0x00, 0x65, 0x00, 0x0A, 192, 168, 0, 1, 32, 239, 0, 0, 1, 32],
+            &[0xC3, 0x24, 0x8B, 0x0B],
+        ]
+        .concat();
+        let mut input = vec![0x7F, 0xFE];
+        input.extend((amt.len() as u16).to_be_bytes());
+        input.extend(&amt);
+        // The same AMT with a bit flipped on the way.
+        let mut corrupted = amt;
+        corrupted[12] ^= 0x01;
+        input.extend([0x7F, 0xFE]);
+        input.extend((corrupted.len() as u16).to_be_bytes());
+        input.extend(corrupted);
+        // An AMT cut short in its service loop.
+        input.extend([0x7F, 0xFE, 0x00, 0x0C, 0xFE, 0xB0, 0x0F, 0xFF, 0xFF, 0xC1]);
+        input.extend([0x00, 0x00, 0x00, 0x40, 0x00, 0x65]);
+        let descrambler = Descrambler::init_keyless(Arc::new(NoKeys), &KEY_CACHE, false);
+        let mut demux = MmtDemuxer::new(Cursor::new(input), descrambler);
+
+        assert!(matches!(
+            demux.next_packet().unwrap(),
+            Some(Packet::Signaling(SignalingEvent::TlvSiTable(TlvSiTable::Amt(amt))))
+                if amt.services.len() == 1 && amt.services[0].service_id == 0x0065
+        ));
+        assert!(demux.next_packet().unwrap().is_none());
+        assert_eq!(demux.drop_counts().sections, 2);
+        assert_eq!(demux.drop_counts().crc_mismatches, 1);
+    }
 }
@@ -3,12 +3,15 @@ use std::io::{Cursor, Seek, SeekFrom, Write};
 use std::num::{NonZeroU16, NonZeroU32};
 
 use bytes::{BufMut, Bytes, BytesMut};
+use cros_codecs::codec::h264::parser::{
+    Nalu as H264Nalu, NaluType as H264NaluType, Parser as H264Parser, Sps as H264Sps,
+};
 use cros_codecs::codec::h265::parser::{
     Nalu, NaluType, Parser as H265Parser, Pps, ProfileTierLevel, Sps, Vps,
 };
 use shiguredo_mp4::boxes::{
-    AudioSampleEntryFields, EsdsBox, Hev1Box, HvccBox, HvccNalUintArray, Mp4aBox, Mp4vBox,
-    SampleEntry, VisualSampleEntryFields,
+    AudioSampleEntryFields, Avc1Box, AvccBox, EsdsBox, Hev1Box, HvccBox, HvccNalUintArray, Mp4aBox,
+    Mp4vBox, SampleEntry, VisualSampleEntryFields,
 };
 use shiguredo_mp4::descriptors::{
     DecoderConfigDescriptor, DecoderSpecificInfo, EsDescriptor, SlConfigDescriptor,
@@ -24,6 +27,9 @@ use crate::remux::Mux;
 
 const VIDEO_TIMESCALE: u32 = 90_000;
 
+/// H.264 profiles whose SPS carries the chroma format and the bit depths.
+const AVC_HIGH_PROFILES: &[u8] = &[100, 110, 122, 244, 44, 83, 86, 118, 128, 138, 139, 134, 135];
+
 #[derive(Clone, Debug)]
 struct TrackMetadata {
     sample_duration: u32,
@@ -345,6 +351,139 @@ impl Track for H265Track {
     }
 }
 
+/// A one-seg H.264 track, whose PES packets each carry an access unit in Annex B format.
+struct H264Track {
+    parser: H264Parser,
+    sps: Option<(Bytes, H264Sps)>,
+    pps: Option<Bytes>,
+    metadata: Option<TrackMetadata>,
+    pending: Option<PendingSample>,
+}
+
+impl H264Track {
+    /// One-seg video runs at 15 fps, used until the DTS of the next sample tells otherwise.
+    const DEFAULT_SAMPLE_DURATION: u32 = VIDEO_TIMESCALE / 15;
+
+    fn new() -> Self {
+        Self {
+            parser: H264Parser::default(),
+            sps: None,
+            pps: None,
+            metadata: None,
+            pending: None,
+        }
+    }
+}
+
+impl Track for H264Track {
+    fn write_sample(
+        &mut self,
+        data: Bytes,
+        dts: Option<f64>,
+        pts: Option<f64>,
+    ) -> anyhow::Result<Vec<TrackSample>> {
+        let mut keyframe = false;
+        let mut sample_entry = None::<SampleEntry>;
+        let mut bytes = BytesMut::new();
+
+        let mut cursor = Cursor::new(data.as_ref());
+        while let Ok(nalu) = H264Nalu::next(&mut cursor) {
+            match nalu.header.type_ {
+                H264NaluType::Sps if self.metadata.is_none() => {
+                    match self.parser.parse_sps(&nalu) {
+                        Ok(sps) => {
+                            debug!("SPS NALU found: {:?}", sps);
+                            self.sps =
+                                Some((Bytes::copy_from_slice(nalu.as_ref()), H264Sps::clone(sps)));
+                        }
+                        Err(err) => error!("SPS parse error: {}", err),
+                    }
+                }
+                H264NaluType::Pps if self.metadata.is_none() => {
+                    self.pps = Some(Bytes::copy_from_slice(nalu.as_ref()));
+                }
+                H264NaluType::SliceIdr => {
+                    keyframe = true;
+                }
+                _ => {}
+            }
+
+            let data = nalu.as_ref();
+            bytes.put_u32(data.len() as u32);
+            bytes.put(data);
+        }
+
+        if self.metadata.is_none()
+            && let (Some(sps), Some(pps)) = (&self.sps, &self.pps)
+        {
+            sample_entry = Some(build_avc1_sample_entry(sps, pps));
+
+            self.metadata = Some(TrackMetadata {
+                sample_duration: Self::DEFAULT_SAMPLE_DURATION,
+                timescale: VIDEO_TIMESCALE,
+            });
+
+            debug!("H264 track is ready: {:?}", &self.metadata);
+        }
+
+        let Some(metadata) = &self.metadata else {
+            // Stream is not ready yet.
+            return Ok(vec![]);
+        };
+
+        let sample = Sample {
+            track_kind: TrackKind::Video,
+            sample_entry,
+            keyframe,
+            timescale: NonZeroU32::new(metadata.timescale).unwrap(),
+            duration: metadata.sample_duration,
+            composition_time_offset: pts
+                .zip(dts)
+                .map(|(pts, dts)| seconds_to_timescale_units(pts - dts, metadata.timescale)),
+            data_offset: 0,
+            data_size: bytes.len(),
+        };
+
+        // Without B-frames, one-seg encoders often send the PTS alone.
+        let data = bytes.freeze();
+        let Some(dts) = dts.or(pts) else {
+            return Ok(vec![TrackSample {
+                sample,
+                data,
+                dts: None,
+            }]);
+        };
+
+        let Some(mut pending) = self.pending.replace(PendingSample { sample, data, dts }) else {
+            return Ok(vec![]);
+        };
+
+        let duration = seconds_to_timescale_units(dts - pending.dts, metadata.timescale);
+        if duration > 0 {
+            pending.sample.duration = duration as u32;
+        }
+
+        Ok(vec![TrackSample {
+            sample: pending.sample,
+            data: pending.data,
+            dts: Some(pending.dts),
+        }])
+    }
+
+    fn finalize(&mut self) -> anyhow::Result<Vec<TrackSample>> {
+        Ok(self
+            .pending
+            .take()
+            .map(|pending| TrackSample {
+                sample: pending.sample,
+                data: pending.data,
+                dts: Some(pending.dts),
+            })
+            .into_iter()
+            .collect())
+    }
+}
+
 struct AacAdtsTrack {
     parser: AdtsParser,
     metadata: Option<TrackMetadata>,
@@ -555,6 +694,10 @@ impl<W: Write + Seek> Mux for Mp4Muxer<W> {
                     .insert(track_id, Box::new(AacAdtsTrack::new()));
                 info!(track_id, "Added an AAC-ADTS audio track");
             }
+            TrackType::H264 => {
+                self.track_map.insert(track_id, Box::new(H264Track::new()));
+                info!(track_id, "Added a H264 video track");
+            }
             TrackType::H265 => {
                 self.track_map.insert(track_id, Box::new(H265Track::new()));
                 info!(track_id, "Added a H265 video track");
@@ -755,6 +898,11 @@ impl<W: WriteMp4Fragment> Mux for FragmentedMp4Muxer<W> {
                 self.track_states.entry(track_id).or_default();
                 info!(track_id, "Added an AAC-ADTS audio track");
             }
+            TrackType::H264 => {
+                self.track_map.insert(track_id, Box::new(H264Track::new()));
+                self.track_states.entry(track_id).or_default();
+                info!(track_id, "Added a H264 video track");
+            }
             TrackType::H265 => {
                 self.track_map.insert(track_id, Box::new(H265Track::new()));
                 self.track_states.entry(track_id).or_default();
@@ -894,6 +1042,47 @@ fn compressor_name() -> [u8; 32] {
     value
 }
 
+fn build_avc1_sample_entry(sps: &(Bytes, H264Sps), pps: &Bytes) -> SampleEntry {
+    let (sps_raw, sps) = sps;
+    let is_high_profile = AVC_HIGH_PROFILES.contains(&sps.profile_idc);
+
+    let avcc_box = AvccBox {
+        avc_profile_indication: sps.profile_idc,
+        profile_compatibility: (sps.constraint_set0_flag as u8) << 7
+            | (sps.constraint_set1_flag as u8) << 6
+            | (sps.constraint_set2_flag as u8) << 5
+            | (sps.constraint_set3_flag as u8) << 4
+            | (sps.constraint_set4_flag as u8) << 3
+            | (sps.constraint_set5_flag as u8) << 2,
+        avc_level_indication: sps.level_idc as u8,
+        length_size_minus_one: Uint::new(3), // NAL length size
+        sps_list: vec![sps_raw.to_vec()],
+        pps_list: vec![pps.to_vec()],
+        chroma_format: is_high_profile.then(|| Uint::new(sps.chroma_format_idc)),
+        bit_depth_luma_minus8: is_high_profile.then(|| Uint::new(sps.bit_depth_luma_minus8)),
+        bit_depth_chroma_minus8: is_high_profile.then(|| Uint::new(sps.bit_depth_chroma_minus8)),
+        sps_ext_list: vec![],
+    };
+    let visible = sps.visible_rectangle();
+
+    let visual = VisualSampleEntryFields {
+        data_reference_index: VisualSampleEntryFields::DEFAULT_DATA_REFERENCE_INDEX,
+        width: (visible.max.x - visible.min.x) as u16,
+        height: (visible.max.y - visible.min.y) as u16,
+        horizresolution: VisualSampleEntryFields::DEFAULT_HORIZRESOLUTION,
+        vertresolution: VisualSampleEntryFields::DEFAULT_VERTRESOLUTION,
+        frame_count: VisualSampleEntryFields::DEFAULT_FRAME_COUNT,
+        compressorname: compressor_name(),
+        depth: VisualSampleEntryFields::DEFAULT_DEPTH,
+    };
+
+    SampleEntry::Avc1(Avc1Box {
+        visual,
+        avcc_box,
+        unknown_boxes: vec![],
+    })
+}
+
 fn build_hev1_sample_entry(
     vps: &(Bytes, Vps),
     pps: &(Bytes, Pps),
@@ -1051,6 +1240,17 @@ mod tests {
         Bytes::from(data)
     }
 
+    /// An access unit of a one-seg stream: a baseline SPS of 320x180 cropped out of 320x192, a
+    /// PPS and an IDR slice.
+    fn one_seg_idr_access_unit() -> Bytes {
+        Bytes::from_static(&[
+            0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0xC0, 0x0C, 0xDA, 0x05, 0x06, 0x7E,
+            0x74, // SPS
+            0x00, 0x00, 0x00, 0x01, 0x68, 0xCE, 0x3C, 0x80, // PPS
+            0x00, 0x00, 0x01, 0x65, 0x88, 0x84, 0x00, // IDR slice
+        ])
+    }
+
     #[test]
     fn creates_mp4v_samples_from_mpeg2_video() {
         let mut track = Mpeg2VideoTrack::new();
@@ -1074,6 +1274,25 @@ mod tests {
         assert!(samples[0].data.starts_with(&[0x00, 0x00, 0x01, 0xB3]));
     }
 
+    #[test]
+    fn creates_avc1_samples_from_one_seg_video() {
+        let mut track = H264Track::new();
+        let samples = track
+            .write_sample(one_seg_idr_access_unit(), None, None)
+            .unwrap();
+
+        assert_eq!(samples.len(), 1);
+        assert!(samples[0].sample.keyframe);
+        let Some(SampleEntry::Avc1(avc1)) = &samples[0].sample.sample_entry else {
+            panic!("not an avc1 sample entry");
+        };
+        assert_eq!((avc1.visual.width, avc1.visual.height), (320, 180));
+        assert_eq!(avc1.avcc_box.avc_profile_indication, 66);
+        assert_eq!(avc1.avcc_box.avc_level_indication, 12);
+        assert!(avc1.avcc_box.chroma_format.is_none());
+        assert!(samples[0].data.starts_with(&[0x00, 0x00, 0x00, 0x09, 0x67]));
+    }
+
     #[test]
     fn creates_mp4a_samples_from_adts_without_the_adts_header() {
         let mut track = AacAdtsTrack::new();
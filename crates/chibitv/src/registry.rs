@@ -1,4 +1,5 @@
-use std::sync::Arc;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
 use chrono::{NaiveDateTime, TimeDelta};
@@ -7,7 +8,7 @@ use tracing::{debug, info};
 
 use chibitv_b10::descriptor::Descriptor as B10Descriptor;
 use chibitv_b10::table::{
-    EventInformation as B10EventInformation, ServiceInformation as B10ServiceInformation,
+    EventInformation as B10EventInformation, Nit, ServiceInformation as B10ServiceInformation,
 };
 use chibitv_b24::decode as decode_b24;
 use chibitv_b60::descriptor::Descriptor;
@@ -74,9 +75,34 @@ impl Event {
 pub struct Registry {
     broadcasters: HashMap<u8, Broadcaster>,
     services: HashMap<u16, Service>,
+    one_seg_services: Mutex<OneSegServices>,
     events: Option<EventWriter>,
 }
 
+/// The one-seg services of ISDB-T, which the SDT tells apart from other data services only along
+/// with the NIT.
+#[derive(Default)]
+struct OneSegServices {
+    /// The services announced by the NIT.
+    service_ids: BTreeSet<u16>,
+    /// The data services the SDT announced without the NIT having announced them, with the
+    /// channel and transport stream they were announced on, registered once the NIT does.
+    pending: BTreeMap<u16, (usize, u16, B10ServiceInformation)>,
+}
+
+/// The services of the transport streams in the NIT that are carried in the centre segment alone.
+pub fn partial_reception_service_ids(nit: &Nit) -> impl Iterator<Item = u16> + '_ {
+    nit.transport_streams
+        .iter()
+        .flat_map(|transport_stream| &transport_stream.descriptors)
+        .filter_map(|descriptor| match descriptor {
+            B10Descriptor::PartialReception(descriptor) => Some(&descriptor.service_ids),
+            _ => None,
+        })
+        .flatten()
+        .copied()
+}
+
 impl Registry {
     /// Keeps the schedule this collects between runs.
     pub fn storing_events(mut self, events: EventWriter) -> Self {
@@ -230,8 +256,20 @@ impl Registry {
             return;
         };
 
-        // Digital television service.
-        if descriptor.service_type != 0x01 {
+        // Digital television service, or a data service when it is the one-seg of the channel.
+        let is_one_seg = descriptor.service_type == 0xC0 && {
+            let mut one_seg_services = self.one_seg_services.lock().unwrap();
+            let is_announced = one_seg_services.service_ids.contains(&service_id);
+            if !is_announced {
+                one_seg_services.pending.insert(
+                    service_id,
+                    (channel_id, transport_stream_id, service.clone()),
+                );
+            }
+
+            is_announced
+        };
+        if descriptor.service_type != 0x01 && !is_one_seg {
             return;
         }
 
@@ -252,6 +290,30 @@ impl Registry {
         services.insert(service_id, service);
     }
 
+    /// Takes the one-seg services from the NIT, so that they are registered along with the
+    /// television services from the SDT, including those the SDT announced first.
+    pub fn put_b10_partial_reception(&self, nit: &Nit) {
+        let announced = {
+            let mut one_seg_services = self.one_seg_services.lock().unwrap();
+            one_seg_services
+                .service_ids
+                .extend(partial_reception_service_ids(nit));
+
+            let OneSegServices {
+                service_ids,
+                pending,
+            } = &mut *one_seg_services;
+            pending
+                .extract_if(.., |service_id, _| service_ids.contains(service_id))
+                .map(|(_, service)| service)
+                .collect::<Vec<_>>()
+        };
+
+        for (channel_id, transport_stream_id, service) in announced {
+            self.put_b10_service(channel_id, transport_stream_id, &service);
+        }
+    }
+
     pub fn put_cached_service(
         &self,
         channel_id: usize,
@@ -500,9 +562,10 @@ mod tests {
     use chrono::{Duration, NaiveDate};
 
     use chibitv_b10::descriptor::{
-        Descriptor as B10Descriptor, ExtendedEventDescriptor, ExtendedEventItem, ServiceDescriptor,
-        ShortEventDescriptor,
+        Descriptor as B10Descriptor, ExtendedEventDescriptor, ExtendedEventItem,
+        PartialReceptionDescriptor, ServiceDescriptor, ShortEventDescriptor,
     };
+    use chibitv_b10::table::TransportStreamInformation;
     use chibitv_b60::descriptor::{
         Descriptor as B60Descriptor, ExtendedEventItem as MhExtendedEventItem,
         MhExtendedEventDescriptor, MhServiceDescriptor, MhShortEventDescriptor,
@@ -664,6 +727,74 @@ mod tests {
         );
     }
 
+    fn data_service(service_id: u16) -> B10ServiceInformation {
+        B10ServiceInformation {
+            service_id,
+            eit_user_defined_flags: 0,
+            eit_schedule_flag: true,
+            eit_present_following_flag: true,
+            running_status: 4,
+            free_ca_mode: false,
+            descriptors: vec![B10Descriptor::Service(ServiceDescriptor {
+                service_type: 0xC0,
+                service_provider_name: b"\x0eProvider".to_vec(),
+                service_name: b"\x0eOne-seg".to_vec(),
+            })],
+        }
+    }
+
+    /// A NIT announcing the service as the one-seg of its transport stream.
+    fn partial_reception_nit(service_id: u16) -> Nit {
+        Nit {
+            section_syntax_indicator: true,
+            section_length: 0,
+            network_id: 0x7FE0,
+            version_number: 0,
+            current_next_indicator: true,
+            section_number: 0,
+            last_section_number: 0,
+            descriptors: vec![],
+            transport_streams: vec![TransportStreamInformation {
+                transport_stream_id: 0x7FE0,
+                original_network_id: 0x7FE0,
+                descriptors: vec![B10Descriptor::PartialReception(
+                    PartialReceptionDescriptor {
+                        service_ids: vec![service_id],
+                    },
+                )],
+            }],
+            crc_32: 0,
+        }
+    }
+
+    #[test]
+    fn registers_the_one_seg_service_announced_by_the_nit() {
+        let registry = Registry::default();
+
+        registry.put_b10_partial_reception(&partial_reception_nit(0x0418));
+        registry.put_b10_service(0, 0x7FE0, &data_service(0x0418));
+        registry.put_b10_service(0, 0x7FE0, &data_service(0x0420));
+
+        assert_eq!(registry.get_service_by_id(0x0418).unwrap().name, "One-seg");
+        assert!(registry.get_service_by_id(0x0420).is_none());
+    }
+
+    #[test]
+    fn registers_the_one_seg_service_the_sdt_announced_before_the_nit() {
+        let registry = Registry::default();
+
+        registry.put_b10_service(0, 0x7FE0, &data_service(0x0418));
+        registry.put_b10_service(0, 0x7FE0, &data_service(0x0420));
+        assert!(registry.get_service_by_id(0x0418).is_none());
+
+        registry.put_b10_partial_reception(&partial_reception_nit(0x0418));
+
+        let service = registry.get_service_by_id(0x0418).unwrap();
+        assert_eq!(service.name, "One-seg");
+        assert_eq!(service.transport_stream_id, 0x7FE0);
+        assert!(registry.get_service_by_id(0x0420).is_none());
+    }
+
     #[test]
     fn registers_isdb_t_service_and_event() {
         let registry = Registry::default();
@@ -1,7 +1,9 @@
+use std::collections::BTreeSet;
+
 use bytes::Bytes;
-use tracing::error;
+use tracing::{error, warn};
 
-use crate::demux::{Demux, MediaPacket, Packet, SignalingEvent, TrackType};
+use crate::demux::{Demux, DropCounts, MediaPacket, Packet, SignalingEvent, TrackType};
 
 pub trait Mux {
     /// Adds a track to the stream.
@@ -56,7 +58,26 @@ impl<D: Demux, M: Mux> Remuxer<D, M> {
         }
     }
 
+    pub fn wanted_pids(&self) -> Option<BTreeSet<u16>> {
+        self.demux.wanted_pids()
+    }
+
+    pub fn drop_counts(&self) -> DropCounts {
+        self.demux.drop_counts()
+    }
+
     pub fn finish(mut self) -> anyhow::Result<()> {
+        let drop_counts = self.drop_counts();
+        if drop_counts != DropCounts::default() {
+            warn!(
+                octets = drop_counts.octets,
+                packets = drop_counts.packets,
+                sections = drop_counts.sections,
+                crc_mismatches = drop_counts.crc_mismatches,
+                "Dropped what could not be read of the input"
+            );
+        }
+
         self.mux.finalize()
     }
 
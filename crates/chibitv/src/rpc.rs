@@ -13,6 +13,7 @@ use crate::event_crawler::CrawledEvent;
 use crate::proto::chibitv::v1::*;
 use crate::registry;
 use crate::service_information::Signal;
+use crate::tuner;
 use crate::workspace::{StreamSubscription, Workspace, WorkspaceError};
 
 pub struct ChibitvServiceImpl {
@@ -146,8 +147,17 @@ impl ChibitvService for ChibitvServiceImpl {
             );
 
             if let Err(error) = result {
-                tracing::error!(%error, "Event refresh failed");
-                let _ = tx.blocking_send(Err(ConnectError::unavailable("event refresh failed")));
+                let error = match error.downcast_ref::<tuner::Preempted>() {
+                    Some(preempted) => {
+                        tracing::info!(%preempted, "Event refresh preempted");
+                        ConnectError::aborted(format!("event refresh stopped: {preempted}"))
+                    }
+                    None => {
+                        tracing::error!(%error, "Event refresh failed");
+                        ConnectError::unavailable("event refresh failed")
+                    }
+                };
+                let _ = tx.blocking_send(Err(error));
             }
         });
 
@@ -167,36 +177,91 @@ impl ChibitvService for ChibitvServiceImpl {
             init_segment,
             fmp4,
             signals,
+            receptions,
         } = self
             .workspace
             .subscribe_stream(service_id)
             .await
             .map_err(workspace_error)?;
 
-        let initial_state = tokio_stream::iter([stream_state(&self.workspace, &stream, None)]);
-        let init_segment = tokio_stream::iter(init_segment.into_iter().map(fmp4_response));
-        let fmp4 = fmp4.filter_map(|data| data.ok().map(fmp4_response));
+        let initial_state = tokio_stream::iter([Ok(stream_state(&self.workspace, &stream, None))]);
+        let init_segment =
+            tokio_stream::iter(init_segment.into_iter().map(|data| Ok(fmp4_response(data))));
+        let fmp4 = fmp4.filter_map(|data| data.ok().map(|data| Ok(fmp4_response(data))));
         let states = {
             let workspace = Arc::clone(&self.workspace);
             let stream = Arc::clone(&stream);
             signals.filter_map(move |signal| match signal.ok()? {
                 Signal::EventChanged { event_id } => {
-                    Some(stream_state(&workspace, &stream, Some(event_id)))
+                    Some(Ok(stream_state(&workspace, &stream, Some(event_id))))
                 }
+                // The stream carries nothing anymore, so it ends with the reason.
+                Signal::Preempted { by } => Some(Err(ConnectError::aborted(format!(
+                    "the tuner was taken over for {by}"
+                )))),
             })
         };
 
+        let receptions = {
+            let workspace = Arc::clone(&self.workspace);
+            let stream = Arc::clone(&stream);
+            receptions.map(move |_| Ok(stream_state(&workspace, &stream, None)))
+        };
+
         // The stream keeps the tuner occupied, so it is moved into the
         // response stream to release the tuner once every client is gone.
         Response::stream_ok(
             initial_state
-                .chain(init_segment.chain(fmp4).merge(states))
+                .chain(init_segment.chain(fmp4).merge(states).merge(receptions))
                 .map(move |response| {
                     let _stream = &stream;
-                    Ok(response)
+                    response
                 }),
         )
     }
+
+    async fn list_tuners(
+        &self,
+        _ctx: RequestContext,
+        _request: ServiceRequest<'_, ListTunersRequest>,
+    ) -> ServiceResult<ListTunersResponse> {
+        let tuners = self
+            .workspace
+            .tuners()
+            .ok_or_else(|| ConnectError::failed_precondition("tuners are unavailable"))?;
+
+        // Measuring waits on the frontend, which a lease may be tuning.
+        let statuses = tokio::task::spawn_blocking(move || tuners.statuses())
+            .await
+            .map_err(|_| ConnectError::internal("failed to measure the tuners"))?;
+
+        Response::ok(ListTunersResponse {
+            tuners: statuses.iter().map(TunerStatus::from).collect(),
+            ..Default::default()
+        })
+    }
+
+    async fn get_tuner_status(
+        &self,
+        _ctx: RequestContext,
+        request: ServiceRequest<'_, GetTunerStatusRequest>,
+    ) -> ServiceResult<GetTunerStatusResponse> {
+        let tuners = self
+            .workspace
+            .tuners()
+            .ok_or_else(|| ConnectError::failed_precondition("tuners are unavailable"))?;
+        let tuner_id = request.tuner_id;
+
+        let status = tokio::task::spawn_blocking(move || tuners.status(tuner_id))
+            .await
+            .map_err(|_| ConnectError::internal("failed to measure the tuner"))?
+            .ok_or_else(|| ConnectError::not_found("tuner not found"))?;
+
+        Response::ok(GetTunerStatusResponse {
+            tuner: Some(TunerStatus::from(&status)).into(),
+            ..Default::default()
+        })
+    }
 }
 
 fn crawled_event_message(value: CrawledEvent) -> Event {
@@ -222,6 +287,7 @@ fn stream_state(
                 .zip(event.as_ref())
                 .map(|(service, event)| event_message(service.id, event))
                 .into(),
+            reception: reception(stream.reception()).into(),
             ..Default::default()
         }))),
         ..Default::default()
@@ -235,10 +301,18 @@ fn fmp4_response(data: bytes::Bytes) -> StreamResponse {
     }
 }
 
+fn reception(value: crate::stream::Reception) -> Reception {
+    match value {
+        crate::stream::Reception::Receiving => Reception::Receiving,
+        crate::stream::Reception::SignalLost => Reception::SignalLost,
+        crate::stream::Reception::Recovering => Reception::Recovering,
+    }
+}
+
 fn delivery_system(inner: &ChannelInner) -> DeliverySystem {
     match inner {
         ChannelInner::IsdbT { .. } => DeliverySystem::IsdbT,
-        ChannelInner::IsdbS { .. } => DeliverySystem::IsdbS,
+        ChannelInner::IsdbS { .. } | ChannelInner::IsdbSTs { .. } => DeliverySystem::IsdbS,
     }
 }
 
@@ -269,6 +343,41 @@ impl From<&registry::Service> for Service {
     }
 }
 
+impl From<&tuner::TunerStatus> for TunerStatus {
+    fn from(value: &tuner::TunerStatus) -> Self {
+        Self {
+            id: value.id,
+            leased: value.in_use,
+            channel_id: value.channel.as_ref().map(|channel| channel.id as u32),
+            stats: value.stats.as_ref().map(TunerStats::from).into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&tuner::TunerStats> for TunerStats {
+    fn from(value: &tuner::TunerStats) -> Self {
+        Self {
+            locked: value.locked,
+            signal_strength_dbm: value.signal_strength_dbm,
+            cnr_db: value.cnr_db,
+            pre_ber: value.pre_ber,
+            post_ber: value.post_ber,
+            layers: value
+                .layers
+                .iter()
+                .map(|layer| LayerStats {
+                    cnr_db: layer.cnr_db,
+                    pre_ber: layer.pre_ber,
+                    post_ber: layer.post_ber,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
 fn event_message(service_id: u16, value: &registry::Event) -> Event {
     Event {
         id: value.id.into(),
@@ -3,14 +3,16 @@ use std::sync::Arc;
 
 use tokio::sync::broadcast::Sender;
 
-use chibitv_b10::table::{Eit, Sdt, Table as B10Table};
+use chibitv_b10::table::{Eit, Nit, Sdt, Table as B10Table};
 use chibitv_b60::message::{M2SectionMessage, Message};
 use chibitv_b60::table::{MhBit, MhEit, MhSdt, Table};
 
 use crate::demux::SignalingEvent;
 use crate::registry::Registry;
 use crate::store::SectionId;
+use crate::tuner::LeasePriority;
 
+const NIT_ACTUAL_TABLE_ID: u8 = 0x40;
 const SDT_ACTUAL_TABLE_ID: u8 = 0x42;
 const EIT_ACTUAL_PRESENT_FOLLOWING_TABLE_ID: u8 = 0x4E;
 const EIT_ACTUAL_SCHEDULE_TABLE_IDS: std::ops::RangeInclusive<u8> = 0x50..=0x5F;
@@ -18,7 +20,13 @@ const MH_EIT_ACTUAL_SCHEDULE_TABLE_IDS: std::ops::RangeInclusive<u8> = 0x8C..=0x
 
 #[derive(Clone, Debug)]
 pub enum Signal {
-    EventChanged { event_id: u16 },
+    EventChanged {
+        event_id: u16,
+    },
+    /// The tuner was taken over by a request of a higher priority, which ends the stream.
+    Preempted {
+        by: LeasePriority,
+    },
 }
 
 /// Identifies one EIT section among the ones a stream carries.
@@ -29,7 +37,7 @@ pub enum Signal {
 struct SectionKey {
     table_id: u8,
     original_network_id: u16,
-    /// The TLV stream id on ISDB-S, the transport stream id on ISDB-T.
+    /// The TLV stream id on ISDB-S, the transport stream id on ISDB-S TS and ISDB-T.
     stream_id: u16,
     service_id: u16,
     section_number: u8,
@@ -123,7 +131,10 @@ impl ServiceInformationProcessor {
             SignalingEvent::B60Message(Message::M2Section(message)) => {
                 self.process_m2_section_message(message)
             }
-            SignalingEvent::B60Message(_) => Ok(()),
+            SignalingEvent::B60Message(_)
+            | SignalingEvent::TlvSiTable(_)
+            | SignalingEvent::Ntp(_)
+            | SignalingEvent::Udp(_) => Ok(()),
         }
     }
 
@@ -135,6 +146,10 @@ impl ServiceInformationProcessor {
             {
                 self.process_b10_eit(table_id, table)
             }
+            B10Table::Nit(table) if table_id == NIT_ACTUAL_TABLE_ID => {
+                self.process_b10_nit(&table);
+                Ok(())
+            }
             B10Table::Sdt(table) if table_id == SDT_ACTUAL_TABLE_ID => {
                 self.process_b10_sdt(table);
                 Ok(())
@@ -143,6 +158,12 @@ impl ServiceInformationProcessor {
         }
     }
 
+    fn process_b10_nit(&self, table: &Nit) {
+        if let Some(registry) = &self.registry {
+            registry.put_b10_partial_reception(table);
+        }
+    }
+
     fn process_b10_sdt(&self, table: Sdt) {
         if let Some(registry) = &self.registry {
             for service in &table.services {
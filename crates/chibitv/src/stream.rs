@@ -1,3 +1,5 @@
+mod session;
+
 use std::collections::HashMap;
 use std::io::BufReader;
 use std::sync::{Arc, Mutex, RwLock, Weak};
@@ -5,30 +7,32 @@ use std::time::Duration;
 
 use bytes::Bytes;
 use tokio::sync::broadcast::{Receiver, Sender, channel as broadcast_channel};
+use tokio::sync::watch;
 use tracing::info;
 
-use chibitv_b25::B25Descrambler;
-use chibitv_b61::Descrambler;
-
-use crate::cas::PcscCasModule;
+use crate::cas::CasPool;
 use crate::channel::{Channel, ChannelInner};
-use crate::demux::Demux;
+use crate::demux::{Demux, DropReporter};
 use crate::m2ts::M2tsDemuxer;
 use crate::mmt::MmtDemuxer;
 use crate::mp4::{FragmentedMp4Muxer, WriteMp4Fragment};
 use crate::registry::Registry;
 use crate::remux::Remuxer;
 use crate::service_information::{ServiceInformationProcessor, Signal};
-use crate::tuner::{AcquireError, TunerLease, Tuners};
+use crate::tuner::{AcquireError, LeasePriority, Preemption, TunerLease, Tuners};
+
+pub use self::session::Reception;
+use self::session::{PidRequest, SessionReader, TunerSession};
 
 const READ_BUFFER_SIZE: usize = 188 * 8192;
 const BROADCAST_CAPACITY: usize = 8192;
 
-/// How long a subscriber keeps waiting for a tuner to become free.
+/// How long a subscriber keeps waiting for a tuner to become free, including
+/// the wait for a preempted lease to give its tuner back.
 ///
-/// A stream that just lost its last subscriber releases its tuner
-/// asynchronously (the remuxer thread has to notice the kill signal first), so
-/// a channel switch briefly sees every tuner in use.
+/// A session that just lost its last stream releases its tuner asynchronously
+/// (the watchdog thread has to notice the kill signal first), so a channel switch
+/// briefly sees every tuner in use.
 const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(5);
 const ACQUIRE_RETRY_INTERVAL: Duration = Duration::from_millis(100);
 
@@ -65,11 +69,15 @@ impl WriteMp4Fragment for Fmp4StreamWriter {
 
 /// A single tuned service, shared by every client streaming it.
 ///
-/// The tuner stays occupied as long as at least one `Arc` of the stream is
-/// alive; dropping the last one signals the remuxer thread to stop, which
-/// closes the tuner device and releases the lease.
+/// Dropping the last `Arc` of the stream signals the remuxer thread to stop.
+/// The tuner is shared with the other services of the physical channel through
+/// a [`TunerSession`], and is released once none of them is streamed anymore.
+/// A stream whose tuner is preempted stops on its own and sends
+/// [`Signal::Preempted`] to its clients, while one whose signal is lost keeps
+/// waiting for the session to recover it.
 pub struct Stream {
     service_id: u16,
+    session: Arc<TunerSession>,
     event_id: Arc<RwLock<Option<u16>>>,
     fmp4_tx: Sender<Bytes>,
     fmp4_init_segment: Arc<Mutex<Option<Bytes>>>,
@@ -86,6 +94,11 @@ impl Stream {
         *self.event_id.read().unwrap()
     }
 
+    /// Whether the stream has given its tuner away, and will not carry anything anymore.
+    pub fn is_preempted(&self) -> bool {
+        self.session.preemption().preempted_by().is_some()
+    }
+
     pub fn subscribe_fmp4(&self) -> (Option<Bytes>, Receiver<Bytes>) {
         let init_segment = self.fmp4_init_segment.lock().unwrap();
         let rx = self.fmp4_tx.subscribe();
@@ -100,6 +113,15 @@ impl Stream {
     pub fn subscribe_signal(&self) -> Receiver<Signal> {
         self.signal_tx.subscribe()
     }
+
+    /// Whether the tuner of the stream is receiving the channel.
+    pub fn reception(&self) -> Reception {
+        self.session.reception()
+    }
+
+    pub fn subscribe_reception(&self) -> tokio::sync::watch::Receiver<Reception> {
+        self.session.subscribe_reception()
+    }
 }
 
 impl Drop for Stream {
@@ -112,89 +134,228 @@ impl Drop for Stream {
     }
 }
 
-/// Starts and shares [`Stream`]s, one per requested service.
+/// A service in the map of the streams.
+enum StreamSlot {
+    Running(Weak<Stream>),
+    /// The stream is being started on the channel. The sender of `started` is
+    /// dropped once it has started or failed, or its request was cancelled.
+    Starting {
+        channel_id: usize,
+        started: watch::Receiver<()>,
+    },
+}
+
+impl StreamSlot {
+    /// The stream, unless it has stopped or given its tuner away.
+    fn running(&self) -> Option<Arc<Stream>> {
+        match self {
+            Self::Running(stream) => stream.upgrade().filter(|stream| !stream.is_preempted()),
+            Self::Starting { .. } => None,
+        }
+    }
+
+    /// What to wait for while a stream is being started on the channel.
+    fn starting_on(&self, channel: usize) -> Option<watch::Receiver<()>> {
+        match self {
+            Self::Starting {
+                channel_id,
+                started,
+            } if *channel_id == channel && started.has_changed().is_ok() => Some(started.clone()),
+            _ => None,
+        }
+    }
+
+    fn is_live(&self) -> bool {
+        match self {
+            Self::Running(stream) => stream.strong_count() > 0,
+            Self::Starting { started, .. } => started.has_changed().is_ok(),
+        }
+    }
+}
+
+/// Starts and shares [`Stream`]s, one per requested service, and the
+/// [`TunerSession`]s they are demultiplexed from, one per physical channel.
 pub struct Streams {
     registry: Arc<Registry>,
     tuners: Arc<Tuners>,
-    cas: Arc<PcscCasModule>,
-    b61_descrambler: Option<Descrambler>,
-    streams: tokio::sync::Mutex<HashMap<u16, Weak<Stream>>>,
+    cas: Arc<CasPool>,
+    streams: Mutex<HashMap<u16, StreamSlot>>,
+    sessions: Arc<Mutex<HashMap<usize, Weak<TunerSession>>>>,
 }
 
 impl Streams {
-    pub fn new(
-        registry: Arc<Registry>,
-        tuners: Arc<Tuners>,
-        cas: Arc<PcscCasModule>,
-        b61_descrambler: Option<Descrambler>,
-    ) -> Self {
+    pub fn new(registry: Arc<Registry>, tuners: Arc<Tuners>, cas: Arc<CasPool>) -> Self {
         Self {
             registry,
             tuners,
             cas,
-            b61_descrambler,
-            streams: tokio::sync::Mutex::new(HashMap::new()),
+            streams: Mutex::new(HashMap::new()),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    /// Returns the running stream for the service, starting one on a free
-    /// tuner when nobody is streaming it yet.
+    /// Returns the running stream for the service, starting one when nobody is
+    /// streaming it yet. A new stream joins the tuner already tuned to its
+    /// physical channel, and leases a free one only when there is none.
+    ///
+    /// The streams lock is held only to look the service up. Concurrent
+    /// requests for a channel that is being tuned wait for it to be started
+    /// and share its session instead of racing for another tuner, while
+    /// requests for other channels are not held up by the tuning.
     pub async fn subscribe(
         &self,
         service_id: u16,
         channel: &Channel,
     ) -> Result<Arc<Stream>, SubscribeError> {
-        let deadline = tokio::time::Instant::now() + ACQUIRE_TIMEOUT;
+        let started_tx = loop {
+            let mut started = {
+                let mut streams = self.streams.lock().unwrap();
+                if let Some(stream) = streams.get(&service_id).and_then(StreamSlot::running) {
+                    return Ok(stream);
+                }
 
-        loop {
-            let mut streams = self.streams.lock().await;
-            if let Some(stream) = streams.get(&service_id).and_then(Weak::upgrade) {
-                return Ok(stream);
+                let starting = streams
+                    .values()
+                    .find_map(|slot| slot.starting_on(channel.id));
+                match starting {
+                    Some(started) => started,
+                    None => {
+                        let (started_tx, started) = watch::channel(());
+                        streams.retain(|_, slot| slot.is_live());
+                        streams.insert(
+                            service_id,
+                            StreamSlot::Starting {
+                                channel_id: channel.id,
+                                started,
+                            },
+                        );
+                        break started_tx;
+                    }
+                }
+            };
+
+            // Nothing is ever sent, so this returns once the sender is dropped.
+            let _ = started.changed().await;
+        };
+
+        let result = self.start(service_id, channel).await;
+
+        let mut streams = self.streams.lock().unwrap();
+        match &result {
+            Ok(stream) => {
+                streams.insert(service_id, StreamSlot::Running(Arc::downgrade(stream)));
+            }
+            Err(_) => {
+                streams.remove(&service_id);
             }
+        }
+        drop(streams);
+        drop(started_tx);
+
+        result
+    }
 
+    /// Starts the stream on the session already tuned to the channel, or on a
+    /// tuner leased for it until the deadline.
+    async fn start(
+        &self,
+        service_id: u16,
+        channel: &Channel,
+    ) -> Result<Arc<Stream>, SubscribeError> {
+        let deadline = tokio::time::Instant::now() + ACQUIRE_TIMEOUT;
+        let mut tuner = None;
+
+        loop {
             // Tuning is blocking device I/O, so it runs off the async runtime.
-            // The streams lock is held across it on purpose: concurrent
-            // requests for the same service must wait and share the stream
-            // instead of racing for another tuner.
-            let starter = self.stream_starter(service_id, channel);
-            let result = tokio::task::spawn_blocking(starter)
+            let starter = self.stream_starter(service_id, channel, tuner.take());
+            match tokio::task::spawn_blocking(starter)
                 .await
-                .map_err(|error| SubscribeError::Internal(error.into()))?;
+                .map_err(|error| SubscribeError::Internal(error.into()))?
+            {
+                Err(SubscribeError::TunerBusy) => {}
+                result => return result,
+            }
+
+            // Waiting for a tuner, which a preempted lease may take a while to
+            // give back, holds no runtime thread.
+            tuner = Some(self.wait_for_tuner(channel, deadline).await?);
+        }
+    }
+
+    /// Leases a tuner for the channel, preempting a lease of a lower priority
+    /// when every tuner is in use, until the deadline.
+    async fn wait_for_tuner(
+        &self,
+        channel: &Channel,
+        deadline: tokio::time::Instant,
+    ) -> Result<TunerLease, SubscribeError> {
+        let delivery_system = channel.inner.delivery_system();
+
+        loop {
+            let tuners = Arc::clone(&self.tuners);
+            let timeout = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let result = tokio::task::spawn_blocking(move || {
+                tuners.acquire_within(delivery_system, LeasePriority::Live, timeout)
+            })
+            .await
+            .map_err(|error| SubscribeError::Internal(error.into()))?;
 
             match result {
-                Ok(stream) => {
-                    streams.retain(|_, stream| stream.strong_count() > 0);
-                    streams.insert(service_id, Arc::downgrade(&stream));
-                    return Ok(stream);
+                Ok(tuner) => return Ok(tuner),
+                Err(AcquireError::Busy) if tokio::time::Instant::now() < deadline => {}
+                Err(AcquireError::Busy) => return Err(SubscribeError::TunerBusy),
+                Err(error @ (AcquireError::NotConfigured | AcquireError::Unsupported(_))) => {
+                    return Err(SubscribeError::Internal(error.into()));
                 }
-                Err(SubscribeError::TunerBusy) if tokio::time::Instant::now() < deadline => {}
-                Err(error) => return Err(error),
             }
 
-            drop(streams);
             tokio::time::sleep(ACQUIRE_RETRY_INTERVAL).await;
         }
     }
 
+    /// Starts the stream on the session already tuned to the channel, or on a
+    /// new session of the tuner. Fails with [`SubscribeError::TunerBusy`] when
+    /// there is neither.
     fn stream_starter(
         &self,
         service_id: u16,
         channel: &Channel,
+        tuner: Option<TunerLease>,
     ) -> impl FnOnce() -> Result<Arc<Stream>, SubscribeError> + Send + 'static {
         let registry = Arc::clone(&self.registry);
-        let tuners = Arc::clone(&self.tuners);
+        let sessions = Arc::clone(&self.sessions);
         let cas = Arc::clone(&self.cas);
-        let b61_descrambler = self.b61_descrambler.clone();
         let channel = channel.clone();
 
         move || {
-            let tuner = tuners.try_acquire().map_err(|error| match error {
-                AcquireError::Busy => SubscribeError::TunerBusy,
-                AcquireError::NotConfigured => SubscribeError::Internal(error.into()),
-            })?;
-            info!(tuner_id = tuner.id(), service_id, "Acquired tuner");
+            let running = sessions
+                .lock()
+                .unwrap()
+                .get(&channel.id)
+                .and_then(Weak::upgrade)
+                .filter(|session| session.is_running());
+
+            let (session, reader) = match running {
+                Some(session) => {
+                    let reader = session.reader().map_err(SubscribeError::Internal)?;
+                    (session, reader)
+                }
+                None => {
+                    let tuner = tuner.ok_or(SubscribeError::TunerBusy)?;
+                    info!(tuner_id = tuner.id(), service_id, "Acquired tuner");
+
+                    let (session, reader) =
+                        TunerSession::start(tuner, &channel).map_err(SubscribeError::Internal)?;
+
+                    let mut sessions = sessions.lock().unwrap();
+                    sessions.retain(|_, session| session.strong_count() > 0);
+                    sessions.insert(channel.id, Arc::downgrade(&session));
 
-            start_stream(registry, cas, b61_descrambler, tuner, service_id, &channel)
+                    (session, reader)
+                }
+            };
+
+            start_stream(registry, &cas, session, reader, service_id, &channel)
                 .map_err(SubscribeError::Internal)
         }
     }
@@ -202,40 +363,39 @@ impl Streams {
 
 fn start_stream(
     registry: Arc<Registry>,
-    cas: Arc<PcscCasModule>,
-    b61_descrambler: Option<Descrambler>,
-    tuner: TunerLease,
+    cas: &CasPool,
+    session: Arc<TunerSession>,
+    reader: SessionReader,
     service_id: u16,
     channel: &Channel,
 ) -> anyhow::Result<Arc<Stream>> {
-    tuner.tune(channel.clone())?;
-    let reader = tuner.open()?;
-
     let (fmp4_tx, _) = broadcast_channel::<Bytes>(BROADCAST_CAPACITY);
     let fmp4_init_segment = Arc::new(Mutex::new(None));
     let (signal_tx, _) = broadcast_channel::<Signal>(16);
     let event_id = Arc::new(RwLock::new(None));
+    let pid_request = reader.pid_request();
 
     let kill_tx = match &channel.inner {
         ChannelInner::IsdbS { .. } => {
-            let descrambler = b61_descrambler
-                .ok_or_else(|| anyhow::anyhow!("B61 descrambler is not configured"))?;
+            let descrambler = cas.b61_descrambler(true)?;
             let reader = BufReader::with_capacity(READ_BUFFER_SIZE, reader);
             spawn_remuxer(
-                MmtDemuxer::new(reader, descrambler),
+                MmtDemuxer::new_for_service(reader, descrambler, service_id),
                 StreamTarget {
                     channel_id: channel.id,
                     service_id: Some(service_id),
                 },
                 registry,
+                session.preemption(),
+                pid_request,
                 &fmp4_tx,
                 &fmp4_init_segment,
                 &signal_tx,
                 &event_id,
             )
         }
-        ChannelInner::IsdbT { .. } => {
-            let descrambler = B25Descrambler::init(cas)?;
+        ChannelInner::IsdbSTs { .. } | ChannelInner::IsdbT { .. } => {
+            let descrambler = cas.b25_descrambler()?;
             // A service of zero streams the whole transport stream instead of
             // picking one service out of it.
             let target_service_id = (service_id != 0).then_some(service_id);
@@ -250,6 +410,8 @@ fn start_stream(
                     service_id: target_service_id,
                 },
                 registry,
+                session.preemption(),
+                pid_request,
                 &fmp4_tx,
                 &fmp4_init_segment,
                 &signal_tx,
@@ -262,6 +424,7 @@ fn start_stream(
 
     Ok(Arc::new(Stream {
         service_id,
+        session,
         event_id,
         fmp4_tx,
         fmp4_init_segment,
@@ -274,6 +437,8 @@ fn spawn_remuxer<D>(
     demux: D,
     target: StreamTarget,
     registry: Arc<Registry>,
+    preemption: &Preemption,
+    pid_request: Arc<PidRequest>,
     fmp4_tx: &Sender<Bytes>,
     fmp4_init_segment: &Arc<Mutex<Option<Bytes>>>,
     signal_tx: &Sender<Signal>,
@@ -296,11 +461,14 @@ where
     .watching_service(target.service_id);
 
     let (kill_tx, mut kill_rx) = tokio::sync::oneshot::channel();
+    let preemption = preemption.clone();
+    let signal_tx = signal_tx.clone();
     let event_id = Arc::clone(event_id);
     std::thread::spawn(move || {
         let result = (|| -> anyhow::Result<()> {
+            let mut drop_reporter = DropReporter::new(target.channel_id);
             loop {
-                if kill_rx.try_recv().is_ok() {
+                if kill_rx.try_recv().is_ok() || preemption.preempted_by().is_some() {
                     break;
                 }
 
@@ -309,6 +477,18 @@ where
                 };
                 processor.process(signaling)?;
                 *event_id.write().unwrap() = processor.current_event_id();
+
+                // Once the demuxer knows the PIDs of its service, the tuner can
+                // stop passing the rest of the multiplex.
+                pid_request.set(remuxer.wanted_pids());
+                drop_reporter.report(remuxer.drop_counts());
+            }
+
+            // A preempted session stops reading, which may end the input
+            // before the remuxer has noticed the preemption itself.
+            if let Some(priority) = preemption.preempted_by() {
+                info!(channel_id = target.channel_id, %priority, "Stream preempted");
+                let _ = signal_tx.send(Signal::Preempted { by: priority });
             }
 
             remuxer.finish()
@@ -0,0 +1,633 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::{Receiver, Sender, WeakSender, channel as broadcast_channel};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::channel::Channel;
+use crate::tuner::{Preemption, TunerLease};
+
+/// How much of the tuner input is passed on at once.
+const CHUNK_SIZE: usize = 188 * 256;
+
+/// How many chunks a stream may fall behind the tuner before it loses some.
+const CHUNK_CAPACITY: usize = 256;
+
+/// How long the input may carry nothing before the signal is considered lost.
+const STALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the watchdog looks at the input when nothing arrives.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the watchdog asks the frontend whether it is still locked.
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait between the first attempts to tune again, doubling up to the maximum.
+const FIRST_RETUNE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETUNE_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How long a stopping session waits for the input to be closed before releasing the tuner.
+const INPUT_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Whether a session is receiving its channel, as reported to the clients of its streams.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Reception {
+    #[default]
+    Receiving,
+    /// The frontend lost its lock, or the input stalled or ended.
+    SignalLost,
+    /// The tuner has been tuned again, and the input has yet to resume.
+    Recovering,
+}
+
+impl std::fmt::Display for Reception {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Receiving => write!(f, "receiving"),
+            Self::SignalLost => write!(f, "signal lost"),
+            Self::Recovering => write!(f, "recovering"),
+        }
+    }
+}
+
+/// A tuner tuned to one physical channel, shared by the stream of every service it carries.
+///
+/// The raw input is read on a thread of its own and handed to every [`SessionReader`] as it is,
+/// so that each stream demultiplexes its service out of it by itself. A watchdog tunes again when
+/// the input stalls or the frontend loses its lock, and the readers carry on once it resumes.
+/// The tuner stays occupied as long as at least one `Arc` of the session is alive.
+pub struct TunerSession {
+    channel_id: usize,
+    tuner_id: u32,
+    preemption: Preemption,
+    data_tx: WeakSender<Bytes>,
+    pid_requests: Arc<Mutex<PidRequests>>,
+    reception_rx: watch::Receiver<Reception>,
+    kill_tx: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl TunerSession {
+    /// Tunes the leased tuner to the channel and starts reading it, returning the first reader of
+    /// the session so that it misses nothing.
+    pub fn start(
+        tuner: TunerLease,
+        channel: &Channel,
+    ) -> anyhow::Result<(Arc<Self>, SessionReader)> {
+        tuner.tune(channel.clone())?;
+
+        let tuner_id = tuner.id();
+        let preemption = tuner.preemption();
+        let input = InputReader::spawn(tuner.open_reader()?);
+
+        let (data_tx, data_rx) = broadcast_channel::<Bytes>(CHUNK_CAPACITY);
+        let (kill_tx, kill_rx) = tokio::sync::oneshot::channel();
+        let (reception_tx, reception_rx) = watch::channel(Reception::Receiving);
+        let pid_requests = Arc::new(Mutex::new(PidRequests::default()));
+        let reader = SessionReader::new(data_rx, &pid_requests);
+        let session = Arc::new(Self {
+            channel_id: channel.id,
+            tuner_id,
+            preemption,
+            data_tx: data_tx.downgrade(),
+            pid_requests: Arc::clone(&pid_requests),
+            reception_rx,
+            kill_tx: Some(kill_tx),
+        });
+
+        let watchdog = Watchdog {
+            tuner,
+            channel: channel.clone(),
+            input: Some(input),
+            data_tx,
+            reception_tx,
+            pid_requests,
+        };
+        std::thread::spawn(move || watchdog.run(kill_rx));
+
+        info!(tuner_id, channel = %channel.name, "Tuner session started");
+
+        Ok((session, reader))
+    }
+
+    pub fn preemption(&self) -> &Preemption {
+        &self.preemption
+    }
+
+    /// Whether the tuner is still being read, so that another stream can join the session.
+    pub fn is_running(&self) -> bool {
+        self.preemption.preempted_by().is_none() && self.data_tx.strong_count() > 0
+    }
+
+    pub fn reception(&self) -> Reception {
+        *self.reception_rx.borrow()
+    }
+
+    /// Follows the reception of the session, which changes as the watchdog notices an outage
+    /// and recovers from it.
+    pub fn subscribe_reception(&self) -> watch::Receiver<Reception> {
+        self.reception_rx.clone()
+    }
+
+    /// Starts reading the tuner input from now on.
+    pub fn reader(&self) -> anyhow::Result<SessionReader> {
+        let data_tx = self
+            .data_tx
+            .upgrade()
+            .ok_or_else(|| anyhow::anyhow!("The tuner session has ended"))?;
+
+        Ok(SessionReader::new(data_tx.subscribe(), &self.pid_requests))
+    }
+}
+
+impl Drop for TunerSession {
+    fn drop(&mut self) {
+        if let Some(kill_tx) = self.kill_tx.take() {
+            let _ = kill_tx.send(());
+        }
+
+        info!(
+            tuner_id = self.tuner_id,
+            channel_id = self.channel_id,
+            "Tuner session stopped"
+        );
+    }
+}
+
+/// Passes the input of a session on to its readers, tuning again whenever the signal is lost.
+struct Watchdog {
+    tuner: TunerLease,
+    channel: Channel,
+    /// `None` once the input has ended or been closed to tune again, until it is opened again.
+    input: Option<InputReader>,
+    data_tx: Sender<Bytes>,
+    reception_tx: watch::Sender<Reception>,
+    pid_requests: Arc<Mutex<PidRequests>>,
+}
+
+impl Watchdog {
+    fn run(mut self, mut kill_rx: tokio::sync::oneshot::Receiver<()>) {
+        let preemption = self.tuner.preemption();
+        let mut last_input = Instant::now();
+        let mut locked = true;
+        let mut next_lock_check = Instant::now() + LOCK_CHECK_INTERVAL;
+        let mut retune_backoff = FIRST_RETUNE_BACKOFF;
+        let mut next_retune = Instant::now();
+
+        loop {
+            if kill_rx.try_recv().is_ok() || preemption.preempted_by().is_some() {
+                break;
+            }
+
+            let filter = self.pid_requests.lock().unwrap().take_filter();
+            if let Some(pids) = filter
+                && let Err(error) = self.tuner.set_pid_filter(pids.as_ref())
+            {
+                warn!(channel = %self.channel.name, %error, "Could not filter the tuner input");
+            }
+
+            match self.input.as_ref().map(InputReader::recv) {
+                Some(Ok(chunk)) => {
+                    last_input = Instant::now();
+                    retune_backoff = FIRST_RETUNE_BACKOFF;
+                    self.set_reception(Reception::Receiving);
+
+                    // Nobody listening is fine: the first reader may not have been handed out yet.
+                    let _ = self.data_tx.send(chunk);
+                    continue;
+                }
+                Some(Err(RecvTimeoutError::Timeout)) => {}
+                Some(Err(RecvTimeoutError::Disconnected)) => self.input = None,
+                None => std::thread::sleep(WATCHDOG_INTERVAL),
+            }
+
+            let now = Instant::now();
+            if now >= next_lock_check {
+                next_lock_check = now + LOCK_CHECK_INTERVAL;
+                // Tuners that cannot tell are trusted to be locked while the input flows.
+                locked = !matches!(self.tuner.stats(), Ok(Some(stats)) if !stats.locked);
+            }
+
+            let lost =
+                self.input.is_none() || !locked || now.duration_since(last_input) >= STALL_TIMEOUT;
+            if !lost {
+                continue;
+            }
+
+            self.set_reception(Reception::SignalLost);
+            if now < next_retune {
+                continue;
+            }
+
+            self.set_reception(Reception::Recovering);
+            if let Err(error) = self.retune() {
+                warn!(channel = %self.channel.name, %error, "Could not tune to the channel again");
+                self.set_reception(Reception::SignalLost);
+            }
+
+            last_input = Instant::now();
+            locked = true;
+            next_lock_check = last_input + LOCK_CHECK_INTERVAL;
+            next_retune = last_input + retune_backoff;
+            retune_backoff = (retune_backoff * 2).min(MAX_RETUNE_BACKOFF);
+        }
+
+        // The tuner is released before the readers learn that the session has ended.
+        let Self {
+            tuner,
+            input,
+            data_tx,
+            ..
+        } = self;
+        if let Some(input) = input {
+            input.close();
+        }
+        drop(tuner);
+        drop(data_tx);
+    }
+
+    /// Tunes to the channel again, closing the input first and opening it again afterwards.
+    ///
+    /// A stalled input does not resume by itself, and most tuners cannot be read twice at once.
+    fn retune(&mut self) -> anyhow::Result<()> {
+        if let Some(input) = self.input.take() {
+            input.close();
+        }
+
+        self.tuner.tune(self.channel.clone())?;
+
+        // Tuning passes every PID again.
+        self.pid_requests.lock().unwrap().changed = true;
+
+        self.input = Some(InputReader::spawn(self.tuner.open_reader()?));
+
+        Ok(())
+    }
+
+    fn set_reception(&self, reception: Reception) {
+        let changed = self.reception_tx.send_if_modified(|current| {
+            let changed = *current != reception;
+            *current = reception;
+            changed
+        });
+
+        if changed {
+            info!(
+                tuner_id = self.tuner.id(),
+                channel = %self.channel.name,
+                %reception,
+                "Reception changed"
+            );
+        }
+    }
+}
+
+/// Reads the input of a tuner on a thread of its own, so that a stalled read cannot keep the
+/// watchdog from noticing.
+struct InputReader {
+    chunk_rx: mpsc::Receiver<Bytes>,
+    stop: Arc<AtomicBool>,
+}
+
+impl InputReader {
+    fn spawn(mut input: Box<dyn Read + Send + Sync>) -> Self {
+        let (chunk_tx, chunk_rx) = mpsc::sync_channel(CHUNK_CAPACITY);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        {
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                let mut buf = vec![0; CHUNK_SIZE];
+
+                while !stop.load(Ordering::Relaxed) {
+                    let len = match input.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(len) => len,
+                        Err(error) => {
+                            warn!(%error, "Could not read the tuner input");
+                            break;
+                        }
+                    };
+
+                    if chunk_tx.send(Bytes::copy_from_slice(&buf[..len])).is_err() {
+                        break;
+                    }
+                }
+
+                // Closed before the channel is, so that `close` returns once the input is closed.
+                drop(input);
+            });
+        }
+
+        Self { chunk_rx, stop }
+    }
+
+    fn recv(&self) -> Result<Bytes, RecvTimeoutError> {
+        self.chunk_rx.recv_timeout(WATCHDOG_INTERVAL)
+    }
+
+    /// Stops reading and waits for the input to be closed, unless its read has stalled.
+    fn close(self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        let deadline = Instant::now() + INPUT_CLOSE_TIMEOUT;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.chunk_rx.recv_timeout(timeout) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {
+                    warn!("The tuner input is stalled, releasing the tuner without closing it");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The PIDs each reader of a session asked for, whose union the tuner passes.
+#[derive(Default)]
+struct PidRequests {
+    next_id: u64,
+    /// `None` for a reader that needs every PID.
+    by_reader: BTreeMap<u64, Option<BTreeSet<u16>>>,
+    changed: bool,
+}
+
+impl PidRequests {
+    fn register(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.by_reader.insert(id, None);
+        self.changed = true;
+
+        id
+    }
+
+    /// The filter the tuner should apply, when it changed since it was last taken.
+    fn take_filter(&mut self) -> Option<Option<BTreeSet<u16>>> {
+        if !std::mem::take(&mut self.changed) || self.by_reader.is_empty() {
+            return None;
+        }
+
+        let mut union = BTreeSet::new();
+        for pids in self.by_reader.values() {
+            let Some(pids) = pids else {
+                return Some(None);
+            };
+            union.extend(pids);
+        }
+
+        Some(Some(union))
+    }
+}
+
+/// Lets the stream of a [`SessionReader`] narrow down what the tuner passes to the session.
+///
+/// Every reader starts out needing every PID, and stops asking for any once the request is
+/// dropped.
+pub struct PidRequest {
+    id: u64,
+    requests: Arc<Mutex<PidRequests>>,
+}
+
+impl PidRequest {
+    /// Asks for the PIDs, or every PID on `None`.
+    pub fn set(&self, pids: Option<BTreeSet<u16>>) {
+        let mut requests = self.requests.lock().unwrap();
+        if requests.by_reader.get(&self.id) != Some(&pids) {
+            requests.by_reader.insert(self.id, pids);
+            requests.changed = true;
+        }
+    }
+}
+
+impl Drop for PidRequest {
+    fn drop(&mut self) {
+        let mut requests = self.requests.lock().unwrap();
+        requests.by_reader.remove(&self.id);
+        requests.changed = true;
+    }
+}
+
+/// The raw input of a [`TunerSession`], from the moment the reader was created.
+///
+/// A reader that falls too far behind skips what it missed, which the demuxers recover from as
+/// from any other gap in the signal.
+pub struct SessionReader {
+    rx: Receiver<Bytes>,
+    chunk: Bytes,
+    pid_request: Arc<PidRequest>,
+}
+
+impl SessionReader {
+    fn new(rx: Receiver<Bytes>, pid_requests: &Arc<Mutex<PidRequests>>) -> Self {
+        let id = pid_requests.lock().unwrap().register();
+
+        Self {
+            rx,
+            chunk: Bytes::new(),
+            pid_request: Arc::new(PidRequest {
+                id,
+                requests: Arc::clone(pid_requests),
+            }),
+        }
+    }
+
+    pub fn pid_request(&self) -> Arc<PidRequest> {
+        Arc::clone(&self.pid_request)
+    }
+}
+
+impl Read for SessionReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.rx.blocking_recv() {
+                Ok(chunk) => self.chunk = chunk,
+                Err(RecvError::Closed) => return Ok(0),
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Stream fell behind the tuner, skipping its input");
+                }
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::channel::{ALL_ISDB_T_LAYERS, ChannelInner};
+    use crate::config::DeliverySystem;
+    use crate::tuner::{LeasePriority, Tuner, Tuners};
+
+    struct CaptureTuner {
+        opens: Arc<AtomicUsize>,
+    }
+
+    impl Tuner for CaptureTuner {
+        fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+            self.opens.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(Cursor::new(vec![0x47; CHUNK_SIZE * 3 + 5])))
+        }
+
+        fn tune(&self, _channel: Channel) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Can be read by one input at a time, like the DVR device of a DVB adapter.
+    #[derive(Default)]
+    struct ExclusiveTuner {
+        is_open: Arc<AtomicBool>,
+        opens: Arc<AtomicUsize>,
+    }
+
+    impl Tuner for ExclusiveTuner {
+        fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+            if self.is_open.swap(true, Ordering::SeqCst) {
+                anyhow::bail!("The input is already open");
+            }
+            self.opens.fetch_add(1, Ordering::SeqCst);
+
+            Ok(Box::new(ExclusiveInput {
+                is_open: Arc::clone(&self.is_open),
+            }))
+        }
+
+        fn tune(&self, _channel: Channel) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Trickles a byte at a time in.
+    struct ExclusiveInput {
+        is_open: Arc<AtomicBool>,
+    }
+
+    impl Read for ExclusiveInput {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            std::thread::sleep(Duration::from_millis(10));
+            buf[0] = 0x47;
+            Ok(1)
+        }
+    }
+
+    impl Drop for ExclusiveInput {
+        fn drop(&mut self) {
+            self.is_open.store(false, Ordering::SeqCst);
+        }
+    }
+
+    fn channel() -> Channel {
+        Channel {
+            id: 0,
+            name: "UHF 20".to_string(),
+            transport_stream_id: None,
+            inner: ChannelInner::IsdbT {
+                frequency: 515_142_857,
+                bandwidth_hz: 6_000_000,
+                layers: ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn filters_the_union_of_what_readers_ask_for() {
+        let requests = Arc::new(Mutex::new(PidRequests::default()));
+        let (_tx, rx) = broadcast_channel::<Bytes>(1);
+        let first = SessionReader::new(rx.resubscribe(), &requests);
+        let second = SessionReader::new(rx, &requests);
+
+        assert_eq!(requests.lock().unwrap().take_filter(), Some(None));
+        assert_eq!(requests.lock().unwrap().take_filter(), None);
+
+        first
+            .pid_request()
+            .set(Some(BTreeSet::from([0x0000, 0x0100])));
+        assert_eq!(requests.lock().unwrap().take_filter(), Some(None));
+
+        second
+            .pid_request()
+            .set(Some(BTreeSet::from([0x0000, 0x0200])));
+        assert_eq!(
+            requests.lock().unwrap().take_filter(),
+            Some(Some(BTreeSet::from([0x0000, 0x0100, 0x0200])))
+        );
+
+        drop(second);
+        assert_eq!(
+            requests.lock().unwrap().take_filter(),
+            Some(Some(BTreeSet::from([0x0000, 0x0100])))
+        );
+    }
+
+    #[test]
+    fn opens_the_input_again_once_it_has_ended() {
+        let opens = Arc::new(AtomicUsize::new(0));
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(
+            0,
+            CaptureTuner {
+                opens: Arc::clone(&opens),
+            },
+        );
+        let lease = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+            .unwrap();
+
+        let (session, mut reader) = TunerSession::start(lease, &channel()).unwrap();
+        let mut reception = session.subscribe_reception();
+
+        // The first reader gets the whole input, and carries on with the input opened again.
+        let mut data = vec![0; (CHUNK_SIZE * 3 + 5) * 2];
+        reader.read_exact(&mut data).unwrap();
+        assert!(data.iter().all(|&byte| byte == 0x47));
+        assert!(opens.load(Ordering::SeqCst) >= 2);
+        assert!(reception.has_changed().unwrap());
+        assert!(session.is_running());
+
+        // Once the session is gone, the tuner is free again before the readers see the end.
+        drop(session);
+        reader.read_to_end(&mut Vec::new()).unwrap();
+        assert_eq!(tuners.is_in_use(0), Some(false));
+    }
+
+    #[test]
+    fn closes_the_input_before_tuning_again() {
+        let tuner = ExclusiveTuner::default();
+        let opens = Arc::clone(&tuner.opens);
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, tuner);
+        let lease = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+            .unwrap();
+        let input = InputReader::spawn(lease.open_reader().unwrap());
+
+        let mut watchdog = Watchdog {
+            tuner: lease,
+            channel: channel(),
+            input: Some(input),
+            data_tx: broadcast_channel(1).0,
+            reception_tx: watch::channel(Reception::Receiving).0,
+            pid_requests: Arc::new(Mutex::new(PidRequests::default())),
+        };
+        watchdog.retune().unwrap();
+        assert_eq!(opens.load(Ordering::SeqCst), 2);
+        assert!(watchdog.input.as_ref().unwrap().recv().is_ok());
+    }
+}
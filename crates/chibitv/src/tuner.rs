@@ -1,17 +1,31 @@
+mod command;
 #[cfg(feature = "dvb")]
 mod dvb;
+mod file;
+mod mirakurun;
 mod stdin;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Read;
-use std::sync::Arc;
+use std::sync::{Arc, Condvar, Mutex, OnceLock, mpsc};
+use std::time::{Duration, Instant};
 
 use anyhow::bail;
 use tokio::sync::{OwnedSemaphorePermit, Semaphore};
-use tracing::warn;
+use tracing::{info, warn};
 
-use crate::channel::Channel;
-use crate::config::TunerConfig;
+use crate::channel::{Channel, ChannelInner};
+use crate::config::{DeliverySystem, TunerConfig, TunerConfigInner};
+use crate::m2ts::read_transport_stream_id;
+
+/// How many packets to read for the PAT when telling relay stations apart, well over the 100 ms
+/// a PAT is repeated within at least.
+const MAX_PAT_PACKETS: usize = 20_000;
+/// How long to wait for the PAT when telling relay stations apart, on an input that may carry
+/// nothing at all.
+const PAT_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long a request waits for a lease it preempted to give its tuner back.
+const PREEMPTION_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub trait Tuner: Send + Sync {
     fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>>;
@@ -20,19 +34,188 @@ pub trait Tuner: Send + Sync {
         warn!("This tuner does not support tuning.");
         Ok(())
     }
+
+    /// Measures the reception of the tuner, or returns `None` when it cannot.
+    fn stats(&self) -> anyhow::Result<Option<TunerStats>> {
+        Ok(None)
+    }
+
+    /// Passes only the packets of the PIDs to the input, or every packet on `None`.
+    ///
+    /// Tuning passes every packet again. Tuners that cannot filter pass everything regardless.
+    fn set_pid_filter(&self, _pids: Option<&BTreeSet<u16>>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// The reception of a tuner at the time it was measured.
+///
+/// Each measurement is `None` when the frontend does not report it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TunerStats {
+    pub locked: bool,
+    pub signal_strength_dbm: Option<f64>,
+    pub cnr_db: Option<f64>,
+    /// The bit error rate before the inner code corrects the errors, since the tuner was tuned.
+    pub pre_ber: Option<f64>,
+    /// The bit error rate after the inner code has corrected what it could, since the tuner was
+    /// tuned.
+    pub post_ber: Option<f64>,
+    /// The measurements of each hierarchical layer on ISDB-T, from layer A to C.
+    pub layers: Vec<LayerStats>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayerStats {
+    pub cnr_db: Option<f64>,
+    pub pre_ber: Option<f64>,
+    pub post_ber: Option<f64>,
+}
+
+/// What a tuner is doing, as reported to clients.
+#[derive(Clone, Debug)]
+pub struct TunerStatus {
+    pub id: u32,
+    pub in_use: bool,
+    /// The channel the tuner was last tuned to by its current lease.
+    pub channel: Option<Channel>,
+    pub stats: Option<TunerStats>,
+}
+
+/// What a lease is for, which decides who gives way when every tuner is in use.
+///
+/// A request preempts the lease of the lowest priority below its own.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum LeasePriority {
+    Crawl,
+    Live,
+    Recording,
+}
+
+impl std::fmt::Display for LeasePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Crawl => write!(f, "event crawling"),
+            Self::Live => write!(f, "live viewing"),
+            Self::Recording => write!(f, "recording"),
+        }
+    }
+}
+
+/// Tells the holder of a lease that a request of a higher priority wants the tuner back.
+///
+/// The holder is expected to notice it soon and drop the lease; nothing is interrupted for it.
+#[derive(Clone, Debug, Default)]
+pub struct Preemption(Arc<OnceLock<LeasePriority>>);
+
+impl Preemption {
+    /// The priority of the request the lease has to give way to, if any.
+    pub fn preempted_by(&self) -> Option<LeasePriority> {
+        self.0.get().copied()
+    }
+
+    /// Fails with [`Preempted`] once the lease has to give way.
+    pub fn check(&self) -> Result<(), Preempted> {
+        match self.preempted_by() {
+            Some(priority) => Err(Preempted(priority)),
+            None => Ok(()),
+        }
+    }
+
+    fn preempt(&self, priority: LeasePriority) {
+        let _ = self.0.set(priority);
+    }
+}
+
+/// The error of work stopped because its tuner was taken over by a request of a higher priority.
+#[derive(Debug)]
+pub struct Preempted(pub LeasePriority);
+
+impl std::fmt::Display for Preempted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The tuner was taken over for {}", self.0)
+    }
+}
+
+impl std::error::Error for Preempted {}
+
+struct LeaseHolder {
+    priority: LeasePriority,
+    preemption: Preemption,
 }
 
 struct TunerSlot {
     id: u32,
     tuner: Arc<dyn Tuner>,
+    delivery_systems: Vec<DeliverySystem>,
     semaphore: Arc<Semaphore>,
+    channel: Mutex<Option<Channel>>,
+    holder: Mutex<Option<LeaseHolder>>,
+    /// Notified with `holder` locked whenever a lease releases the tuner.
+    released: Condvar,
+}
+
+impl TunerSlot {
+    fn status(&self) -> TunerStatus {
+        let stats = self.tuner.stats().unwrap_or_else(|error| {
+            warn!(
+                tuner_id = self.id,
+                ?error,
+                "Could not measure the reception"
+            );
+            None
+        });
+
+        TunerStatus {
+            id: self.id,
+            in_use: self.semaphore.available_permits() == 0,
+            channel: self.channel.lock().unwrap().clone(),
+            stats,
+        }
+    }
+
+    fn lease(
+        self: &Arc<Self>,
+        permit: OwnedSemaphorePermit,
+        priority: LeasePriority,
+    ) -> TunerLease {
+        let preemption = Preemption::default();
+        *self.holder.lock().unwrap() = Some(LeaseHolder {
+            priority,
+            preemption: preemption.clone(),
+        });
+
+        TunerLease {
+            slot: Arc::clone(self),
+            preemption,
+            probed_input: Mutex::new(None),
+            permit: Some(permit),
+        }
+    }
+
+    /// Waits for the lease holding the tuner to release it, and takes the tuner over.
+    fn wait_for_release(&self, timeout: Duration) -> Option<OwnedSemaphorePermit> {
+        let deadline = Instant::now() + timeout;
+        let mut holder = self.holder.lock().unwrap();
+        loop {
+            if let Ok(permit) = Arc::clone(&self.semaphore).try_acquire_owned() {
+                return Some(permit);
+            }
+
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            holder = self.released.wait_timeout(holder, remaining).unwrap().0;
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum AcquireError {
     /// No tuners are defined in the configuration.
     NotConfigured,
-    /// Every configured tuner is currently leased.
+    /// No configured tuner can receive the delivery system.
+    Unsupported(DeliverySystem),
+    /// Every configured tuner that can receive the delivery system is currently leased, and no
+    /// lease of a lower priority gave its tuner back in time.
     Busy,
 }
 
@@ -40,6 +223,9 @@ impl std::fmt::Display for AcquireError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::NotConfigured => write!(f, "No tuners are configured"),
+            Self::Unsupported(delivery_system) => {
+                write!(f, "No tuner can receive {delivery_system}")
+            }
             Self::Busy => write!(f, "All tuners are in use"),
         }
     }
@@ -49,7 +235,11 @@ impl std::error::Error for AcquireError {}
 
 pub struct TunerLease {
     slot: Arc<TunerSlot>,
-    _permit: OwnedSemaphorePermit,
+    preemption: Preemption,
+    /// The input the transport stream id was read from while tuning, handed out by the next
+    /// `open_reader` so that the tuner is not opened twice.
+    probed_input: Mutex<Option<Box<dyn Read + Send + Sync>>>,
+    permit: Option<OwnedSemaphorePermit>,
 }
 
 impl TunerLease {
@@ -57,8 +247,85 @@ impl TunerLease {
         self.slot.id
     }
 
+    /// Reports when the lease has to give way to a request of a higher priority.
+    pub fn preemption(&self) -> Preemption {
+        self.preemption.clone()
+    }
+
+    /// Tunes to the channel, trying its alternate frequencies in order when it has any.
+    ///
+    /// A frequency is kept once the tuner locks to it and, if the channel names its transport
+    /// stream id, once the PAT received there reports that id.
     pub fn tune(&self, channel: Channel) -> anyhow::Result<()> {
-        self.slot.tuner.tune(channel)
+        *self.slot.channel.lock().unwrap() = None;
+        // Closed before tuning, as most tuners cannot be read twice at once.
+        *self.probed_input.lock().unwrap() = None;
+
+        let candidates = channel.inner.candidates();
+        let channel = if candidates.len() == 1 {
+            self.slot.tuner.tune(channel.clone())?;
+            channel
+        } else {
+            self.tune_any(&channel, candidates)?
+        };
+        *self.slot.channel.lock().unwrap() = Some(channel);
+
+        Ok(())
+    }
+
+    fn tune_any(
+        &self,
+        channel: &Channel,
+        candidates: Vec<ChannelInner>,
+    ) -> anyhow::Result<Channel> {
+        for inner in candidates {
+            let candidate = Channel {
+                inner,
+                ..channel.clone()
+            };
+            if let Err(error) = self.slot.tuner.tune(candidate.clone()) {
+                warn!(
+                    channel = %channel.name,
+                    candidate = ?candidate.inner,
+                    error = %error,
+                    "Could not tune to a frequency of the channel"
+                );
+                continue;
+            }
+
+            let Some(expected) = channel.transport_stream_id else {
+                return Ok(candidate);
+            };
+            match self.slot.tuner.open().map(probe_transport_stream_id) {
+                Ok(Some((Some(transport_stream_id), input))) if transport_stream_id == expected => {
+                    *self.probed_input.lock().unwrap() = Some(input);
+                    return Ok(candidate);
+                }
+                Ok(Some((transport_stream_id, _))) => warn!(
+                    channel = %channel.name,
+                    candidate = ?candidate.inner,
+                    expected,
+                    ?transport_stream_id,
+                    "Received another transport stream on a frequency of the channel"
+                ),
+                Ok(None) => warn!(
+                    channel = %channel.name,
+                    candidate = ?candidate.inner,
+                    "Received nothing on a frequency of the channel"
+                ),
+                Err(error) => warn!(
+                    channel = %channel.name,
+                    candidate = ?candidate.inner,
+                    error = %error,
+                    "Could not open the tuner"
+                ),
+            }
+        }
+
+        bail!(
+            "Could not receive {} on any of its frequencies",
+            channel.name
+        )
     }
 
     pub fn open(self) -> anyhow::Result<TunerInput> {
@@ -70,8 +337,34 @@ impl TunerLease {
     }
 
     pub(crate) fn open_reader(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+        if let Some(input) = self.probed_input.lock().unwrap().take() {
+            return Ok(input);
+        }
+
         self.slot.tuner.open()
     }
+
+    /// Narrows the input down to the packets of the PIDs, or widens it to every packet on `None`.
+    pub fn set_pid_filter(&self, pids: Option<&BTreeSet<u16>>) -> anyhow::Result<()> {
+        self.slot.tuner.set_pid_filter(pids)
+    }
+
+    /// Measures the reception of the tuner, or returns `None` when it cannot.
+    pub fn stats(&self) -> anyhow::Result<Option<TunerStats>> {
+        self.slot.tuner.stats()
+    }
+}
+
+impl Drop for TunerLease {
+    fn drop(&mut self) {
+        *self.slot.channel.lock().unwrap() = None;
+
+        // The permit is released with the holder locked, so the next holder is never cleared here.
+        let mut holder = self.slot.holder.lock().unwrap();
+        *holder = None;
+        drop(self.permit.take());
+        self.slot.released.notify_all();
+    }
 }
 
 pub struct TunerInput {
@@ -92,24 +385,57 @@ pub struct Tuners {
 }
 
 impl Tuners {
-    pub fn try_acquire(&self) -> Result<TunerLease, AcquireError> {
+    /// Leases the first free tuner that can receive the delivery system.
+    ///
+    /// When every such tuner is in use, the lease of the lowest priority below `priority` is asked
+    /// to give way, and its tuner is leased once it has. This blocks until then, or fails with
+    /// [`AcquireError::Busy`] after a while.
+    pub fn acquire(
+        &self,
+        delivery_system: DeliverySystem,
+        priority: LeasePriority,
+    ) -> Result<TunerLease, AcquireError> {
+        self.acquire_within(delivery_system, priority, PREEMPTION_TIMEOUT)
+    }
+
+    /// Leases a tuner like [`Tuners::acquire`], waiting at most `timeout` for a preempted lease to
+    /// give its tuner back.
+    pub fn acquire_within(
+        &self,
+        delivery_system: DeliverySystem,
+        priority: LeasePriority,
+        timeout: Duration,
+    ) -> Result<TunerLease, AcquireError> {
         if self.tuners.is_empty() {
             return Err(AcquireError::NotConfigured);
         }
 
-        for slot in self.tuners.values() {
+        let slots = self
+            .tuners
+            .values()
+            .filter(|slot| slot.delivery_systems.contains(&delivery_system))
+            .collect::<Vec<_>>();
+        if slots.is_empty() {
+            return Err(AcquireError::Unsupported(delivery_system));
+        }
+
+        for slot in &slots {
             if let Ok(permit) = Arc::clone(&slot.semaphore).try_acquire_owned() {
-                return Ok(TunerLease {
-                    slot: Arc::clone(slot),
-                    _permit: permit,
-                });
+                return Ok(slot.lease(permit, priority));
             }
         }
 
-        Err(AcquireError::Busy)
+        let slot = preempt_lowest(&slots, priority).ok_or(AcquireError::Busy)?;
+        let permit = slot.wait_for_release(timeout).ok_or(AcquireError::Busy)?;
+
+        Ok(slot.lease(permit, priority))
     }
 
-    pub fn try_acquire_by_id(&self, id: u32) -> anyhow::Result<TunerLease> {
+    pub fn try_acquire_by_id(
+        &self,
+        id: u32,
+        priority: LeasePriority,
+    ) -> anyhow::Result<TunerLease> {
         let Some(slot) = self.tuners.get(&id) else {
             bail!("Tuner {id} is not configured");
         };
@@ -117,10 +443,7 @@ impl Tuners {
             .try_acquire_owned()
             .map_err(|_| anyhow::anyhow!("Tuner {id} is in use"))?;
 
-        Ok(TunerLease {
-            slot: Arc::clone(slot),
-            _permit: permit,
-        })
+        Ok(slot.lease(permit, priority))
     }
 
     pub fn is_in_use(&self, id: u32) -> Option<bool> {
@@ -129,29 +452,78 @@ impl Tuners {
             .map(|slot| slot.semaphore.available_permits() == 0)
     }
 
+    /// Reports every tuner, measuring the reception of each.
+    pub fn statuses(&self) -> Vec<TunerStatus> {
+        self.tuners.values().map(|slot| slot.status()).collect()
+    }
+
+    pub fn status(&self, id: u32) -> Option<TunerStatus> {
+        self.tuners.get(&id).map(|slot| slot.status())
+    }
+
+    /// Adds a tuner that can receive every delivery system.
     pub fn add_tuner<T: Tuner + 'static>(&mut self, id: u32, tuner: T) {
+        self.add_tuner_receiving(id, tuner, &DeliverySystem::ALL);
+    }
+
+    pub fn add_tuner_receiving<T: Tuner + 'static>(
+        &mut self,
+        id: u32,
+        tuner: T,
+        delivery_systems: &[DeliverySystem],
+    ) {
         self.tuners.insert(
             id,
             Arc::new(TunerSlot {
                 id,
                 tuner: Arc::new(tuner),
+                delivery_systems: delivery_systems.to_vec(),
                 semaphore: Arc::new(Semaphore::new(1)),
+                channel: Mutex::new(None),
+                holder: Mutex::new(None),
+                released: Condvar::new(),
             }),
         );
     }
 
     pub fn add_tuner_from_config(&mut self, id: u32, config: &TunerConfig) -> anyhow::Result<()> {
-        match config {
-            TunerConfig::Stdin => {
-                self.add_tuner(id, stdin::StdinTuner);
+        let delivery_systems = &config.delivery_systems;
+
+        match &config.inner {
+            TunerConfigInner::Stdin => {
+                self.add_tuner_receiving(id, stdin::StdinTuner, delivery_systems);
             }
 
             #[cfg(feature = "dvb")]
-            TunerConfig::Dvb {
+            TunerConfigInner::Dvb {
                 adapter_num,
                 frontend_num,
             } => {
-                self.add_tuner(id, dvb::DvbTuner::new(*adapter_num, *frontend_num)?);
+                self.add_tuner_receiving(
+                    id,
+                    dvb::DvbTuner::new(*adapter_num, *frontend_num)?,
+                    delivery_systems,
+                );
+            }
+
+            TunerConfigInner::File { channels } => {
+                self.add_tuner_receiving(id, file::FileTuner::new(channels)?, delivery_systems);
+            }
+
+            TunerConfigInner::Mirakurun { url, channels } => {
+                self.add_tuner_receiving(
+                    id,
+                    mirakurun::MirakurunTuner::new(url, channels),
+                    delivery_systems,
+                );
+            }
+
+            TunerConfigInner::Command { tune_command } => {
+                self.add_tuner_receiving(
+                    id,
+                    command::CommandTuner::new(tune_command)?,
+                    delivery_systems,
+                );
             }
         }
 
@@ -159,9 +531,82 @@ impl Tuners {
     }
 }
 
+/// Reads the transport stream id of the input on a thread of its own, and returns it with the
+/// input to carry on reading. Returns `None` when the input does not end or report an id within
+/// [`PAT_TIMEOUT`], leaving the thread to close the input once its read returns. Tuners whose input
+/// can be open only once end such a read when they are tuned to the next frequency.
+fn probe_transport_stream_id(
+    input: Box<dyn Read + Send + Sync>,
+) -> Option<(Option<u16>, Box<dyn Read + Send + Sync>)> {
+    let (result_tx, result_rx) = mpsc::sync_channel(1);
+    std::thread::spawn(move || {
+        let mut input = DeadlineReader {
+            inner: input,
+            deadline: Instant::now() + PAT_TIMEOUT,
+        };
+        let transport_stream_id = read_transport_stream_id(&mut input, MAX_PAT_PACKETS);
+        let _ = result_tx.send((transport_stream_id, input.inner));
+    });
+
+    // An input that carries anything ends at the deadline, so only a stalled read outlasts this.
+    result_rx.recv_timeout(PAT_TIMEOUT * 2).ok()
+}
+
+/// Ends the input once the deadline has passed.
+struct DeadlineReader {
+    inner: Box<dyn Read + Send + Sync>,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if Instant::now() >= self.deadline {
+            return Ok(0);
+        }
+
+        self.inner.read(buf)
+    }
+}
+
+/// Asks the lease of the lowest priority below `priority` to give way, unless one of the slots is
+/// already giving way to no higher a priority, and returns the slot giving way.
+fn preempt_lowest<'a>(
+    slots: &[&'a Arc<TunerSlot>],
+    priority: LeasePriority,
+) -> Option<&'a Arc<TunerSlot>> {
+    let mut lowest: Option<(&Arc<TunerSlot>, LeasePriority, Preemption)> = None;
+    for &slot in slots {
+        let holder = slot.holder.lock().unwrap();
+        let Some(holder) = holder.as_ref() else {
+            continue;
+        };
+        if let Some(preempted_by) = holder.preemption.preempted_by() {
+            // A slot giving way to a higher priority is not ours to wait for.
+            if preempted_by <= priority {
+                return Some(slot);
+            }
+            continue;
+        }
+        if holder.priority < priority
+            && lowest
+                .as_ref()
+                .is_none_or(|(_, lowest, _)| holder.priority < *lowest)
+        {
+            lowest = Some((slot, holder.priority, holder.preemption.clone()));
+        }
+    }
+
+    let (slot, preempted, preemption) = lowest?;
+    info!(tuner_id = slot.id, %preempted, by = %priority, "Preempting tuner lease");
+    preemption.preempt(priority);
+
+    Some(slot)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Cursor;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use super::*;
 
@@ -173,6 +618,76 @@ mod tests {
         }
     }
 
+    struct MeasuringTuner;
+
+    impl Tuner for MeasuringTuner {
+        fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+            Ok(Box::new(Cursor::new(vec![1, 2, 3])))
+        }
+
+        fn stats(&self) -> anyhow::Result<Option<TunerStats>> {
+            Ok(Some(TunerStats {
+                locked: true,
+                cnr_db: Some(28.5),
+                ..Default::default()
+            }))
+        }
+    }
+
+    /// Receives no signal on the first frequency, another transport stream on the second, and
+    /// transport stream 0x7FE0 on every other frequency.
+    #[derive(Default)]
+    struct RelayTuner {
+        frequency: Mutex<Option<u32>>,
+        opens: Arc<AtomicUsize>,
+    }
+
+    impl Tuner for RelayTuner {
+        fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+            self.opens.fetch_add(1, Ordering::SeqCst);
+            let transport_stream_id = match *self.frequency.lock().unwrap() {
+                Some(515_142_857) => 0x7FE8,
+                _ => 0x7FE0,
+            };
+            Ok(Box::new(Cursor::new(crate::m2ts::pat_packets(
+                transport_stream_id,
+            ))))
+        }
+
+        fn tune(&self, channel: Channel) -> anyhow::Result<()> {
+            let ChannelInner::IsdbT { frequency, .. } = channel.inner else {
+                anyhow::bail!("Not a terrestrial channel");
+            };
+            if frequency == 509_142_857 {
+                anyhow::bail!("No signal");
+            }
+            *self.frequency.lock().unwrap() = Some(frequency);
+
+            Ok(())
+        }
+    }
+
+    fn relayed_channel(transport_stream_id: Option<u16>) -> Channel {
+        Channel {
+            id: 0,
+            name: "UHF 19".to_string(),
+            transport_stream_id,
+            inner: ChannelInner::IsdbT {
+                frequency: 509_142_857,
+                bandwidth_hz: 6_000_000,
+                layers: crate::channel::ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![515_142_857, 521_142_857],
+            },
+        }
+    }
+
+    fn tuned_frequency(tuners: &Tuners) -> Option<u32> {
+        match tuners.status(0)?.channel?.inner {
+            ChannelInner::IsdbT { frequency, .. } => Some(frequency),
+            _ => None,
+        }
+    }
+
     struct FailingTuner;
 
     impl Tuner for FailingTuner {
@@ -187,17 +702,17 @@ mod tests {
         tuners.add_tuner(7, FakeTuner);
         assert_eq!(tuners.is_in_use(7), Some(false));
 
-        let lease = tuners.try_acquire_by_id(7).unwrap();
+        let lease = tuners.try_acquire_by_id(7, LeasePriority::Live).unwrap();
         assert_eq!(lease.id(), 7);
         assert_eq!(tuners.is_in_use(7), Some(true));
-        assert!(tuners.try_acquire_by_id(7).is_err());
+        assert!(tuners.try_acquire_by_id(7, LeasePriority::Live).is_err());
 
         let input = lease.open().unwrap();
         assert_eq!(tuners.is_in_use(7), Some(true));
 
         drop(input);
         assert_eq!(tuners.is_in_use(7), Some(false));
-        assert!(tuners.try_acquire_by_id(7).is_ok());
+        assert!(tuners.try_acquire_by_id(7, LeasePriority::Live).is_ok());
     }
 
     #[test]
@@ -205,7 +720,7 @@ mod tests {
         let mut tuners = Tuners::default();
         tuners.add_tuner(7, FakeTuner);
 
-        let lease = tuners.try_acquire_by_id(7).unwrap();
+        let lease = tuners.try_acquire_by_id(7, LeasePriority::Live).unwrap();
         let input = lease.open_reader().unwrap();
         assert_eq!(tuners.is_in_use(7), Some(true));
 
@@ -223,19 +738,239 @@ mod tests {
         tuners.add_tuner(0, FakeTuner);
         tuners.add_tuner(1, FakeTuner);
 
-        let first = tuners.try_acquire_by_id(0).unwrap();
-        let second = tuners.try_acquire().unwrap();
+        let first = tuners.try_acquire_by_id(0, LeasePriority::Live).unwrap();
+        let second = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+            .unwrap();
 
         assert_eq!(first.id(), 0);
         assert_eq!(second.id(), 1);
     }
 
+    #[test]
+    fn acquires_only_tuners_receiving_the_delivery_system() {
+        let mut tuners = Tuners::default();
+        tuners.add_tuner_receiving(0, FakeTuner, &[DeliverySystem::IsdbT]);
+        tuners.add_tuner_receiving(1, FakeTuner, &[DeliverySystem::IsdbS]);
+
+        let satellite = tuners
+            .acquire(DeliverySystem::IsdbS, LeasePriority::Live)
+            .unwrap();
+        assert_eq!(satellite.id(), 1);
+        assert!(matches!(
+            tuners.acquire(DeliverySystem::IsdbS, LeasePriority::Live),
+            Err(AcquireError::Busy)
+        ));
+        assert_eq!(
+            tuners
+                .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+                .unwrap()
+                .id(),
+            0
+        );
+
+        let mut terrestrial_only = Tuners::default();
+        terrestrial_only.add_tuner_receiving(0, FakeTuner, &[DeliverySystem::IsdbT]);
+        assert!(matches!(
+            terrestrial_only.acquire(DeliverySystem::IsdbS, LeasePriority::Live),
+            Err(AcquireError::Unsupported(DeliverySystem::IsdbS))
+        ));
+    }
+
+    #[test]
+    fn preempts_the_lowest_priority_lease() {
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, FakeTuner);
+        tuners.add_tuner(1, FakeTuner);
+
+        let crawl = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Crawl)
+            .unwrap();
+        let live = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+            .unwrap();
+
+        assert!(matches!(
+            tuners.acquire(DeliverySystem::IsdbT, LeasePriority::Crawl),
+            Err(AcquireError::Busy)
+        ));
+        assert_eq!(crawl.preemption().preempted_by(), None);
+
+        let timeout = Duration::from_millis(10);
+        assert!(matches!(
+            tuners.acquire_within(DeliverySystem::IsdbT, LeasePriority::Recording, timeout),
+            Err(AcquireError::Busy)
+        ));
+        assert_eq!(
+            crawl.preemption().preempted_by(),
+            Some(LeasePriority::Recording)
+        );
+        assert!(crawl.preemption().check().is_err());
+
+        // Nothing else gives way while the crawl is still releasing its tuner.
+        assert!(matches!(
+            tuners.acquire_within(DeliverySystem::IsdbT, LeasePriority::Recording, timeout),
+            Err(AcquireError::Busy)
+        ));
+        assert_eq!(live.preemption().preempted_by(), None);
+
+        drop(crawl);
+        let recording = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Recording)
+            .unwrap();
+        assert_eq!(recording.id(), 0);
+        assert_eq!(recording.preemption().preempted_by(), None);
+    }
+
+    #[test]
+    fn preempts_another_lease_past_one_giving_way_to_a_higher_priority() {
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, FakeTuner);
+        tuners.add_tuner(1, FakeTuner);
+
+        let first = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Crawl)
+            .unwrap();
+        let second = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Crawl)
+            .unwrap();
+
+        let timeout = Duration::from_millis(10);
+        assert!(matches!(
+            tuners.acquire_within(DeliverySystem::IsdbT, LeasePriority::Recording, timeout),
+            Err(AcquireError::Busy)
+        ));
+        assert_eq!(
+            first.preemption().preempted_by(),
+            Some(LeasePriority::Recording)
+        );
+
+        assert!(matches!(
+            tuners.acquire_within(DeliverySystem::IsdbT, LeasePriority::Live, timeout),
+            Err(AcquireError::Busy)
+        ));
+        assert_eq!(
+            second.preemption().preempted_by(),
+            Some(LeasePriority::Live)
+        );
+
+        drop(second);
+        let live = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+            .unwrap();
+        assert_eq!(live.id(), 1);
+    }
+
+    #[test]
+    fn waits_for_the_preempted_lease_to_give_its_tuner_back() {
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, FakeTuner);
+
+        let crawl = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Crawl)
+            .unwrap();
+        let crawler = std::thread::spawn(move || {
+            let preemption = crawl.preemption();
+            while preemption.check().is_ok() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        });
+
+        let live = tuners
+            .acquire(DeliverySystem::IsdbT, LeasePriority::Live)
+            .unwrap();
+        assert_eq!(live.id(), 0);
+        assert_eq!(live.preemption().preempted_by(), None);
+        crawler.join().unwrap();
+    }
+
     #[test]
     fn releases_tuner_when_open_fails() {
         let mut tuners = Tuners::default();
         tuners.add_tuner(0, FailingTuner);
 
-        assert!(tuners.try_acquire_by_id(0).unwrap().open().is_err());
+        assert!(
+            tuners
+                .try_acquire_by_id(0, LeasePriority::Live)
+                .unwrap()
+                .open()
+                .is_err()
+        );
         assert_eq!(tuners.is_in_use(0), Some(false));
     }
+
+    #[test]
+    fn reports_the_tuned_channel_while_leased() {
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, MeasuringTuner);
+        tuners.add_tuner(1, FakeTuner);
+
+        let lease = tuners.try_acquire_by_id(0, LeasePriority::Live).unwrap();
+        lease
+            .tune(Channel {
+                id: 3,
+                name: "UHF 20".to_string(),
+                transport_stream_id: None,
+                inner: crate::channel::ChannelInner::IsdbT {
+                    frequency: 515_142_857,
+                    bandwidth_hz: 6_000_000,
+                    layers: crate::channel::ALL_ISDB_T_LAYERS,
+                    alternate_frequencies: vec![],
+                },
+            })
+            .unwrap();
+
+        let statuses = tuners.statuses();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].in_use);
+        assert_eq!(
+            statuses[0].channel.as_ref().map(|channel| channel.id),
+            Some(3)
+        );
+        assert_eq!(
+            statuses[0].stats.as_ref().and_then(|stats| stats.cnr_db),
+            Some(28.5)
+        );
+        assert!(!statuses[1].in_use);
+        assert!(statuses[1].stats.is_none());
+
+        drop(lease);
+        let status = tuners.status(0).unwrap();
+        assert!(!status.in_use);
+        assert!(status.channel.is_none());
+    }
+
+    #[test]
+    fn fails_over_to_the_frequency_carrying_the_transport_stream() {
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, RelayTuner::default());
+
+        let lease = tuners.try_acquire_by_id(0, LeasePriority::Live).unwrap();
+        lease.tune(relayed_channel(Some(0x7FE0))).unwrap();
+        assert_eq!(tuned_frequency(&tuners), Some(521_142_857));
+
+        // Without a transport stream id, the first frequency to lock is kept.
+        lease.tune(relayed_channel(None)).unwrap();
+        assert_eq!(tuned_frequency(&tuners), Some(515_142_857));
+
+        assert!(lease.tune(relayed_channel(Some(0x0001))).is_err());
+        assert_eq!(tuned_frequency(&tuners), None);
+    }
+
+    #[test]
+    fn reads_on_from_the_input_the_transport_stream_id_was_read_from() {
+        let tuner = RelayTuner::default();
+        let opens = Arc::clone(&tuner.opens);
+        let mut tuners = Tuners::default();
+        tuners.add_tuner(0, tuner);
+
+        let lease = tuners.try_acquire_by_id(0, LeasePriority::Live).unwrap();
+        lease.tune(relayed_channel(Some(0x7FE0))).unwrap();
+        assert_eq!(opens.load(Ordering::SeqCst), 2);
+
+        lease.open_reader().unwrap();
+        assert_eq!(opens.load(Ordering::SeqCst), 2);
+        lease.open_reader().unwrap();
+        assert_eq!(opens.load(Ordering::SeqCst), 3);
+    }
 }
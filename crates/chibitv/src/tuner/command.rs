@@ -0,0 +1,218 @@
+use std::io::Read;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+
+use anyhow::{Context, bail};
+use tracing::{info, warn};
+
+use super::Tuner;
+use crate::channel::{Channel, ChannelInner, uhf_channel};
+
+/// Receives channels through an external command that writes the raw stream to its standard
+/// output, such as `recpt1` of a vendor driver.
+///
+/// The command is started when the input is opened, with the placeholders in its arguments
+/// replaced by the channel last tuned to, and is killed once the input is dropped.
+pub struct CommandTuner {
+    tune_command: Vec<String>,
+    selected: Mutex<Option<Vec<String>>>,
+}
+
+impl CommandTuner {
+    pub fn new(tune_command: &[String]) -> anyhow::Result<Self> {
+        if tune_command.is_empty() {
+            bail!("The tune command of a command tuner is empty");
+        }
+
+        Ok(Self {
+            tune_command: tune_command.to_vec(),
+            selected: Mutex::new(None),
+        })
+    }
+}
+
+impl Tuner for CommandTuner {
+    fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+        let Some(command_line) = self.selected.lock().unwrap().clone() else {
+            bail!("No channel is tuned yet");
+        };
+
+        let (program, args) = command_line.split_first().unwrap();
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Could not run {program}"))?;
+        let stdout = child.stdout.take().unwrap();
+
+        Ok(Box::new(CommandReader { child, stdout }))
+    }
+
+    fn tune(&self, channel: Channel) -> anyhow::Result<()> {
+        let command_line = self
+            .tune_command
+            .iter()
+            .map(|arg| substitute(arg, &channel))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        info!("Receiving {} with {:?}", channel.name, command_line);
+        *self.selected.lock().unwrap() = Some(command_line);
+
+        Ok(())
+    }
+}
+
+/// Replaces each `{placeholder}` in the argument by what the channel has for it.
+fn substitute(arg: &str, channel: &Channel) -> anyhow::Result<String> {
+    let mut output = String::with_capacity(arg.len());
+    let mut rest = arg;
+
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            bail!("The placeholder in `{arg}` is not closed");
+        };
+
+        output.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..start + len];
+        output.push_str(&placeholder_value(placeholder, channel)?);
+        rest = &rest[start + len + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+fn placeholder_value(placeholder: &str, channel: &Channel) -> anyhow::Result<String> {
+    let value = match (placeholder, &channel.inner) {
+        ("name", _) => Some(channel.name.clone()),
+        ("frequency", ChannelInner::IsdbS { frequency, .. })
+        | ("frequency", ChannelInner::IsdbSTs { frequency, .. })
+        | ("frequency", ChannelInner::IsdbT { frequency, .. }) => Some(frequency.to_string()),
+        ("stream_id", ChannelInner::IsdbS { stream_id, .. }) => Some(stream_id.to_string()),
+        (
+            "stream_id",
+            ChannelInner::IsdbSTs {
+                transport_stream_id,
+                ..
+            },
+        ) => Some(transport_stream_id.to_string()),
+        ("bandwidth_hz", ChannelInner::IsdbT { bandwidth_hz, .. }) => {
+            Some(bandwidth_hz.to_string())
+        }
+        ("uhf_channel", ChannelInner::IsdbT { frequency, .. }) => {
+            uhf_channel(*frequency).map(|channel| channel.to_string())
+        }
+        ("name" | "frequency" | "stream_id" | "bandwidth_hz" | "uhf_channel", _) => None,
+        _ => bail!("Unknown placeholder `{{{placeholder}}}` in the tune command"),
+    };
+
+    value.with_context(|| format!("The channel {} has no {placeholder}", channel.name))
+}
+
+/// The standard output of a running tune command, which is killed when dropped.
+struct CommandReader {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Read for CommandReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.stdout.read(buf)?;
+
+        // A command that could not tune fails rather than ending the stream silently.
+        if len == 0 && !buf.is_empty() {
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(std::io::Error::other(format!(
+                    "The tune command exited with {status}"
+                )));
+            }
+        }
+
+        Ok(len)
+    }
+}
+
+impl Drop for CommandReader {
+    fn drop(&mut self) {
+        if let Err(error) = self.child.kill() {
+            warn!(%error, "Could not kill the tune command");
+        }
+
+        // Waiting reaps the process, so the device is free once the tuner is released.
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::ALL_ISDB_T_LAYERS;
+
+    fn terrestrial_channel() -> Channel {
+        Channel {
+            id: 0,
+            name: "UHF 27".to_string(),
+            transport_stream_id: None,
+            inner: ChannelInner::IsdbT {
+                frequency: 557_142_857,
+                bandwidth_hz: 6_000_000,
+                layers: ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![],
+            },
+        }
+    }
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn substitutes_the_channel_into_the_arguments() {
+        let channel = terrestrial_channel();
+
+        assert_eq!(substitute("{uhf_channel}", &channel).unwrap(), "27");
+        assert_eq!(
+            substitute("--freq={frequency}:{bandwidth_hz}", &channel).unwrap(),
+            "--freq=557142857:6000000"
+        );
+        assert!(substitute("{stream_id}", &channel).is_err());
+        assert!(substitute("{unknown}", &channel).is_err());
+        assert!(substitute("{frequency", &channel).is_err());
+    }
+
+    #[test]
+    fn reads_the_output_of_the_command() {
+        let tuner = CommandTuner::new(&command(&["sh", "-c", "printf {frequency}"])).unwrap();
+        assert!(tuner.open().is_err());
+
+        tuner.tune(terrestrial_channel()).unwrap();
+        let mut output = String::new();
+        tuner.open().unwrap().read_to_string(&mut output).unwrap();
+
+        assert_eq!(output, "557142857");
+    }
+
+    #[test]
+    fn fails_when_the_command_fails() {
+        let tuner = CommandTuner::new(&command(&["sh", "-c", "exit 1"])).unwrap();
+        tuner.tune(terrestrial_channel()).unwrap();
+
+        assert!(tuner.open().unwrap().read_to_end(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn kills_the_command_with_the_input() {
+        let tuner = CommandTuner::new(&command(&["sh", "-c", "yes"])).unwrap();
+        tuner.tune(terrestrial_channel()).unwrap();
+
+        let mut input = tuner.open().unwrap();
+        let mut buf = [0; 4];
+        input.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"y\ny\n");
+
+        // Dropping returns only once the endless command is gone.
+        drop(input);
+    }
+}
@@ -1,28 +1,40 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::ffi::c_void;
 use std::io::{ErrorKind, Read};
 use std::ptr::{null, null_mut};
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::bail;
 use dvbv5_sys::dvb_dev_type::{DVB_DEVICE_DEMUX, DVB_DEVICE_DVR, DVB_DEVICE_FRONTEND};
 use dvbv5_sys::{
-    DTV_BANDWIDTH_HZ, DTV_FREQUENCY, DTV_GUARD_INTERVAL, DTV_ISDBT_LAYER_ENABLED,
-    DTV_STAT_SIGNAL_STRENGTH, DTV_STATUS, DTV_STREAM_ID, DTV_TRANSMISSION_MODE, dmx_output,
-    dmx_ts_pes, dvb_dev_alloc, dvb_dev_close, dvb_dev_dmx_set_pesfilter, dvb_dev_find,
-    dvb_dev_free, dvb_dev_list, dvb_dev_open, dvb_dev_read, dvb_dev_seek_by_adapter,
-    dvb_dev_set_bufsize, dvb_dev_set_log, dvb_device, dvb_fe_get_stats, dvb_fe_retrieve_stats,
-    dvb_fe_retrieve_stats_layer, dvb_fe_set_parms, dvb_fe_store_parm, dvb_open_descriptor,
-    dvb_set_compat_delivery_system, dvb_v5_fe_parms, fe_delivery_system, fe_guard_interval,
-    fe_status, fe_transmit_mode, fecap_scale_params,
+    DTV_BANDWIDTH_HZ, DTV_FREQUENCY, DTV_GUARD_INTERVAL, DTV_ISDBT_LAYER_ENABLED, DTV_STAT_CNR,
+    DTV_STAT_POST_ERROR_BIT_COUNT, DTV_STAT_POST_TOTAL_BIT_COUNT, DTV_STAT_PRE_ERROR_BIT_COUNT,
+    DTV_STAT_PRE_TOTAL_BIT_COUNT, DTV_STAT_SIGNAL_STRENGTH, DTV_STATUS, DTV_STREAM_ID,
+    DTV_TRANSMISSION_MODE, dmx_output, dmx_ts_pes, dtv_stats, dvb_dev_alloc, dvb_dev_close,
+    dvb_dev_dmx_set_pesfilter, dvb_dev_find, dvb_dev_free, dvb_dev_get_fd, dvb_dev_list,
+    dvb_dev_open, dvb_dev_read, dvb_dev_seek_by_adapter, dvb_dev_set_bufsize, dvb_dev_set_log,
+    dvb_device, dvb_fe_get_stats, dvb_fe_retrieve_stats, dvb_fe_retrieve_stats_layer,
+    dvb_fe_set_parms, dvb_fe_store_parm, dvb_open_descriptor, dvb_set_compat_delivery_system,
+    dvb_v5_fe_parms, fe_delivery_system, fe_guard_interval, fe_status, fe_transmit_mode,
+    fecap_scale_params,
 };
-use libc::{EOVERFLOW, O_RDONLY, O_RDWR};
+use libc::{EINTR, EOVERFLOW, O_RDONLY, O_RDWR, POLLIN, poll, pollfd};
 use tracing::{error, info, warn};
 
 use crate::channel::ChannelInner;
-use crate::tuner::{Channel, Tuner};
+use crate::tuner::{Channel, LayerStats, Tuner, TunerStats};
 
 const DVR_BUFFER_SIZE: i32 = 32 * 1024 * 1024;
+/// How long a read of the DVR device waits for data at once before it checks whether the tuner
+/// has been tuned again.
+const DVR_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long opening the DVR device waits for the inputs of a previous tuning to be closed.
+const DVR_CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+/// The PID a PES filter passes every packet of the multiplex for.
+const ALL_PIDS: u16 = 0x2000;
 const DVB_VERBOSE_ENV: &str = "CHIBITV_DVB_VERBOSE";
 
 struct DvbDevice {
@@ -80,7 +92,9 @@ impl DvbDevice {
         }
     }
 
-    fn open_dvr(&self) -> anyhow::Result<DvbDvr> {
+    fn open_dvr(&self, inputs: Arc<DvrInputs>) -> anyhow::Result<DvbDvr> {
+        inputs.wait_for_close();
+
         unsafe {
             let fd = dvb_dev_open(self.dvb, (*self.dvr_dev).sysname, O_RDONLY);
             if fd.is_null() {
@@ -91,7 +105,7 @@ impl DvbDevice {
                 warn!("Couldn't set DVR buffer size.");
             }
 
-            Ok(DvbDvr { fd })
+            Ok(DvbDvr::new(fd, inputs))
         }
     }
 }
@@ -111,6 +125,25 @@ struct DvbDemux {
     fd: *mut dvb_open_descriptor,
 }
 
+impl DvbDemux {
+    fn set_pes_filter(&self, pid: u16) -> anyhow::Result<()> {
+        unsafe {
+            if dvb_dev_dmx_set_pesfilter(
+                self.fd,
+                pid,
+                dmx_ts_pes::DMX_PES_OTHER,
+                dmx_output::DMX_OUT_TS_TAP,
+                DVR_BUFFER_SIZE,
+            ) < 0
+            {
+                bail!("Couldn't set the PES filter of PID {pid:#06X}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Drop for DvbDemux {
     fn drop(&mut self) {
         unsafe {
@@ -122,12 +155,87 @@ impl Drop for DvbDemux {
 unsafe impl Send for DvbDemux {}
 unsafe impl Sync for DvbDemux {}
 
+/// The inputs opened from the DVR device, which can be open only once at a time.
+///
+/// Tuning ends the inputs opened before, so that a read stalled on a frequency of no signal gives
+/// the device back instead of keeping it open for as long as nothing arrives.
+#[derive(Default)]
+struct DvrInputs {
+    /// Bumped by each tuning.
+    generation: AtomicU64,
+    open: Mutex<usize>,
+    closed: Condvar,
+}
+
+impl DvrInputs {
+    fn end_previous(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Waits for the inputs of the previous tunings to see that they have ended and be closed.
+    fn wait_for_close(&self) {
+        let deadline = Instant::now() + DVR_CLOSE_TIMEOUT;
+        let mut open = self.open.lock().unwrap();
+        while *open > 0 {
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                warn!("The previous input of the DVR device is still open");
+                return;
+            };
+            open = self.closed.wait_timeout(open, remaining).unwrap().0;
+        }
+    }
+}
+
 struct DvbDvr {
     fd: *mut dvb_open_descriptor,
+    generation: u64,
+    inputs: Arc<DvrInputs>,
+}
+
+impl DvbDvr {
+    fn new(fd: *mut dvb_open_descriptor, inputs: Arc<DvrInputs>) -> Self {
+        *inputs.open.lock().unwrap() += 1;
+
+        Self {
+            fd,
+            generation: inputs.generation.load(Ordering::SeqCst),
+            inputs,
+        }
+    }
+
+    /// Waits for data to read, and returns `false` once the tuner has been tuned again.
+    fn wait_readable(&self) -> std::io::Result<bool> {
+        let mut descriptor = pollfd {
+            fd: unsafe { dvb_dev_get_fd(self.fd) },
+            events: POLLIN,
+            revents: 0,
+        };
+
+        loop {
+            if self.inputs.generation.load(Ordering::SeqCst) != self.generation {
+                return Ok(false);
+            }
+
+            match unsafe { poll(&mut descriptor, 1, DVR_POLL_INTERVAL.as_millis() as i32) } {
+                0 => {}
+                ret if ret > 0 => return Ok(true),
+                _ => {
+                    let error = std::io::Error::last_os_error();
+                    if error.raw_os_error() != Some(EINTR) {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl Read for DvbDvr {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if !self.wait_readable()? {
+            return Ok(0);
+        }
+
         unsafe {
             let ret = dvb_dev_read(self.fd, buf.as_mut_ptr() as *mut c_void, buf.len());
             if ret < 0 {
@@ -148,6 +256,9 @@ impl Drop for DvbDvr {
         unsafe {
             dvb_dev_close(self.fd);
         }
+
+        *self.inputs.open.lock().unwrap() -= 1;
+        self.inputs.closed.notify_all();
     }
 }
 
@@ -159,52 +270,150 @@ fn is_verbose_logging_enabled() -> bool {
         .is_ok_and(|value| !matches!(value.as_str(), "" | "0" | "false" | "False" | "FALSE"))
 }
 
+#[derive(Default)]
+struct FrontendState {
+    is_isdb_t: bool,
+}
+
+/// The filters passing packets to the DVR device, each on a demux of its own.
+#[derive(Default)]
+struct PidFilters {
+    /// Passes every packet, while no set of PIDs is asked for.
+    all: Option<DvbDemux>,
+    pids: BTreeMap<u16, DvbDemux>,
+}
+
 pub struct DvbTuner {
-    demux: DvbDemux,
+    // Keep the filters before the device so they are closed before the device is freed.
+    filters: Mutex<PidFilters>,
     dev: DvbDevice,
+    /// Guards the frontend, which `stats` reads while a lease may be tuning it.
+    frontend: Mutex<FrontendState>,
+    dvr_inputs: Arc<DvrInputs>,
 }
 
 impl DvbTuner {
     pub fn new(adapter_num: u8, frontend_num: u8) -> anyhow::Result<Self> {
         let dev = DvbDevice::open(adapter_num.into(), frontend_num.into())?;
-        let demux_fd = dev.open_demux()?;
 
         Ok(Self {
-            demux: demux_fd,
+            filters: Mutex::new(PidFilters::default()),
             dev,
+            frontend: Mutex::new(FrontendState::default()),
+            dvr_inputs: Arc::new(DvrInputs::default()),
         })
     }
+
+    fn open_filter(&self, pid: u16) -> anyhow::Result<DvbDemux> {
+        let demux = self.dev.open_demux()?;
+        demux.set_pes_filter(pid)?;
+
+        Ok(demux)
+    }
+}
+
+/// Retrieves a statistic of a layer, which is absent when the frontend does not report it.
+unsafe fn retrieve_stats(p: *mut dvb_v5_fe_parms, cmd: u32, layer: u32) -> Option<dtv_stats> {
+    unsafe {
+        let stats = dvb_fe_retrieve_stats_layer(p, cmd, layer);
+        if stats.is_null() { None } else { Some(*stats) }
+    }
+}
+
+fn decibel(stats: Option<dtv_stats>) -> Option<f64> {
+    let stats = stats?;
+    (stats.scale == fecap_scale_params::FE_SCALE_DECIBEL as u8)
+        .then(|| unsafe { stats.__bindgen_anon_1.svalue as f64 } / 1000.0)
+}
+
+fn counter(stats: Option<dtv_stats>) -> Option<u64> {
+    let stats = stats?;
+    (stats.scale == fecap_scale_params::FE_SCALE_COUNTER as u8)
+        .then(|| unsafe { stats.__bindgen_anon_1.uvalue })
+}
+
+/// The ratio of the errors to the bits counted since the frontend was tuned.
+///
+/// The counters are cumulative, so that every caller measuring the tuner reads the same rate.
+fn error_rate(error: Option<u64>, total: Option<u64>) -> Option<f64> {
+    let (error, total) = error.zip(total)?;
+    (total > 0).then(|| error as f64 / total as f64)
+}
+
+unsafe fn store_isdb_s_parms(p: *mut dvb_v5_fe_parms, frequency: u32, stream_id: u32) {
+    unsafe {
+        info!("Tuning to {}, 0x{:#X}", frequency, stream_id);
+
+        // FIXME: It will be an invalid argument if this is omitted.
+        //        Maybe an issue around LNBf, but I don't know much about here :(
+        (*p).lnb = null();
+
+        dvb_set_compat_delivery_system(p, fe_delivery_system::SYS_ISDBS as u32);
+        dvb_fe_store_parm(p, DTV_FREQUENCY, frequency);
+        dvb_fe_store_parm(p, DTV_STREAM_ID, stream_id);
+    }
+}
+
+/// The relative number of a BS or CS110 transport stream on its transponder, which ARIB TR-B15
+/// assigns to the lowest 3 bits of its id. `DTV_STREAM_ID` selects a stream by that number when
+/// it is below 8, and by its id otherwise.
+fn relative_ts_number(transport_stream_id: u16) -> u32 {
+    u32::from(transport_stream_id & 0x07)
+}
+
+/// Layer 0 is the whole signal, and layers 1 to 3 are the layers A to C of ISDB-T.
+unsafe fn measure_layer(p: *mut dvb_v5_fe_parms, layer: u32) -> LayerStats {
+    unsafe {
+        LayerStats {
+            cnr_db: decibel(retrieve_stats(p, DTV_STAT_CNR, layer)),
+            pre_ber: error_rate(
+                counter(retrieve_stats(p, DTV_STAT_PRE_ERROR_BIT_COUNT, layer)),
+                counter(retrieve_stats(p, DTV_STAT_PRE_TOTAL_BIT_COUNT, layer)),
+            ),
+            post_ber: error_rate(
+                counter(retrieve_stats(p, DTV_STAT_POST_ERROR_BIT_COUNT, layer)),
+                counter(retrieve_stats(p, DTV_STAT_POST_TOTAL_BIT_COUNT, layer)),
+            ),
+        }
+    }
 }
 
 impl Tuner for DvbTuner {
     fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
-        Ok(Box::new(self.dev.open_dvr()?))
+        Ok(Box::new(self.dev.open_dvr(Arc::clone(&self.dvr_inputs))?))
     }
 
     fn tune(&self, channel: Channel) -> anyhow::Result<()> {
+        let p = self.dev.fe_parms;
+        self.dvr_inputs.end_previous();
+
+        // The frontend is locked for each access only, so that `stats` is not held up while the
+        // signal is awaited.
         unsafe {
-            let p = self.dev.fe_parms;
+            let mut frontend = self.frontend.lock().unwrap();
+            *frontend = FrontendState {
+                is_isdb_t: matches!(channel.inner, ChannelInner::IsdbT { .. }),
+            };
 
             match channel.inner {
                 ChannelInner::IsdbS {
                     frequency,
                     stream_id,
-                } => {
-                    info!("Tuning to {}, 0x{:#X}", frequency, stream_id);
-
-                    // FIXME: It will be an invalid argument if this is omitted.
-                    //        Maybe an issue around LNBf, but I don't know much about here :(
-                    (*p).lnb = null();
-
-                    dvb_set_compat_delivery_system(p, fe_delivery_system::SYS_ISDBS as u32);
-                    dvb_fe_store_parm(p, DTV_FREQUENCY, frequency);
-                    dvb_fe_store_parm(p, DTV_STREAM_ID, stream_id);
-                }
+                } => store_isdb_s_parms(p, frequency, stream_id),
+                ChannelInner::IsdbSTs {
+                    frequency,
+                    transport_stream_id,
+                } => store_isdb_s_parms(p, frequency, relative_ts_number(transport_stream_id)),
                 ChannelInner::IsdbT {
                     frequency,
                     bandwidth_hz,
+                    layers,
+                    ..
                 } => {
-                    info!("Tuning to {}, bandwidth {} Hz", frequency, bandwidth_hz);
+                    info!(
+                        "Tuning to {}, bandwidth {} Hz, layers {:#05b}",
+                        frequency, bandwidth_hz, layers
+                    );
 
                     dvb_set_compat_delivery_system(p, fe_delivery_system::SYS_ISDBT as u32);
                     dvb_fe_store_parm(p, DTV_FREQUENCY, frequency);
@@ -219,48 +428,101 @@ impl Tuner for DvbTuner {
                         DTV_GUARD_INTERVAL,
                         fe_guard_interval::GUARD_INTERVAL_AUTO as u32,
                     );
-                    dvb_fe_store_parm(p, DTV_ISDBT_LAYER_ENABLED, 0b111);
+                    dvb_fe_store_parm(p, DTV_ISDBT_LAYER_ENABLED, u32::from(layers));
                 }
             }
 
             dvb_fe_set_parms(p);
+        }
 
-            let mut attempt = 0;
-            let mut status: fe_status = fe_status::FE_NONE;
-            while (status as u8 & fe_status::FE_HAS_LOCK as u8) == 0 {
-                std::thread::sleep(Duration::from_secs(1));
+        let mut attempt = 0;
+        let mut status: fe_status = fe_status::FE_NONE;
+        while (status as u8 & fe_status::FE_HAS_LOCK as u8) == 0 {
+            std::thread::sleep(Duration::from_secs(1));
 
+            unsafe {
+                let _frontend = self.frontend.lock().unwrap();
                 dvb_fe_get_stats(p);
                 dvb_fe_retrieve_stats(p, DTV_STATUS, &mut status as *mut fe_status as *mut _);
+            }
 
-                if attempt >= 5 {
-                    bail!("No signal");
-                } else {
-                    attempt += 1;
-                }
+            if attempt >= 5 {
+                bail!("No signal");
+            } else {
+                attempt += 1;
             }
+        }
 
+        unsafe {
+            let _frontend = self.frontend.lock().unwrap();
             dvb_fe_get_stats(p);
 
-            let stats = *dvb_fe_retrieve_stats_layer(p, DTV_STAT_SIGNAL_STRENGTH, 0);
-            if stats.scale == fecap_scale_params::FE_SCALE_DECIBEL as u8 {
-                info!(
-                    "Signal Strength: {:.2} dBm",
-                    stats.__bindgen_anon_1.svalue as f64 / 1000.0,
-                );
+            if let Some(strength) = decibel(retrieve_stats(p, DTV_STAT_SIGNAL_STRENGTH, 0)) {
+                info!("Signal Strength: {:.2} dBm", strength);
             }
+        }
 
-            if dvb_dev_dmx_set_pesfilter(
-                self.demux.fd,
-                0x2000, // select all PIDs
-                dmx_ts_pes::DMX_PES_OTHER,
-                dmx_output::DMX_OUT_TS_TAP,
-                DVR_BUFFER_SIZE,
-            ) < 0
-            {
-                bail!("Couldn't set the PES filter");
+        // The filters of the previous channel are closed before passing everything again.
+        let mut filters = self.filters.lock().unwrap();
+        *filters = PidFilters::default();
+        filters.all = Some(self.open_filter(ALL_PIDS)?);
+
+        Ok(())
+    }
+
+    fn stats(&self) -> anyhow::Result<Option<TunerStats>> {
+        let frontend = self.frontend.lock().unwrap();
+
+        unsafe {
+            let p = self.dev.fe_parms;
+
+            if dvb_fe_get_stats(p) < 0 {
+                bail!("Couldn't read the frontend statistics");
             }
+
+            let mut status: fe_status = fe_status::FE_NONE;
+            dvb_fe_retrieve_stats(p, DTV_STATUS, &mut status as *mut fe_status as *mut _);
+
+            let whole = measure_layer(p, 0);
+            let layers = match frontend.is_isdb_t {
+                true => (1..=3).map(|layer| measure_layer(p, layer)).collect(),
+                false => Vec::new(),
+            };
+
+            Ok(Some(TunerStats {
+                locked: (status as u8 & fe_status::FE_HAS_LOCK as u8) != 0,
+                signal_strength_dbm: decibel(retrieve_stats(p, DTV_STAT_SIGNAL_STRENGTH, 0)),
+                cnr_db: whole.cnr_db,
+                pre_ber: whole.pre_ber,
+                post_ber: whole.post_ber,
+                layers,
+            }))
         }
+    }
+
+    fn set_pid_filter(&self, pids: Option<&BTreeSet<u16>>) -> anyhow::Result<()> {
+        let mut filters = self.filters.lock().unwrap();
+
+        let Some(pids) = pids else {
+            if filters.all.is_none() {
+                filters.all = Some(self.open_filter(ALL_PIDS)?);
+            }
+            filters.pids.clear();
+
+            return Ok(());
+        };
+
+        filters.pids.retain(|pid, _| pids.contains(pid));
+        for &pid in pids {
+            if !filters.pids.contains_key(&pid) {
+                let demux = self.open_filter(pid)?;
+                filters.pids.insert(pid, demux);
+            }
+        }
+
+        // Everything keeps passing until the wanted PIDs pass on their own.
+        filters.all = None;
+        info!(pids = ?pids, "Filtering PIDs");
 
         Ok(())
     }
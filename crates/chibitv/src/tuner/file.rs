@@ -0,0 +1,513 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, bail};
+use bytes::Bytes;
+use tracing::{debug, info};
+
+use chibitv_b60::ip::UdpDatagram;
+use chibitv_b60::ntp::{NTP_PORT, NtpPacket, NtpTimestamp};
+
+use super::{Tuner, TunerStats};
+use crate::channel::Channel;
+
+const TS_PACKET_SIZE: usize = 188;
+const TS_SYNC_BYTE: u8 = 0x47;
+const TLV_SYNC_BYTE: u8 = 0x7F;
+const TLV_HEADER_SIZE: usize = 4;
+
+const TLV_TYPE_IPV4: u8 = 0x01;
+const TLV_TYPE_IPV6: u8 = 0x02;
+
+/// How far the timestamps may drift from the wall clock before the pace is taken up again from the
+/// current position, as happens on a PCR wrap-around or at a splice in the capture.
+const MAX_TIMESTAMP_JUMP: Duration = Duration::from_secs(5);
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum CaptureFormat {
+    M2ts,
+    Mmts,
+}
+
+impl CaptureFormat {
+    fn from_path(path: &Path) -> anyhow::Result<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("m2ts" | "ts") => Ok(Self::M2ts),
+            Some("mmts" | "tlv") => Ok(Self::Mmts),
+            _ => bail!(
+                "Cannot tell the format of {} from its extension; use .m2ts, .ts, .mmts or .tlv",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Plays captures written by `record` back as if they were received live.
+///
+/// Each channel is mapped by its name to a capture, which `tune` switches to. The capture is read
+/// at the pace of the timestamps it carries and starts over when it ends, so the stream never runs
+/// dry and can be opened as many times as the callers like.
+pub struct FileTuner {
+    channels: BTreeMap<String, (PathBuf, CaptureFormat)>,
+    selected: Mutex<Option<(PathBuf, CaptureFormat)>>,
+}
+
+impl FileTuner {
+    pub fn new(channels: &BTreeMap<String, PathBuf>) -> anyhow::Result<Self> {
+        let channels = channels
+            .iter()
+            .map(|(name, path)| {
+                let format = CaptureFormat::from_path(path)?;
+                Ok((name.clone(), (path.clone(), format)))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            channels,
+            selected: Mutex::new(None),
+        })
+    }
+}
+
+impl Tuner for FileTuner {
+    fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+        let Some((path, format)) = self.selected.lock().unwrap().clone() else {
+            bail!("No channel is tuned yet");
+        };
+
+        let file =
+            File::open(&path).with_context(|| format!("Could not open {}", path.display()))?;
+
+        Ok(Box::new(ReplayReader::new(file, format)))
+    }
+
+    fn tune(&self, channel: Channel) -> anyhow::Result<()> {
+        let Some(capture) = self.channels.get(&channel.name) else {
+            bail!("No capture is configured for the channel {}", channel.name);
+        };
+
+        info!(
+            "Replaying {} as the channel {}",
+            capture.0.display(),
+            channel.name
+        );
+        *self.selected.lock().unwrap() = Some(capture.clone());
+
+        Ok(())
+    }
+
+    fn stats(&self) -> anyhow::Result<Option<TunerStats>> {
+        Ok(Some(TunerStats {
+            locked: self.selected.lock().unwrap().is_some(),
+            ..Default::default()
+        }))
+    }
+}
+
+/// Keeps a stream of timestamps in step with the wall clock.
+#[derive(Default)]
+struct Pacer {
+    origin: Option<(Instant, Duration)>,
+}
+
+impl Pacer {
+    /// Returns how long to wait before handing out the data stamped with `timestamp`.
+    fn delay(&mut self, timestamp: Duration, now: Instant) -> Duration {
+        let Some((origin_instant, origin_timestamp)) = self.origin else {
+            self.origin = Some((now, timestamp));
+            return Duration::ZERO;
+        };
+
+        let deadline = timestamp
+            .checked_sub(origin_timestamp)
+            .map(|elapsed| origin_instant + elapsed);
+
+        match deadline {
+            Some(deadline)
+                if deadline <= now + MAX_TIMESTAMP_JUMP && now <= deadline + MAX_TIMESTAMP_JUMP =>
+            {
+                deadline.saturating_duration_since(now)
+            }
+            _ => {
+                debug!("Timestamps are discontinuous; rebasing the pace");
+                self.origin = Some((now, timestamp));
+                Duration::ZERO
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.origin = None;
+    }
+}
+
+struct ReplayReader<R> {
+    inner: BufReader<R>,
+    format: CaptureFormat,
+    pacer: Pacer,
+    pcr_pid: Option<u16>,
+    unit: Vec<u8>,
+    position: usize,
+    has_data: bool,
+}
+
+impl<R: Read + Seek> ReplayReader<R> {
+    fn new(inner: R, format: CaptureFormat) -> Self {
+        Self {
+            inner: BufReader::new(inner),
+            format,
+            pacer: Pacer::default(),
+            pcr_pid: None,
+            unit: Vec::new(),
+            position: 0,
+            has_data: false,
+        }
+    }
+
+    /// Reads the next TS or TLV packet into `unit`, starting over from the beginning at EOF.
+    fn fill_unit(&mut self) -> std::io::Result<()> {
+        loop {
+            if self.read_unit()? {
+                self.has_data = true;
+                return Ok(());
+            }
+
+            if !self.has_data {
+                return Err(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "The capture contains no packets",
+                ));
+            }
+
+            debug!("Reached the end of the capture; starting over");
+            self.inner.seek(SeekFrom::Start(0))?;
+            self.pacer.reset();
+            self.has_data = false;
+        }
+    }
+
+    fn read_unit(&mut self) -> std::io::Result<bool> {
+        let sync_byte = match self.format {
+            CaptureFormat::M2ts => TS_SYNC_BYTE,
+            CaptureFormat::Mmts => TLV_SYNC_BYTE,
+        };
+
+        // Skip whatever precedes the next sync byte, such as a truncated packet.
+        let mut byte = [0u8];
+        loop {
+            if !read_exact_or_eof(&mut self.inner, &mut byte)? {
+                return Ok(false);
+            }
+            if byte[0] == sync_byte {
+                break;
+            }
+        }
+
+        self.unit.clear();
+        self.unit.push(byte[0]);
+
+        let unit_size = match self.format {
+            CaptureFormat::M2ts => TS_PACKET_SIZE,
+            CaptureFormat::Mmts => {
+                let mut header = [0u8; TLV_HEADER_SIZE - 1];
+                if !read_exact_or_eof(&mut self.inner, &mut header)? {
+                    return Ok(false);
+                }
+                self.unit.extend_from_slice(&header);
+                TLV_HEADER_SIZE + u16::from_be_bytes([header[1], header[2]]) as usize
+            }
+        };
+
+        let header_size = self.unit.len();
+        self.unit.resize(unit_size, 0);
+        if !read_exact_or_eof(&mut self.inner, &mut self.unit[header_size..])? {
+            return Ok(false);
+        }
+        self.position = 0;
+
+        let timestamp = match self.format {
+            CaptureFormat::M2ts => read_pcr(&self.unit, &mut self.pcr_pid),
+            CaptureFormat::Mmts => read_ntp_timestamp(&self.unit),
+        };
+        if let Some(timestamp) = timestamp {
+            let delay = self.pacer.delay(timestamp, Instant::now());
+            if !delay.is_zero() {
+                std::thread::sleep(delay);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read + Seek> Read for ReplayReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.position >= self.unit.len() {
+            self.fill_unit()?;
+        }
+
+        let len = buf.len().min(self.unit.len() - self.position);
+        buf[..len].copy_from_slice(&self.unit[self.position..self.position + len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
+
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads the PCR of a TS packet, following the PID that carries it first.
+fn read_pcr(packet: &[u8], pcr_pid: &mut Option<u16>) -> Option<Duration> {
+    let pid = u16::from_be_bytes([packet[1], packet[2]]) & 0x1FFF;
+    let has_adaptation_field = packet[3] & 0x20 != 0;
+    if !has_adaptation_field || packet[4] < 7 || packet[5] & 0x10 == 0 {
+        return None;
+    }
+    if *pcr_pid.get_or_insert(pid) != pid {
+        return None;
+    }
+
+    let base = ((packet[6] as u64) << 25)
+        | ((packet[7] as u64) << 17)
+        | ((packet[8] as u64) << 9)
+        | ((packet[9] as u64) << 1)
+        | ((packet[10] as u64) >> 7);
+    let extension = (((packet[10] & 0x01) as u64) << 8) | packet[11] as u64;
+    let ticks = base * 300 + extension;
+
+    Some(Duration::from_nanos(ticks * 1_000 / 27))
+}
+
+/// Reads the transmit timestamp of an NTP packet carried in an uncompressed IP TLV packet.
+///
+/// The seconds are taken as they are, since only the differences between timestamps pace the
+/// capture; the rollover of the NTP era is a discontinuity the pacer rebases on.
+fn read_ntp_timestamp(packet: &[u8]) -> Option<Duration> {
+    let read_ip_packet = match packet[1] {
+        TLV_TYPE_IPV4 => UdpDatagram::read_ipv4,
+        TLV_TYPE_IPV6 => UdpDatagram::read_ipv6,
+        _ => return None,
+    };
+    let datagram =
+        read_ip_packet(&mut Bytes::copy_from_slice(&packet[TLV_HEADER_SIZE..])).ok()??;
+    if datagram.destination.port() != NTP_PORT {
+        return None;
+    }
+
+    let NtpTimestamp { seconds, fraction } = NtpPacket::read(&mut datagram.payload.clone())
+        .ok()?
+        .transmit_timestamp;
+
+    Some(
+        Duration::from_secs(u64::from(seconds))
+            + Duration::from_nanos((u64::from(fraction) * 1_000_000_000) >> 32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+    use crate::channel::{ALL_ISDB_T_LAYERS, ChannelInner};
+
+    fn ts_packet(pid: u16, pcr: Option<u64>) -> Vec<u8> {
+        let mut packet = vec![0xFF; TS_PACKET_SIZE];
+        packet[0] = TS_SYNC_BYTE;
+        packet[1] = (pid >> 8) as u8;
+        packet[2] = pid as u8;
+        packet[3] = 0x10;
+
+        if let Some(base) = pcr {
+            packet[3] = 0x30;
+            packet[4] = 7;
+            packet[5] = 0x10;
+            packet[6] = (base >> 25) as u8;
+            packet[7] = (base >> 17) as u8;
+            packet[8] = (base >> 9) as u8;
+            packet[9] = (base >> 1) as u8;
+            packet[10] = ((base & 1) << 7) as u8 | 0x7E;
+            packet[11] = 0;
+        }
+
+        packet
+    }
+
+    fn channel(name: &str) -> Channel {
+        Channel {
+            id: 0,
+            name: name.to_string(),
+            transport_stream_id: None,
+            inner: ChannelInner::IsdbT {
+                frequency: 0,
+                bandwidth_hz: 0,
+                layers: ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn reads_pcr_of_the_first_pcr_pid_only() {
+        let mut pcr_pid = None;
+
+        assert_eq!(
+            read_pcr(&ts_packet(0x100, Some(90_000)), &mut pcr_pid),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            read_pcr(&ts_packet(0x200, Some(90_000)), &mut pcr_pid),
+            None
+        );
+        assert_eq!(read_pcr(&ts_packet(0x100, None), &mut pcr_pid), None);
+    }
+
+    #[test]
+    fn paces_timestamps_against_the_wall_clock() {
+        let mut pacer = Pacer::default();
+        let now = Instant::now();
+
+        assert_eq!(pacer.delay(Duration::from_secs(10), now), Duration::ZERO);
+        assert_eq!(
+            pacer.delay(Duration::from_millis(10_500), now),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            pacer.delay(Duration::from_secs(11), now + Duration::from_secs(2)),
+            Duration::ZERO
+        );
+
+        // A jump backwards rebases instead of waiting forever.
+        assert_eq!(pacer.delay(Duration::from_secs(1), now), Duration::ZERO);
+        assert_eq!(
+            pacer.delay(Duration::from_millis(1_100), now),
+            Duration::from_millis(100)
+        );
+    }
+
+    #[test]
+    fn loops_the_capture_at_eof() {
+        let mut capture = Vec::new();
+        capture.extend(ts_packet(0x100, None));
+        capture.extend(ts_packet(0x101, None));
+
+        let mut reader = ReplayReader::new(Cursor::new(capture.clone()), CaptureFormat::M2ts);
+        let mut output = vec![0; capture.len() * 3];
+        reader.read_exact(&mut output).unwrap();
+
+        assert_eq!(output, capture.repeat(3));
+    }
+
+    #[test]
+    fn fails_to_replay_an_empty_capture() {
+        let mut reader = ReplayReader::new(Cursor::new(Vec::new()), CaptureFormat::Mmts);
+
+        assert!(reader.read(&mut [0; 16]).is_err());
+    }
+
+    #[test]
+    fn paces_an_mmts_capture_by_its_ntp_timestamps() {
+        // A quarter of a second apart.
+        let capture = [ntp_tlv_packet(10, 0), ntp_tlv_packet(10, 0x4000_0000)].concat();
+
+        let mut reader = ReplayReader::new(Cursor::new(capture.clone()), CaptureFormat::Mmts);
+        let mut output = vec![0; capture.len()];
+        let start = Instant::now();
+        reader.read_exact(&mut output).unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        assert_eq!(output, capture);
+    }
+
+    #[test]
+    fn switches_captures_on_tune() {
+        let directory = tempfile::tempdir().unwrap();
+        let first = directory.path().join("first.m2ts");
+        let second = directory.path().join("second.m2ts");
+        File::create(&first)
+            .unwrap()
+            .write_all(&ts_packet(0x100, None))
+            .unwrap();
+        File::create(&second)
+            .unwrap()
+            .write_all(&ts_packet(0x200, None))
+            .unwrap();
+
+        let tuner = FileTuner::new(&BTreeMap::from([
+            ("First".to_string(), first),
+            ("Second".to_string(), second),
+        ]))
+        .unwrap();
+        assert!(tuner.open().is_err());
+        assert!(tuner.tune(channel("Unknown")).is_err());
+
+        let mut packet = [0; TS_PACKET_SIZE];
+        tuner.tune(channel("First")).unwrap();
+        tuner.open().unwrap().read_exact(&mut packet).unwrap();
+        assert_eq!(packet, ts_packet(0x100, None).as_slice());
+
+        tuner.tune(channel("Second")).unwrap();
+        tuner.open().unwrap().read_exact(&mut packet).unwrap();
+        assert_eq!(packet, ts_packet(0x200, None).as_slice());
+    }
+
+    /// An NTP packet in an IPv6 TLV packet, transmitted at `seconds` past the Unix epoch.
+    fn ntp_tlv_packet(seconds: u32, fraction: u32) -> Vec<u8> {
+        let mut ntp = vec![0x25, 0x01, 0x04, 0xEC];
+        ntp.extend([0; 36]);
+        ntp.extend(seconds.to_be_bytes());
+        ntp.extend(fraction.to_be_bytes());
+
+        let udp_length = 8 + ntp.len() as u16;
+        let mut packet = vec![TLV_SYNC_BYTE, TLV_TYPE_IPV6];
+        packet.extend((40 + udp_length).to_be_bytes());
+        packet.extend([0x60, 0x00, 0x00, 0x00]);
+        packet.extend(udp_length.to_be_bytes());
+        packet.extend([0x11, 0x01]);
+        packet.extend([0; 32]);
+        packet.extend([0x00, 0x7B, 0x00, 0x7B]);
+        packet.extend(udp_length.to_be_bytes());
+        packet.extend([0x00, 0x00]);
+        packet.extend(ntp);
+
+        packet
+    }
+
+    #[test]
+    fn reads_the_ntp_timestamp_of_an_ip_packet() {
+        let mut packet = ntp_tlv_packet(10, 0x8000_0000);
+        assert_eq!(
+            read_ntp_timestamp(&packet),
+            Some(Duration::from_millis(10_500))
+        );
+
+        // The same datagram to another port.
+        packet[TLV_HEADER_SIZE + 43] = 0x7C;
+        assert_eq!(read_ntp_timestamp(&packet), None);
+    }
+
+    #[test]
+    fn rebases_the_pace_on_the_rollover_of_the_ntp_era() {
+        let timestamp = |seconds| read_ntp_timestamp(&ntp_tlv_packet(seconds, 0)).unwrap();
+        let mut pacer = Pacer::default();
+        let now = Instant::now();
+
+        assert_eq!(pacer.delay(timestamp(u32::MAX), now), Duration::ZERO);
+        assert_eq!(pacer.delay(timestamp(0), now), Duration::ZERO);
+        assert_eq!(pacer.delay(timestamp(1), now), Duration::from_secs(1));
+    }
+}
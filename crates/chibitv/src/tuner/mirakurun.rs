@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use tracing::info;
+
+use super::Tuner;
+use crate::channel::{Channel, ChannelInner, uhf_channel};
+
+/// How long connecting to the server may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the server may take to answer a request with the headers of its response. The body is
+/// a live stream, which is left to the stall detection of the stream instead.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Receives channels from a remote server speaking the HTTP API of Mirakurun.
+///
+/// Mirakurun names a channel by its type and its channel on that type, such as `GR/27` or
+/// `BS/BS15_0`. ISDB-T channels are named after the UHF channel their frequency belongs to unless
+/// `channels` names them otherwise, which it has to for every other channel.
+pub struct MirakurunTuner {
+    agent: ureq::Agent,
+    url: String,
+    channels: BTreeMap<String, String>,
+    selected: Mutex<Option<String>>,
+}
+
+impl MirakurunTuner {
+    pub fn new(url: &str, channels: &BTreeMap<String, String>) -> Self {
+        Self {
+            agent: ureq::Agent::new_with_config(
+                ureq::Agent::config_builder()
+                    .timeout_connect(Some(CONNECT_TIMEOUT))
+                    .timeout_recv_response(Some(RESPONSE_TIMEOUT))
+                    .build(),
+            ),
+            url: url.trim_end_matches('/').to_string(),
+            channels: channels.clone(),
+            selected: Mutex::new(None),
+        }
+    }
+
+    fn remote_channel(&self, channel: &Channel) -> anyhow::Result<String> {
+        if let Some(remote_channel) = self.channels.get(&channel.name) {
+            return Ok(remote_channel.clone());
+        }
+
+        match channel.inner {
+            ChannelInner::IsdbT { frequency, .. } => uhf_channel(frequency)
+                .map(|physical_channel| format!("GR/{physical_channel}"))
+                .with_context(|| format!("{frequency} Hz is not a UHF channel")),
+            ChannelInner::IsdbS { .. } | ChannelInner::IsdbSTs { .. } => bail!(
+                "The Mirakurun channel of {} has to be configured in `channels`",
+                channel.name
+            ),
+        }
+    }
+}
+
+impl Tuner for MirakurunTuner {
+    fn open(&self) -> anyhow::Result<Box<dyn Read + Send + Sync>> {
+        let Some(remote_channel) = self.selected.lock().unwrap().clone() else {
+            bail!("No channel is tuned yet");
+        };
+
+        // The stream is requested as it is received, since chibitv descrambles it by itself.
+        let url = format!("{}/api/channels/{remote_channel}/stream?decode=0", self.url);
+        let response = self
+            .agent
+            .get(&url)
+            .call()
+            .with_context(|| format!("Could not request {url}"))?;
+
+        Ok(Box::new(response.into_body().into_reader()))
+    }
+
+    fn tune(&self, channel: Channel) -> anyhow::Result<()> {
+        let remote_channel = self.remote_channel(&channel)?;
+
+        info!(
+            "Receiving {} from {} as {}",
+            channel.name, self.url, remote_channel
+        );
+        *self.selected.lock().unwrap() = Some(remote_channel);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::channel::ALL_ISDB_T_LAYERS;
+
+    /// Serves `body` to a single request, reporting the request line it received.
+    fn serve_once(body: &'static [u8]) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header == "\r\n" {
+                    break;
+                }
+            }
+            tx.send(request_line.trim_end().to_string()).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: video/MP2T\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        });
+
+        (url, rx)
+    }
+
+    fn terrestrial_channel(name: &str, frequency: u32) -> Channel {
+        Channel {
+            id: 0,
+            name: name.to_string(),
+            transport_stream_id: None,
+            inner: ChannelInner::IsdbT {
+                frequency,
+                bandwidth_hz: 6_000_000,
+                layers: ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn streams_the_tuned_channel_from_the_server() {
+        let (url, requests) = serve_once(b"capture");
+        let tuner = MirakurunTuner::new(&format!("{url}/"), &BTreeMap::new());
+
+        tuner
+            .tune(terrestrial_channel("UHF 27", 557_142_857))
+            .unwrap();
+        let mut body = Vec::new();
+        tuner.open().unwrap().read_to_end(&mut body).unwrap();
+
+        assert_eq!(body, b"capture");
+        assert_eq!(
+            requests.recv().unwrap(),
+            "GET /api/channels/GR/27/stream?decode=0 HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn prefers_the_configured_remote_channel() {
+        let tuner = MirakurunTuner::new(
+            "http://localhost:40772",
+            &BTreeMap::from([("BS Example".to_string(), "BS/BS15_0".to_string())]),
+        );
+
+        let satellite = Channel {
+            id: 1,
+            name: "BS Example".to_string(),
+            transport_stream_id: None,
+            inner: ChannelInner::IsdbS {
+                frequency: 1_318_000,
+                stream_id: 0x40F1,
+            },
+        };
+        assert_eq!(tuner.remote_channel(&satellite).unwrap(), "BS/BS15_0");
+
+        let unnamed = Channel {
+            name: "Unknown".to_string(),
+            ..satellite
+        };
+        assert!(tuner.remote_channel(&unnamed).is_err());
+        assert!(
+            tuner
+                .remote_channel(&terrestrial_channel("Off grid", 500_000_000))
+                .is_err()
+        );
+    }
+}
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
-use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::{BroadcastStream, WatchStream};
 
 use crate::channel::{Channel, ChannelInner};
 use crate::event_crawler::EventCrawler;
 use crate::registry::Registry;
 use crate::service_information::Signal;
-use crate::stream::{Stream, Streams, SubscribeError};
+use crate::stream::{Reception, Stream, Streams, SubscribeError};
+use crate::tuner::Tuners;
 
 pub enum WorkspaceError {
     ChannelNotFound,
@@ -22,6 +23,8 @@ pub struct StreamSubscription {
     pub init_segment: Option<Bytes>,
     pub fmp4: BroadcastStream<Bytes>,
     pub signals: BroadcastStream<Signal>,
+    /// Every change of the reception after the subscription.
+    pub receptions: WatchStream<Reception>,
 }
 
 pub struct Workspace {
@@ -29,6 +32,7 @@ pub struct Workspace {
     channels: Vec<Channel>,
     streams: Option<Streams>,
     event_crawler: Option<Arc<EventCrawler>>,
+    tuners: Option<Arc<Tuners>>,
 }
 
 impl Workspace {
@@ -38,6 +42,7 @@ impl Workspace {
             channels,
             streams,
             event_crawler: None,
+            tuners: None,
         }
     }
 
@@ -46,6 +51,11 @@ impl Workspace {
         self
     }
 
+    pub fn with_tuners(mut self, tuners: Arc<Tuners>) -> Self {
+        self.tuners = Some(tuners);
+        self
+    }
+
     pub fn channels(&self) -> impl Iterator<Item = (usize, &Channel)> {
         self.channels.iter().enumerate()
     }
@@ -62,6 +72,10 @@ impl Workspace {
         self.event_crawler.clone()
     }
 
+    pub fn tuners(&self) -> Option<Arc<Tuners>> {
+        self.tuners.clone()
+    }
+
     /// Attaches to the shared stream of the service, tuning to it first when
     /// nobody is streaming it yet.
     pub async fn subscribe_stream(
@@ -80,7 +94,9 @@ impl Workspace {
                 ChannelInner::IsdbS { stream_id, .. } => {
                     *stream_id == u32::from(service.transport_stream_id)
                 }
-                ChannelInner::IsdbT { .. } => service.channel_id == channel.id,
+                ChannelInner::IsdbSTs { .. } | ChannelInner::IsdbT { .. } => {
+                    service.channel_id == channel.id
+                }
             })
             .ok_or(WorkspaceError::ChannelNotFound)?;
 
@@ -99,12 +115,14 @@ impl Workspace {
 
         let (init_segment, fmp4) = stream.subscribe_fmp4();
         let signals = stream.subscribe_signal();
+        let receptions = stream.subscribe_reception();
 
         Ok(StreamSubscription {
             stream,
             init_segment,
             fmp4: BroadcastStream::new(fmp4),
             signals: BroadcastStream::new(signals),
+            receptions: WatchStream::from_changes(receptions),
         })
     }
 }
@@ -112,14 +130,18 @@ impl Workspace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channel::ALL_ISDB_T_LAYERS;
 
     fn channel() -> Channel {
         Channel {
             id: 0,
             name: "UHF 20".to_string(),
+            transport_stream_id: None,
             inner: ChannelInner::IsdbT {
                 frequency: 515_142_857,
                 bandwidth_hz: 6_000_000,
+                layers: ALL_ISDB_T_LAYERS,
+                alternate_frequencies: vec![],
             },
         }
     }
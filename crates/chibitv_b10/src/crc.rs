@@ -0,0 +1,71 @@
+//! The CRC-32 closing the sections of ISO/IEC 13818-1 Annex A, computed MSB first without a
+//! final XOR.
+
+use crate::error::{Error, Result};
+
+const POLYNOMIAL: u32 = 0x04C1_1DB7;
+
+const TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = (index as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ POLYNOMIAL
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+
+    table
+};
+
+pub fn crc_32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0xFFFF_FFFF, |crc, &byte| {
+        (crc << 8) ^ TABLE[usize::from((crc >> 24) as u8 ^ byte)]
+    })
+}
+
+/// Checks the CRC_32 in the last 4 bytes of a section against the bytes before it.
+pub(crate) fn verify_crc_32(section: &[u8]) -> Result<()> {
+    let Some((body, crc)) = section.split_last_chunk::<4>() else {
+        return Err(Error::UnexpectedEof);
+    };
+    let expected = crc_32(body);
+    let actual = u32::from_be_bytes(*crc);
+    if expected != actual {
+        return Err(Error::CrcMismatch { expected, actual });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_the_crc_32_of_mpeg_2() {
+        assert_eq!(crc_32(b"123456789"), 0x0376_E6E7);
+        assert_eq!(crc_32(&[]), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn verifies_the_crc_32_closing_a_section() {
+        let mut section = vec![0x00, 0xB0, 0x09, 0x7F, 0xE1, 0xC1, 0x00, 0x00];
+        section.extend(crc_32(&section).to_be_bytes());
+        assert_eq!(verify_crc_32(&section), Ok(()));
+        // The CRC over a whole section, its CRC_32 included, is zero.
+        assert_eq!(crc_32(&section), 0);
+
+        section[3] ^= 0x01;
+        assert!(verify_crc_32(&section).unwrap_err().is_crc_mismatch());
+        assert_eq!(verify_crc_32(&[0x00, 0x01]), Err(Error::UnexpectedEof));
+    }
+}
@@ -1,8 +1,7 @@
-use std::io::{Error, ErrorKind, Result};
-
 use bytes::{Buf, Bytes};
 use strum::FromRepr;
 
+use crate::error::{Error, Result};
 use crate::read_ext::BytesExt;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -14,15 +13,8 @@ pub struct CaDescriptor {
 
 impl CaDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        if bytes.remaining() < 4 {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "CA descriptor must be at least 4 bytes",
-            ));
-        }
-
-        let ca_system_id = bytes.get_u16();
-        let ca_pid = bytes.get_u16() & 0x1FFF;
+        let ca_system_id = bytes.try_get_u16()?;
+        let ca_pid = bytes.try_get_u16()? & 0x1FFF;
         let private_data = bytes.to_vec();
 
         Ok(Self {
@@ -54,8 +46,8 @@ pub struct ServiceListItem {
 
 impl ServiceListItem {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_id = bytes.get_u16();
-        let service_type = bytes.get_u8();
+        let service_id = bytes.try_get_u16()?;
+        let service_type = bytes.try_get_u8()?;
 
         Ok(Self {
             service_id,
@@ -80,6 +72,55 @@ impl ServiceListDescriptor {
     }
 }
 
+/// Satellite delivery system descriptor, telling where a transport stream or TLV stream of ISDB-S
+/// is transmitted. Every number is decoded from its BCD form.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SatelliteDeliverySystemDescriptor {
+    /// The downlink frequency in 10 kHz, such as `1172748` for BS-1 at 11.72748 GHz.
+    pub frequency: u32,
+    /// The orbital position in 0.1 degrees, such as `1100` for 110.0 degrees.
+    pub orbital_position: u16,
+    pub west_east_flag: bool,
+    pub polarisation: u8,
+    pub modulation: u8,
+    /// The symbol rate in 100 symbols per second.
+    pub symbol_rate: u32,
+    pub fec_inner: u8,
+}
+
+impl SatelliteDeliverySystemDescriptor {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let frequency = bcd(bytes.try_get_u32()?, 8);
+        let orbital_position = bcd(u32::from(bytes.try_get_u16()?), 4) as u16;
+
+        let head = bytes.try_get_u8()?;
+        let west_east_flag = (head & 0x80) != 0;
+        let polarisation = (head & 0x60) >> 5;
+        let modulation = head & 0x1F;
+
+        let tail = bytes.try_get_u32()?;
+        let symbol_rate = bcd(tail >> 4, 7);
+        let fec_inner = (tail & 0x0F) as u8;
+
+        Ok(Self {
+            frequency,
+            orbital_position,
+            west_east_flag,
+            polarisation,
+            modulation,
+            symbol_rate,
+            fec_inner,
+        })
+    }
+}
+
+/// Decodes the lowest `digits` nibbles of `value` as a decimal number.
+fn bcd(value: u32, digits: u32) -> u32 {
+    (0..digits).rev().fold(0, |decimal, digit| {
+        decimal * 10 + ((value >> (digit * 4)) & 0x0F)
+    })
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ServiceDescriptor {
     pub service_type: u8,
@@ -89,13 +130,15 @@ pub struct ServiceDescriptor {
 
 impl ServiceDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_type = bytes.get_u8();
+        let service_type = bytes.try_get_u8()?;
 
-        let service_provider_name_length = bytes.get_u8();
-        let service_provider_name = bytes.split_to(service_provider_name_length as usize).into();
+        let service_provider_name_length = bytes.try_get_u8()?;
+        let service_provider_name = bytes
+            .try_split_to(service_provider_name_length as usize)?
+            .into();
 
-        let service_name_length = bytes.get_u8();
-        let service_name = bytes.split_to(service_name_length as usize).into();
+        let service_name_length = bytes.try_get_u8()?;
+        let service_name = bytes.try_split_to(service_name_length as usize)?.into();
 
         Ok(Self {
             service_type,
@@ -105,6 +148,24 @@ impl ServiceDescriptor {
     }
 }
 
+/// Partial reception descriptor, naming the services of an ISDB-T transport stream that are
+/// carried in the centre segment alone, i.e. one-seg.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PartialReceptionDescriptor {
+    pub service_ids: Vec<u16>,
+}
+
+impl PartialReceptionDescriptor {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let mut service_ids = Vec::new();
+        while bytes.remaining() >= 2 {
+            service_ids.push(bytes.try_get_u16()?);
+        }
+
+        Ok(Self { service_ids })
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ShortEventDescriptor {
     pub iso_639_language_code: [u8; 3],
@@ -114,13 +175,13 @@ pub struct ShortEventDescriptor {
 
 impl ShortEventDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let iso_639_language_code = bytes.get_byte_array::<3>();
+        let iso_639_language_code = bytes.try_get_byte_array::<3>()?;
 
-        let event_name_length = bytes.get_u8();
-        let event_name = bytes.split_to(event_name_length as usize).into();
+        let event_name_length = bytes.try_get_u8()?;
+        let event_name = bytes.try_split_to(event_name_length as usize)?.into();
 
-        let text_length = bytes.get_u8();
-        let text = bytes.split_to(text_length as usize).into();
+        let text_length = bytes.try_get_u8()?;
+        let text = bytes.try_split_to(text_length as usize)?.into();
 
         Ok(Self {
             iso_639_language_code,
@@ -138,11 +199,11 @@ pub struct ExtendedEventItem {
 
 impl ExtendedEventItem {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let item_description_length = bytes.get_u8();
-        let item_description = split_to(bytes, item_description_length as usize)?.into();
+        let item_description_length = bytes.try_get_u8()?;
+        let item_description = bytes.try_split_to(item_description_length as usize)?.into();
 
-        let item_length = bytes.get_u8();
-        let item = split_to(bytes, item_length as usize)?.into();
+        let item_length = bytes.try_get_u8()?;
+        let item = bytes.try_split_to(item_length as usize)?.into();
 
         Ok(Self {
             item_description,
@@ -168,22 +229,15 @@ pub struct ExtendedEventDescriptor {
 
 impl ExtendedEventDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        if bytes.remaining() < 5 {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "extended event descriptor must be at least 5 bytes",
-            ));
-        }
-
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let descriptor_number = (head & 0xF0) >> 4;
         let last_descriptor_number = head & 0x0F;
 
-        let iso_639_language_code = bytes.get_byte_array::<3>();
+        let iso_639_language_code = bytes.try_get_byte_array::<3>()?;
 
         let items = {
-            let length_of_items = bytes.get_u8();
-            let mut bytes = split_to(bytes, length_of_items as usize)?;
+            let length_of_items = bytes.try_get_u8()?;
+            let mut bytes = bytes.try_split_to(length_of_items as usize)?;
             let mut items = Vec::new();
             while bytes.has_remaining() {
                 items.push(ExtendedEventItem::read(&mut bytes)?);
@@ -192,8 +246,8 @@ impl ExtendedEventDescriptor {
             items
         };
 
-        let text_length = bytes.get_u8();
-        let text = split_to(bytes, text_length as usize)?.into();
+        let text_length = bytes.try_get_u8()?;
+        let text = bytes.try_split_to(text_length as usize)?.into();
 
         Ok(Self {
             descriptor_number,
@@ -205,26 +259,17 @@ impl ExtendedEventDescriptor {
     }
 }
 
-fn split_to(bytes: &mut Bytes, length: usize) -> Result<Bytes> {
-    if bytes.remaining() < length {
-        return Err(Error::new(
-            ErrorKind::UnexpectedEof,
-            "descriptor field runs past the end of the descriptor",
-        ));
-    }
-
-    Ok(bytes.split_to(length))
-}
-
 #[derive(Clone, Debug, FromRepr)]
 #[repr(u8)]
 pub enum DescriptorTag {
     CaDescriptor = 0x09,
     NetworkNameDescriptor = 0x40,
     ServiceListDescriptor = 0x41,
+    SatelliteDeliverySystemDescriptor = 0x43,
     ServiceDescriptor = 0x48,
     ShortEventDescriptor = 0x4D,
     ExtendedEventDescriptor = 0x4E,
+    PartialReceptionDescriptor = 0xFB,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -232,17 +277,19 @@ pub enum Descriptor {
     Ca(CaDescriptor),
     NetworkName(NetworkNameDescriptor),
     ServiceList(ServiceListDescriptor),
+    SatelliteDeliverySystem(SatelliteDeliverySystemDescriptor),
     Service(ServiceDescriptor),
     ShortEvent(ShortEventDescriptor),
     ExtendedEvent(ExtendedEventDescriptor),
+    PartialReception(PartialReceptionDescriptor),
     Unknown(u8, Vec<u8>),
 }
 
 impl Descriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let descriptor_tag = bytes.get_u8();
-        let descriptor_length = bytes.get_u8();
-        let mut bytes = bytes.split_to(descriptor_length as usize);
+        let descriptor_tag = bytes.try_get_u8()?;
+        let descriptor_length = bytes.try_get_u8()?;
+        let mut bytes = bytes.try_split_to(descriptor_length as usize)?;
 
         let Some(descriptor_tag) = DescriptorTag::from_repr(descriptor_tag) else {
             return Ok(Self::Unknown(descriptor_tag, bytes.into()));
@@ -256,6 +303,9 @@ impl Descriptor {
             DescriptorTag::ServiceListDescriptor => {
                 Self::ServiceList(ServiceListDescriptor::read(&mut bytes)?)
             }
+            DescriptorTag::SatelliteDeliverySystemDescriptor => {
+                Self::SatelliteDeliverySystem(SatelliteDeliverySystemDescriptor::read(&mut bytes)?)
+            }
             DescriptorTag::ServiceDescriptor => Self::Service(ServiceDescriptor::read(&mut bytes)?),
             DescriptorTag::ShortEventDescriptor => {
                 Self::ShortEvent(ShortEventDescriptor::read(&mut bytes)?)
@@ -263,6 +313,9 @@ impl Descriptor {
             DescriptorTag::ExtendedEventDescriptor => {
                 Self::ExtendedEvent(ExtendedEventDescriptor::read(&mut bytes)?)
             }
+            DescriptorTag::PartialReceptionDescriptor => {
+                Self::PartialReception(PartialReceptionDescriptor::read(&mut bytes)?)
+            }
         })
     }
 }
@@ -271,12 +324,8 @@ impl TryFrom<&mpeg2ts::ts::Descriptor> for Descriptor {
     type Error = Error;
 
     fn try_from(descriptor: &mpeg2ts::ts::Descriptor) -> Result<Self> {
-        let descriptor_length = u8::try_from(descriptor.data.len()).map_err(|_| {
-            Error::new(
-                ErrorKind::InvalidInput,
-                "descriptor payload must be at most 255 bytes",
-            )
-        })?;
+        let descriptor_length = u8::try_from(descriptor.data.len())
+            .map_err(|_| Error::InvalidData("Descriptor payload must be at most 255 bytes"))?;
 
         let mut bytes = Vec::with_capacity(2 + descriptor.data.len());
         bytes.push(descriptor.tag);
@@ -387,7 +436,49 @@ mod tests {
         ]))
         .unwrap_err();
 
-        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(error, Error::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_satellite_delivery_system_descriptor() {
+        let descriptor = Descriptor::read(&mut Bytes::from_static(&[
+            0x43, 0x0B, // descriptor_tag, descriptor_length
+            0x01, 0x17, 0x27, 0x48, // frequency
+            0x11, 0x00, // orbital_position
+            0xC8, // west_east_flag, polarisation, modulation
+            0x02, 0x88, 0x60, 0x0F, // symbol_rate, FEC_inner
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            descriptor,
+            Descriptor::SatelliteDeliverySystem(SatelliteDeliverySystemDescriptor {
+                frequency: 1_172_748,
+                orbital_position: 1100,
+                west_east_flag: true,
+                polarisation: 2,
+                modulation: 8,
+                symbol_rate: 288_600,
+                fec_inner: 0x0F,
+            })
+        );
+    }
+
+    #[test]
+    fn read_partial_reception_descriptor() {
+        let descriptor = Descriptor::read(&mut Bytes::from_static(&[
+            0xFB, 0x04, // descriptor_tag, descriptor_length
+            0x04, 0x18, // service_id
+            0x04, 0x19, // service_id
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            descriptor,
+            Descriptor::PartialReception(PartialReceptionDescriptor {
+                service_ids: vec![0x0418, 0x0419],
+            })
+        );
     }
 
     #[test]
@@ -398,6 +489,6 @@ mod tests {
         ]))
         .unwrap_err();
 
-        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(error, Error::UnexpectedEof);
     }
 }
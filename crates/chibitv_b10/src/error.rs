@@ -0,0 +1,43 @@
+use std::fmt::{Display, Formatter};
+
+use bytes::TryGetError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A section or descriptor could not be read, as those corrupted by packet loss cannot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// A field runs past the end of the section or descriptor it is in.
+    UnexpectedEof,
+    /// The CRC_32 the section carries (`actual`) is not the one of its bytes (`expected`).
+    CrcMismatch { expected: u32, actual: u32 },
+    /// A field has a value it cannot take.
+    InvalidData(&'static str),
+}
+
+impl Error {
+    pub fn is_crc_mismatch(&self) -> bool {
+        matches!(self, Self::CrcMismatch { .. })
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "Field runs past the end of the section"),
+            Self::CrcMismatch { expected, actual } => write!(
+                f,
+                "Section carries the CRC_32 {actual:#010x} where its bytes give {expected:#010x}"
+            ),
+            Self::InvalidData(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<TryGetError> for Error {
+    fn from(_: TryGetError) -> Self {
+        Self::UnexpectedEof
+    }
+}
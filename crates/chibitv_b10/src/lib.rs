@@ -1,4 +1,9 @@
 pub mod descriptor;
 pub mod table;
 
+mod crc;
+mod error;
 mod read_ext;
+
+pub use crc::crc_32;
+pub use error::{Error, Result};
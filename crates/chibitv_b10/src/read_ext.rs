@@ -1,22 +1,37 @@
 use std::net::{Ipv4Addr, Ipv6Addr};
 
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
 
+use crate::error::{Error, Result};
+
+/// Reads that fail on a buffer too short for them instead of panicking as [`Buf`] does.
 pub(crate) trait BytesExt {
-    fn get_byte_array<const N: usize>(&mut self) -> [u8; N];
+    fn try_get_byte_array<const N: usize>(&mut self) -> Result<[u8; N]>;
+
+    fn try_split_to(&mut self, len: usize) -> Result<Bytes>;
 
-    fn get_ipv4_addr(&mut self) -> Ipv4Addr {
-        Ipv4Addr::from(self.get_byte_array::<4>())
+    fn try_get_ipv4_addr(&mut self) -> Result<Ipv4Addr> {
+        Ok(Ipv4Addr::from(self.try_get_byte_array::<4>()?))
     }
 
-    fn get_ipv6_addr(&mut self) -> Ipv6Addr {
-        Ipv6Addr::from(self.get_byte_array::<16>())
+    fn try_get_ipv6_addr(&mut self) -> Result<Ipv6Addr> {
+        Ok(Ipv6Addr::from(self.try_get_byte_array::<16>()?))
     }
 }
 
 impl BytesExt for Bytes {
-    fn get_byte_array<const N: usize>(&mut self) -> [u8; N] {
-        let buf = self.split_to(N);
-        buf.as_ref().try_into().unwrap()
+    fn try_get_byte_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.try_copy_to_slice(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    fn try_split_to(&mut self, len: usize) -> Result<Bytes> {
+        if self.remaining() < len {
+            return Err(Error::UnexpectedEof);
+        }
+
+        Ok(self.split_to(len))
     }
 }
@@ -1,4 +1,3 @@
-use std::io::Result;
 use std::net::{Ipv4Addr, Ipv6Addr};
 
 use bytes::{Buf, Bytes};
@@ -6,11 +5,13 @@ use chrono::{Duration, NaiveDateTime, NaiveTime};
 use julianday::ModifiedJulianDay;
 use strum::FromRepr;
 
+use crate::crc::verify_crc_32;
 use crate::descriptor::Descriptor;
+use crate::error::{Error, Result};
 use crate::read_ext::BytesExt;
 
 fn read_descriptors(bytes: &mut Bytes, length: usize) -> Result<Vec<Descriptor>> {
-    let mut bytes = bytes.split_to(length);
+    let mut bytes = bytes.try_split_to(length)?;
     let mut descriptors = Vec::new();
     while bytes.has_remaining() {
         descriptors.push(Descriptor::read(&mut bytes)?);
@@ -19,14 +20,14 @@ fn read_descriptors(bytes: &mut Bytes, length: usize) -> Result<Vec<Descriptor>>
     Ok(descriptors)
 }
 
-fn read_section_header(bytes: &mut Bytes) -> (bool, u16) {
-    let head = bytes.get_u16();
-    (((head & 0x8000) >> 15) == 1, head & 0x0FFF)
+fn read_section_header(bytes: &mut Bytes) -> Result<(bool, u16)> {
+    let head = bytes.try_get_u16()?;
+    Ok((((head & 0x8000) >> 15) == 1, head & 0x0FFF))
 }
 
-fn read_version(bytes: &mut Bytes) -> (u8, bool) {
-    let head = bytes.get_u8();
-    ((head & 0b0011_1110) >> 1, (head & 0b0000_0001) == 1)
+fn read_version(bytes: &mut Bytes) -> Result<(u8, bool)> {
+    let head = bytes.try_get_u8()?;
+    Ok(((head & 0b0011_1110) >> 1, (head & 0b0000_0001) == 1))
 }
 
 #[derive(Copy, Clone, Debug, Eq, FromRepr, PartialEq)]
@@ -53,8 +54,8 @@ pub enum ProgramAssociation {
 
 impl ProgramAssociation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let program_number = bytes.get_u16();
-        let pid = bytes.get_u16() & 0x1FFF;
+        let program_number = bytes.try_get_u16()?;
+        let pid = bytes.try_get_u16()? & 0x1FFF;
 
         Ok(if program_number == 0 {
             Self::Network {
@@ -86,18 +87,18 @@ pub struct Pat {
 
 impl Pat {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let transport_stream_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
         let mut programs = Vec::new();
         while bytes.remaining() > 4 {
             programs.push(ProgramAssociation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -128,19 +129,19 @@ pub struct Cat {
 
 impl Cat {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        _ = bytes.get_u8();
-        _ = bytes.get_u8();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        _ = bytes.try_get_u8()?;
+        _ = bytes.try_get_u8()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
         let mut descriptors = Vec::new();
         while bytes.remaining() > 4 {
             descriptors.push(Descriptor::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -164,9 +165,9 @@ pub struct ElementaryStreamInfo {
 
 impl ElementaryStreamInfo {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let stream_type = bytes.get_u8();
-        let elementary_pid = bytes.get_u16() & 0x1FFF;
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let stream_type = bytes.try_get_u8()?;
+        let elementary_pid = bytes.try_get_u16()? & 0x1FFF;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
 
         Ok(Self {
@@ -195,13 +196,13 @@ pub struct Pmt {
 
 impl Pmt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let program_number = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let pcr_pid = bytes.get_u16() & 0x1FFF;
-        let program_info_length = bytes.get_u16() & 0x0FFF;
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let program_number = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let pcr_pid = bytes.try_get_u16()? & 0x1FFF;
+        let program_info_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, program_info_length as usize)?;
 
         let mut streams = Vec::new();
@@ -209,7 +210,7 @@ impl Pmt {
             streams.push(ElementaryStreamInfo::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -236,9 +237,9 @@ pub struct TransportStreamInformation {
 
 impl TransportStreamInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let transport_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
-        let transport_descriptors_length = bytes.get_u16() & 0x0FFF;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
+        let transport_descriptors_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, transport_descriptors_length as usize)?;
 
         Ok(Self {
@@ -266,23 +267,23 @@ pub struct Nit {
 
 impl Nit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let network_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let network_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
-        let network_descriptors_length = bytes.get_u16() & 0x0FFF;
+        let network_descriptors_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, network_descriptors_length as usize)?;
 
-        let transport_stream_loop_length = bytes.get_u16() & 0x0FFF;
-        let mut transport_bytes = bytes.split_to(transport_stream_loop_length as usize);
+        let transport_stream_loop_length = bytes.try_get_u16()? & 0x0FFF;
+        let mut transport_bytes = bytes.try_split_to(transport_stream_loop_length as usize)?;
         let mut transport_streams = Vec::new();
         while transport_bytes.has_remaining() {
             transport_streams.push(TransportStreamInformation::read(&mut transport_bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -316,23 +317,23 @@ pub struct Bat {
 
 impl Bat {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let bouquet_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let bouquet_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
-        let bouquet_descriptors_length = bytes.get_u16() & 0x0FFF;
+        let bouquet_descriptors_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, bouquet_descriptors_length as usize)?;
 
-        let transport_stream_loop_length = bytes.get_u16() & 0x0FFF;
-        let mut transport_bytes = bytes.split_to(transport_stream_loop_length as usize);
+        let transport_stream_loop_length = bytes.try_get_u16()? & 0x0FFF;
+        let mut transport_bytes = bytes.try_split_to(transport_stream_loop_length as usize)?;
         let mut transport_streams = Vec::new();
         while transport_bytes.has_remaining() {
             transport_streams.push(TransportStreamInformation::read(&mut transport_bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -362,14 +363,14 @@ pub struct ServiceInformation {
 
 impl ServiceInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_id = bytes.get_u16();
+        let service_id = bytes.try_get_u16()?;
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let eit_user_defined_flags = (head & 0b0001_1100) >> 2;
         let eit_schedule_flag = ((head & 0b0000_0010) >> 1) == 1;
         let eit_present_following_flag = (head & 0b0000_0001) == 1;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let running_status = ((head & 0xE000) >> 13) as u8;
         let free_ca_mode = ((head & 0x1000) >> 12) == 1;
         let descriptors_loop_length = head & 0x0FFF;
@@ -404,20 +405,20 @@ pub struct Sdt {
 
 impl Sdt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let transport_stream_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let original_network_id = bytes.get_u16();
-        _ = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let original_network_id = bytes.try_get_u16()?;
+        _ = bytes.try_get_u8()?;
 
         let mut services = Vec::new();
         while bytes.remaining() > 4 {
             services.push(ServiceInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -446,11 +447,11 @@ pub struct EventInformation {
 
 impl EventInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let event_id = bytes.get_u16();
-        let start_time = parse_jst_time(bytes.get_byte_array::<5>());
-        let duration = parse_duration(bytes.get_byte_array::<3>());
+        let event_id = bytes.try_get_u16()?;
+        let start_time = parse_jst_time(bytes.try_get_byte_array::<5>()?)?;
+        let duration = parse_duration(bytes.try_get_byte_array::<3>()?);
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let running_status = ((head & 0xE000) >> 13) as u8;
         let free_ca_mode = ((head & 0x1000) >> 12) == 1;
         let descriptors_loop_length = head & 0x0FFF;
@@ -487,22 +488,22 @@ pub struct Eit {
 
 impl Eit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let service_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let transport_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
-        let segment_last_section_number = bytes.get_u8();
-        let last_table_id = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let service_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
+        let segment_last_section_number = bytes.try_get_u8()?;
+        let last_table_id = bytes.try_get_u8()?;
 
         let mut events = Vec::new();
         while bytes.remaining() > 4 {
             events.push(EventInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -532,8 +533,8 @@ pub struct Tdt {
 
 impl Tdt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let jst_time = parse_jst_time(bytes.get_byte_array::<5>());
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let jst_time = parse_jst_time(bytes.try_get_byte_array::<5>()?)?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -555,11 +556,11 @@ pub struct Tot {
 
 impl Tot {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let jst_time = parse_jst_time(bytes.get_byte_array::<5>());
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let jst_time = parse_jst_time(bytes.try_get_byte_array::<5>()?)?;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -582,11 +583,11 @@ pub struct RunningStatusInformation {
 
 impl RunningStatusInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let transport_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
-        let service_id = bytes.get_u16();
-        let event_id = bytes.get_u16();
-        let running_status = bytes.get_u8() & 0b0000_0111;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
+        let service_id = bytes.try_get_u16()?;
+        let event_id = bytes.try_get_u16()?;
+        let running_status = bytes.try_get_u8()? & 0b0000_0111;
 
         Ok(Self {
             transport_stream_id,
@@ -608,7 +609,7 @@ pub struct Rst {
 
 impl Rst {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
         let mut statuses = Vec::new();
         while bytes.has_remaining() {
             statuses.push(RunningStatusInformation::read(bytes)?);
@@ -632,7 +633,7 @@ pub struct St {
 
 impl St {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
         let data = bytes.to_vec();
 
         Ok(Self {
@@ -651,8 +652,8 @@ pub struct ContentSchedule {
 
 impl ContentSchedule {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let start_time = parse_jst_time(bytes.get_byte_array::<5>());
-        let duration = parse_duration(bytes.get_byte_array::<3>());
+        let start_time = parse_jst_time(bytes.try_get_byte_array::<5>()?)?;
+        let duration = parse_duration(bytes.try_get_byte_array::<3>()?);
 
         Ok(Self {
             start_time,
@@ -672,16 +673,17 @@ pub struct ContentVersionInformation {
 
 impl ContentVersionInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let content_version = bytes.get_u16();
-        let content_minor_version = bytes.get_u16();
-        let head = bytes.get_u16();
+        let content_version = bytes.try_get_u16()?;
+        let content_minor_version = bytes.try_get_u16()?;
+        let head = bytes.try_get_u16()?;
         let version_indicator = ((head & 0xC000) >> 14) as u8;
         let content_descriptor_length = head & 0x0FFF;
 
-        let mut content_bytes = bytes.split_to(content_descriptor_length as usize);
-        let schedule_description_length = content_bytes.get_u16() & 0x0FFF;
+        let mut content_bytes = bytes.try_split_to(content_descriptor_length as usize)?;
+        let schedule_description_length = content_bytes.try_get_u16()? & 0x0FFF;
 
-        let mut schedule_bytes = content_bytes.split_to(schedule_description_length as usize);
+        let mut schedule_bytes =
+            content_bytes.try_split_to(schedule_description_length as usize)?;
         let mut schedules = Vec::new();
         while schedule_bytes.has_remaining() {
             schedules.push(ContentSchedule::read(&mut schedule_bytes)?);
@@ -721,22 +723,22 @@ pub struct Pcat {
 
 impl Pcat {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let service_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let transport_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
-        let content_id = bytes.get_u32();
-
-        let num_of_content_version = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let service_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
+        let content_id = bytes.try_get_u32()?;
+
+        let num_of_content_version = bytes.try_get_u8()?;
         let mut contents = Vec::with_capacity(num_of_content_version as usize);
         for _ in 0..num_of_content_version {
             contents.push(ContentVersionInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -763,8 +765,8 @@ pub struct BroadcasterInformation {
 
 impl BroadcasterInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let broadcaster_id = bytes.get_u8();
-        let broadcaster_descriptors_length = bytes.get_u16() & 0x0FFF;
+        let broadcaster_id = bytes.try_get_u8()?;
+        let broadcaster_descriptors_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, broadcaster_descriptors_length as usize)?;
 
         Ok(Self {
@@ -792,13 +794,13 @@ pub struct Bit {
 
 impl Bit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let original_network_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let original_network_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let broadcast_view_propriety = ((head & 0x1000) >> 12) == 1;
         let first_descriptors_length = head & 0x0FFF;
         let descriptors = read_descriptors(bytes, first_descriptors_length as usize)?;
@@ -808,7 +810,7 @@ impl Bit {
             broadcasters.push(BroadcasterInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -838,19 +840,19 @@ pub struct NetworkBoardInformation {
 
 impl NetworkBoardInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let information_id = bytes.get_u16();
-        let head = bytes.get_u8();
+        let information_id = bytes.try_get_u16()?;
+        let head = bytes.try_get_u8()?;
         let information_type = (head & 0xF0) >> 4;
         let description_body_location = (head & 0x0C) >> 2;
-        let user_defined = bytes.get_u8();
+        let user_defined = bytes.try_get_u8()?;
 
-        let number_of_keys = bytes.get_u8();
+        let number_of_keys = bytes.try_get_u8()?;
         let mut key_ids = Vec::with_capacity(number_of_keys as usize);
         for _ in 0..number_of_keys {
-            key_ids.push(bytes.get_u16());
+            key_ids.push(bytes.try_get_u16()?);
         }
 
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
 
         Ok(Self {
@@ -880,18 +882,18 @@ pub struct Nbit {
 
 impl Nbit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let original_network_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let original_network_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
         let mut information = Vec::new();
         while bytes.remaining() > 4 {
             information.push(NetworkBoardInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -915,9 +917,9 @@ pub struct LinkedDescription {
 
 impl LinkedDescription {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let description_id = bytes.get_u16();
-        _ = bytes.get_u8();
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let description_id = bytes.try_get_u16()?;
+        _ = bytes.try_get_u8()?;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
 
         Ok(Self {
@@ -945,20 +947,20 @@ pub struct Ldt {
 
 impl Ldt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let original_service_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let transport_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let original_service_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
 
         let mut descriptions = Vec::new();
         while bytes.remaining() > 4 {
             descriptions.push(LinkedDescription::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -984,8 +986,8 @@ pub struct LocalEventInformation {
 
 impl LocalEventInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let local_event_id = bytes.get_u16();
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let local_event_id = bytes.try_get_u16()?;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
 
         Ok(Self {
@@ -1014,21 +1016,21 @@ pub struct Lit {
 
 impl Lit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let event_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let service_id = bytes.get_u16();
-        let transport_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let event_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let service_id = bytes.try_get_u16()?;
+        let transport_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
 
         let mut local_events = Vec::new();
         while bytes.remaining() > 4 {
             local_events.push(LocalEventInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -1058,11 +1060,11 @@ pub struct EventRelationNode {
 
 impl EventRelationNode {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let node_id = bytes.get_u16();
-        let collection_mode = (bytes.get_u8() & 0xF0) >> 4;
-        let parent_node_id = bytes.get_u16();
-        let reference_number = bytes.get_u8();
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let node_id = bytes.try_get_u16()?;
+        let collection_mode = (bytes.try_get_u8()? & 0xF0) >> 4;
+        let parent_node_id = bytes.try_get_u16()?;
+        let reference_number = bytes.try_get_u8()?;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
 
         Ok(Self {
@@ -1093,20 +1095,20 @@ pub struct Ert {
 
 impl Ert {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let event_relation_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let information_provider_id = bytes.get_u16();
-        let relation_type = (bytes.get_u8() & 0xF0) >> 4;
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let event_relation_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let information_provider_id = bytes.try_get_u16()?;
+        let relation_type = (bytes.try_get_u8()? & 0xF0) >> 4;
 
         let mut nodes = Vec::new();
         while bytes.remaining() > 4 {
             nodes.push(EventRelationNode::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -1140,14 +1142,14 @@ pub struct Itt {
 
 impl Itt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let event_id = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let descriptors_loop_length = bytes.get_u16() & 0x0FFF;
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let event_id = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let descriptors_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let descriptors = read_descriptors(bytes, descriptors_loop_length as usize)?;
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -1182,10 +1184,10 @@ pub enum IpAddressInformation {
 impl IpAddressInformation {
     pub fn read(bytes: &mut Bytes, ip_version: bool) -> Result<Self> {
         Ok(if ip_version {
-            let src_addr = bytes.get_ipv6_addr();
-            let src_mask = bytes.get_u8();
-            let dst_addr = bytes.get_ipv6_addr();
-            let dst_mask = bytes.get_u8();
+            let src_addr = bytes.try_get_ipv6_addr()?;
+            let src_mask = bytes.try_get_u8()?;
+            let dst_addr = bytes.try_get_ipv6_addr()?;
+            let dst_mask = bytes.try_get_u8()?;
 
             Self::Ipv6 {
                 src_addr,
@@ -1194,10 +1196,10 @@ impl IpAddressInformation {
                 dst_mask,
             }
         } else {
-            let src_addr = bytes.get_ipv4_addr();
-            let src_mask = bytes.get_u8();
-            let dst_addr = bytes.get_ipv4_addr();
-            let dst_mask = bytes.get_u8();
+            let src_addr = bytes.try_get_ipv4_addr()?;
+            let src_mask = bytes.try_get_u8()?;
+            let dst_addr = bytes.try_get_ipv4_addr()?;
+            let dst_mask = bytes.try_get_u8()?;
 
             Self::Ipv4 {
                 src_addr,
@@ -1218,12 +1220,12 @@ pub struct AddressMapService {
 
 impl AddressMapService {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_id = bytes.get_u16();
-        let head = bytes.get_u16();
+        let service_id = bytes.try_get_u16()?;
+        let head = bytes.try_get_u16()?;
         let ip_version = ((head & 0x8000) >> 15) == 1;
         let service_loop_length = head & 0x03FF;
 
-        let mut service_bytes = bytes.split_to(service_loop_length as usize);
+        let mut service_bytes = bytes.try_split_to(service_loop_length as usize)?;
         let address = IpAddressInformation::read(&mut service_bytes, ip_version)?;
         let private_data = service_bytes.to_vec();
 
@@ -1251,20 +1253,20 @@ pub struct Amt {
 
 impl Amt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let table_id_extension = bytes.get_u16();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let table_id_extension = bytes.try_get_u16()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let num_of_service_id = (head & 0xFFC0) >> 6;
         let mut services = Vec::with_capacity(num_of_service_id as usize);
         for _ in 0..num_of_service_id {
             services.push(AddressMapService::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -1288,10 +1290,10 @@ pub struct IpMacPlatformInformation {
 
 impl IpMacPlatformInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let target_descriptor_loop_length = bytes.get_u16() & 0x0FFF;
+        let target_descriptor_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let target_descriptors = read_descriptors(bytes, target_descriptor_loop_length as usize)?;
 
-        let operational_descriptor_loop_length = bytes.get_u16() & 0x0FFF;
+        let operational_descriptor_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let operational_descriptors =
             read_descriptors(bytes, operational_descriptor_loop_length as usize)?;
 
@@ -1322,15 +1324,15 @@ pub struct Int {
 
 impl Int {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let (section_syntax_indicator, section_length) = read_section_header(bytes);
-        let action_type = bytes.get_u8();
-        let platform_id_hash = bytes.get_u8();
-        let (version_number, current_next_indicator) = read_version(bytes);
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let platform_id = bytes.get_uint(3) as u32;
-        let processing_order = bytes.get_u8();
-        let platform_descriptor_loop_length = bytes.get_u16() & 0x0FFF;
+        let (section_syntax_indicator, section_length) = read_section_header(bytes)?;
+        let action_type = bytes.try_get_u8()?;
+        let platform_id_hash = bytes.try_get_u8()?;
+        let (version_number, current_next_indicator) = read_version(bytes)?;
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let platform_id = bytes.try_get_uint(3)? as u32;
+        let processing_order = bytes.try_get_u8()?;
+        let platform_descriptor_loop_length = bytes.try_get_u16()? & 0x0FFF;
         let platform_descriptors =
             read_descriptors(bytes, platform_descriptor_loop_length as usize)?;
 
@@ -1339,7 +1341,7 @@ impl Int {
             platforms.push(IpMacPlatformInformation::read(bytes)?);
         }
 
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -1359,9 +1361,9 @@ impl Int {
     }
 }
 
-fn parse_jst_time(jst_time: [u8; 5]) -> Option<NaiveDateTime> {
+fn parse_jst_time(jst_time: [u8; 5]) -> Result<Option<NaiveDateTime>> {
     if jst_time == [0xFF, 0xFF, 0xFF, 0xFF, 0xFF] {
-        return None;
+        return Ok(None);
     }
 
     let mjd = u16::from_be_bytes([jst_time[0], jst_time[1]]);
@@ -1369,9 +1371,10 @@ fn parse_jst_time(jst_time: [u8; 5]) -> Option<NaiveDateTime> {
     let hour = parse_bcd(jst_time[2]) as u32;
     let minute = parse_bcd(jst_time[3]) as u32;
     let second = parse_bcd(jst_time[4]) as u32;
-    let time = NaiveTime::from_hms_opt(hour, minute, second).unwrap();
+    let time = NaiveTime::from_hms_opt(hour, minute, second)
+        .ok_or(Error::InvalidData("JST time is not a time of day"))?;
 
-    Some(NaiveDateTime::new(date, time))
+    Ok(Some(NaiveDateTime::new(date, time)))
 }
 
 fn parse_duration(duration: [u8; 3]) -> Option<Duration> {
@@ -1444,9 +1447,44 @@ pub enum Table {
     Unknown(u8, Vec<u8>),
 }
 
+/// Whether the sections of the table end with a CRC_32, which is checked before they are read.
+fn has_crc_32(table_id: u8) -> bool {
+    matches!(
+        table_id,
+        PAT_ID
+            | CAT_ID
+            | PMT_ID
+            | NIT_ACTUAL_ID
+            | NIT_OTHER_ID
+            | BAT_ID
+            | SDT_ACTUAL_ID
+            | SDT_OTHER_ID
+            | EIT_ACTUAL_PRESENT_FOLLOWING_ID
+            | EIT_OTHER_PRESENT_FOLLOWING_ID
+            | EIT_ACTUAL_SCHEDULE_ID_START..=EIT_ACTUAL_SCHEDULE_ID_END
+            | EIT_OTHER_SCHEDULE_ID_START..=EIT_OTHER_SCHEDULE_ID_END
+            | TOT_ID
+            | PCAT_ID
+            | BIT_ID
+            | NBIT_BODY_ID
+            | NBIT_REFERENCE_ID
+            | LDT_ID
+            | LIT_ID
+            | ERT_ID
+            | ITT_ID
+            | AMT_ID
+            | INT_ID
+    )
+}
+
 impl Table {
+    /// Reads a whole section, from its `table_id` to its last byte.
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let table_id = bytes.get_u8();
+        let table_id = *bytes.first().ok_or(Error::UnexpectedEof)?;
+        if has_crc_32(table_id) {
+            verify_crc_32(bytes)?;
+        }
+        bytes.advance(1);
 
         Ok(match table_id {
             PAT_ID => Self::Pat(Pat::read(bytes)?),
@@ -1482,17 +1520,27 @@ impl Table {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crc_32;
     use chrono::NaiveDate;
 
+    fn with_crc_32(section: &[u8]) -> Bytes {
+        let mut section = section.to_vec();
+        section.extend(crc_32(&section).to_be_bytes());
+
+        Bytes::from(section)
+    }
+
     #[test]
     fn test_parse_jst_time() {
         assert_eq!(
             parse_jst_time([0xC0, 0x79, 0x12, 0x45, 0x00]),
-            Some(NaiveDateTime::new(
+            Ok(Some(NaiveDateTime::new(
                 NaiveDate::from_ymd_opt(1993, 10, 13).unwrap(),
                 NaiveTime::from_hms_opt(12, 45, 0).unwrap()
-            )),
+            ))),
         );
+        assert_eq!(parse_jst_time([0xFF; 5]), Ok(None));
+        assert!(parse_jst_time([0xC0, 0x79, 0x25, 0x45, 0x00]).is_err());
     }
 
     #[test]
@@ -1503,4 +1551,40 @@ mod tests {
         assert_eq!(duration.num_minutes() % 60, 45);
         assert_eq!(duration.num_seconds() % 60, 30);
     }
+
+    #[test]
+    fn checks_the_crc_32_of_a_section_before_reading_it() {
+        let section = with_crc_32(&[
+            0x00, 0xB0, 0x11, // table_id, section_syntax_indicator, section_length
+            0x7F, 0xE1, 0xC1, 0x00, 0x00, // transport_stream_id, version, section numbers
+            0x00, 0x00, 0xE0, 0x10, // network PID
+            0x04, 0x08, 0xE1, 0xF0, // program_number, program_map_PID
+        ]);
+        let Table::Pat(pat) = Table::read(&mut section.clone()).unwrap() else {
+            panic!("section must be read as a PAT");
+        };
+        assert_eq!(pat.transport_stream_id, 0x7FE1);
+        assert_eq!(pat.programs.len(), 2);
+
+        let mut corrupted = section.to_vec();
+        corrupted[13] ^= 0x01;
+        let error = Table::read(&mut Bytes::from(corrupted)).unwrap_err();
+        assert!(error.is_crc_mismatch());
+    }
+
+    #[test]
+    fn rejects_a_truncated_section() {
+        // A TDT, which has no CRC_32, cut short in its JST_time.
+        let error =
+            Table::read(&mut Bytes::from_static(&[0x70, 0x70, 0x05, 0xC0, 0x79])).unwrap_err();
+        assert_eq!(error, Error::UnexpectedEof);
+
+        // A TOT whose descriptor loop runs past the end of the section.
+        let mut section = with_crc_32(&[
+            0x73, 0x70, 0x0F, // table_id, section_syntax_indicator, section_length
+            0xC0, 0x79, 0x12, 0x45, 0x00, // JST_time
+            0xF0, 0x08, // descriptors_loop_length
+        ]);
+        assert_eq!(Table::read(&mut section).unwrap_err(), Error::UnexpectedEof);
+    }
 }
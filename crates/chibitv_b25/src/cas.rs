@@ -1,7 +1,7 @@
 //! APDU commands and responses implemented on an ARIB STD-B25 CAS module.
 
 use std::fmt::{Debug, Formatter};
-use std::io::{Cursor, ErrorKind, Read, Result};
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
 use std::sync::Arc;
 
 use apdu_core::{Command, Response};
@@ -144,10 +144,64 @@ impl EcmReceptionResponse {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct EmmReceptionCommand {
+    pub emm: Vec<u8>,
+    pub acas: bool,
+}
+
+impl EmmReceptionCommand {
+    fn write(&self, buf: &mut [u8]) -> usize {
+        let p2 = if self.acas { 0x02 } else { 0x00 };
+        let cmd = Command::new_with_payload_le(0x90, 0x36, 0x00, p2, 0x00, &self.emm);
+        cmd.write(buf);
+        cmd.len()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EmmReceptionResponse {
+    pub unit_length: u8,
+    pub card_instruction: u16,
+    pub return_code: u16,
+}
+
+impl EmmReceptionResponse {
+    fn read(buf: &[u8]) -> Result<Self> {
+        let response = Response::from(buf);
+        if !response.is_ok() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "CAS module did not complete the EMM reception",
+            ));
+        }
+
+        let mut reader = Cursor::new(response.payload);
+
+        let protocol_unit_number = reader.read_u8()?;
+        if protocol_unit_number != 0x00 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "EMM reception response is of an unknown protocol unit",
+            ));
+        }
+
+        Ok(Self {
+            unit_length: reader.read_u8()?,
+            card_instruction: reader.read_u16::<BE>()?,
+            return_code: reader.read_u16::<BE>()?,
+        })
+    }
+}
+
 /// ARIB STD-B25 commands executed on a physical CAS module.
 pub(crate) struct CasClient {
     module: Arc<dyn CasModule>,
     acas: bool,
+    /// The generation of the module the initial setting was last made on.
+    generation: u64,
+    /// The id of the card the initial setting was last made on.
+    card_id: [u8; 6],
     tx_buf: Vec<u8>,
     rx_buf: Vec<u8>,
 }
@@ -161,24 +215,37 @@ impl Debug for CasClient {
 impl CasClient {
     pub fn new(module: Arc<dyn CasModule>, acas: bool) -> Self {
         Self {
+            generation: module.generation(),
             module,
             acas,
+            card_id: [0; 6],
             tx_buf: vec![0u8; 2048],
             rx_buf: vec![0u8; 4096],
         }
     }
 
+    /// Whether the module has been reconnected since the initial setting was made.
+    pub fn is_reconnected(&self) -> bool {
+        self.module.generation() != self.generation
+    }
+
     pub fn initial_setting_condition(&mut self) -> anyhow::Result<InitialSettingConditionResponse> {
+        self.generation = self.module.generation();
         let cmd = InitialSettingConditionCommand { acas: self.acas };
         let len = cmd.write(&mut self.tx_buf);
         let response_len = self
             .module
             .transmit(&self.tx_buf[..len], &mut self.rx_buf)?;
         let response = InitialSettingConditionResponse::read(&self.rx_buf[..response_len])?;
+        self.card_id = response.card_id;
 
         Ok(response)
     }
 
+    pub fn card_id(&self) -> [u8; 6] {
+        self.card_id
+    }
+
     pub fn ecm_reception(&mut self, ecm: &[u8]) -> anyhow::Result<EcmReceptionResponse> {
         let cmd = EcmReceptionCommand {
             ecm: ecm.to_vec(),
@@ -192,4 +259,37 @@ impl CasClient {
 
         Ok(response)
     }
+
+    pub fn emm_reception(&mut self, emm: &[u8]) -> anyhow::Result<EmmReceptionResponse> {
+        let cmd = EmmReceptionCommand {
+            emm: emm.to_vec(),
+            acas: self.acas,
+        };
+        let len = cmd.write(&mut self.tx_buf);
+        let response_len = self
+            .module
+            .transmit(&self.tx_buf[..len], &mut self.rx_buf)?;
+        let response = EmmReceptionResponse::read(&self.rx_buf[..response_len])?;
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fails_on_an_emm_the_module_did_not_complete() {
+        let response = [0x00, 0x04, 0x00, 0x36, 0x21, 0x00, 0x90, 0x00];
+        assert_eq!(
+            EmmReceptionResponse::read(&response).unwrap().return_code,
+            0x2100
+        );
+
+        // A status word other than 90 00, and a response of another protocol unit.
+        assert!(EmmReceptionResponse::read(&[0x6A, 0x86]).is_err());
+        let response = [0x01, 0x04, 0x00, 0x36, 0x21, 0x00, 0x90, 0x00];
+        assert!(EmmReceptionResponse::read(&response).is_err());
+    }
 }
@@ -2,12 +2,15 @@ use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::{Arc, Mutex};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
+use chibitv_cas::{EmmHistory, KeyCache};
 use mpeg2ts::ts::payload::Bytes;
 use mpeg2ts::ts::{TransportScramblingControl, TsPacket, TsPayload};
+use tracing::{debug, info, warn};
 
 use crate::CasModule;
 use crate::cas::CasClient;
+use crate::key_log::{DecryptionKey, KeyLog, KeyStore, SystemSettings};
 use crate::multi2::Multi2;
 
 #[derive(Copy, Clone, Debug)]
@@ -21,10 +24,19 @@ impl Display for NoDecryptionKeyError {
 
 impl Error for NoDecryptionKeyError {}
 
+/// Where a descrambler is given the keys of the ECMs.
+enum KeySource {
+    Card(Mutex<CasClient>),
+    Store(Arc<dyn KeyStore>),
+}
+
 pub struct B25Descrambler {
-    cas: Mutex<CasClient>,
+    source: KeySource,
+    key_log: Option<Arc<dyn KeyLog>>,
+    key_cache: &'static KeyCache<[u8; 16]>,
     multi2: Mutex<Multi2>,
     ca_system_id: u16,
+    emm_history: EmmHistory,
 }
 
 impl Debug for B25Descrambler {
@@ -34,31 +46,159 @@ impl Debug for B25Descrambler {
 }
 
 impl B25Descrambler {
-    pub fn init(module: Arc<dyn CasModule>) -> Result<Self> {
+    /// Initializes the card of the module, writing everything the card gives to the key log and
+    /// sharing the scramble keys with the other descramblers of the key cache.
+    pub fn init(
+        module: Arc<dyn CasModule>,
+        key_log: Option<Arc<dyn KeyLog>>,
+        key_cache: &'static KeyCache<[u8; 16]>,
+    ) -> Result<Self> {
         let mut cas = CasClient::new(module, true);
-        let settings = cas.initial_setting_condition()?;
+        let response = cas.initial_setting_condition()?;
+        let settings = SystemSettings {
+            ca_system_id: response.ca_system_id,
+            system_key: response.system_key,
+            init_cbc: response.init_cbc,
+        };
+        if let Some(key_log) = &key_log {
+            key_log.log_system_settings(&settings);
+        }
+
+        Ok(Self::new(
+            KeySource::Card(Mutex::new(cas)),
+            key_log,
+            key_cache,
+            settings,
+        ))
+    }
 
-        Ok(Self {
-            cas: Mutex::new(cas),
+    /// Descrambles with the keys of the store instead of asking a card for them.
+    pub fn init_keyless(
+        store: Arc<dyn KeyStore>,
+        key_cache: &'static KeyCache<[u8; 16]>,
+    ) -> Result<Self> {
+        let Some(settings) = store.system_settings() else {
+            bail!("No system settings of a B-CAS card are stored");
+        };
+
+        Ok(Self::new(
+            KeySource::Store(store),
+            None,
+            key_cache,
+            settings,
+        ))
+    }
+
+    fn new(
+        source: KeySource,
+        key_log: Option<Arc<dyn KeyLog>>,
+        key_cache: &'static KeyCache<[u8; 16]>,
+        settings: SystemSettings,
+    ) -> Self {
+        Self {
+            source,
+            key_log,
+            key_cache,
             multi2: Mutex::new(Multi2::new(settings.system_key, settings.init_cbc)),
             ca_system_id: settings.ca_system_id,
-        })
+            emm_history: EmmHistory::default(),
+        }
     }
 
     pub fn ca_system_id(&self) -> u16 {
         self.ca_system_id
     }
 
+    /// Pushes an ECM, setting the key it carries.
+    ///
+    /// A key another descrambler of the key cache has been given for the same ECM is used without
+    /// asking the card. Otherwise the initial setting is made again whenever the module has been
+    /// reconnected to its card, and an ECM the reconnection interrupted is sent once more.
     pub fn push_ecm(&mut self, ecm: &[u8]) -> Result<()> {
-        let response = self.cas.lock().unwrap().ecm_reception(ecm)?;
-        let mut key = [0u8; 16];
-        key[..8].copy_from_slice(&response.odd);
-        key[8..].copy_from_slice(&response.even);
+        let key = match self.key_cache.get(ecm) {
+            Some(key) => key,
+            None => {
+                let key = match &self.source {
+                    KeySource::Card(cas) => {
+                        let key = self.receive_ecm(cas, ecm)?;
+                        if let Some(key_log) = &self.key_log {
+                            key_log.log_key(ecm, &key);
+                        }
+                        key
+                    }
+                    KeySource::Store(store) => store
+                        .key(ecm)
+                        .ok_or_else(|| anyhow!("No key of the ECM is stored"))?,
+                }
+                .to_scramble_key();
+                self.key_cache.insert(ecm, key);
+                key
+            }
+        };
 
         self.multi2.lock().unwrap().set_scramble_key(key);
         Ok(())
     }
 
+    fn receive_ecm(&self, cas: &Mutex<CasClient>, ecm: &[u8]) -> Result<DecryptionKey> {
+        let mut cas = cas.lock().unwrap();
+        if cas.is_reconnected() {
+            self.reinitialize(&mut cas)?;
+        }
+        let response = match cas.ecm_reception(ecm) {
+            Err(error) if cas.is_reconnected() => {
+                warn!(%error, "ECM was interrupted by the CAS module reconnecting");
+                self.reinitialize(&mut cas)?;
+                cas.ecm_reception(ecm)?
+            }
+            result => result?,
+        };
+
+        Ok(DecryptionKey {
+            odd: response.odd,
+            even: response.even,
+        })
+    }
+
+    /// Pushes the payload of an EMM section, giving the card the EMMs addressed to it that it
+    /// has not been given yet.
+    pub fn push_emm(&mut self, payload: &[u8]) -> Result<()> {
+        // A keyless descrambler has no card to give them to.
+        let KeySource::Card(cas) = &self.source else {
+            return Ok(());
+        };
+
+        let mut cas = cas.lock().unwrap();
+        if cas.is_reconnected() {
+            self.reinitialize(&mut cas)?;
+        }
+
+        for emm in chibitv_cas::read_emms(payload) {
+            if emm.card_id != cas.card_id() || !self.emm_history.is_new(&emm) {
+                continue;
+            }
+
+            let response = cas.emm_reception(emm.data)?;
+            debug!(
+                broadcaster_group_id = emm.broadcaster_group_id,
+                update_number = emm.update_number,
+                return_code = response.return_code,
+                "EMM was received by the CAS module"
+            );
+            self.emm_history.record(&emm);
+        }
+
+        Ok(())
+    }
+
+    fn reinitialize(&self, cas: &mut CasClient) -> Result<()> {
+        let settings = cas.initial_setting_condition()?;
+        info!("CAS module was reconnected and initialized again");
+        *self.multi2.lock().unwrap() = Multi2::new(settings.system_key, settings.init_cbc);
+
+        Ok(())
+    }
+
     pub fn descramble(&mut self, packet: &mut TsPacket) -> Result<()> {
         let scrambling_control = packet.header.transport_scrambling_control;
         if scrambling_control == TransportScramblingControl::NotScrambled {
@@ -102,8 +242,13 @@ impl B25Descrambler {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
     use super::*;
 
+    /// Shared by the tests as the key cache of the process is by its descramblers.
+    static KEY_CACHE: KeyCache<[u8; 16]> = KeyCache::new();
+
     struct FakeCasModule;
 
     impl CasModule for FakeCasModule {
@@ -126,8 +271,169 @@ mod tests {
 
     #[test]
     fn initializes_with_a_caller_supplied_cas_module() {
-        let descrambler = B25Descrambler::init(Arc::new(FakeCasModule)).unwrap();
+        let descrambler = B25Descrambler::init(Arc::new(FakeCasModule), None, &KEY_CACHE).unwrap();
 
         assert_eq!(descrambler.ca_system_id(), 0x1234);
     }
+
+    /// Reconnects to its card on the ECM following `reset`, failing that ECM.
+    #[derive(Default)]
+    struct ResettingCasModule {
+        generation: AtomicU64,
+        initial_settings: AtomicUsize,
+        reset: AtomicBool,
+    }
+
+    impl CasModule for ResettingCasModule {
+        fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            if command[1] == 0x30 {
+                self.initial_settings.fetch_add(1, Ordering::Relaxed);
+                return FakeCasModule.transmit(command, response);
+            }
+
+            assert_eq!(command[1], 0x34);
+            if self.reset.swap(false, Ordering::Relaxed) {
+                self.generation.fetch_add(1, Ordering::Relaxed);
+                anyhow::bail!("The card was reset");
+            }
+
+            let card_response = [
+                &[0x00, 0x15, 0x00, 0x34, 0x08, 0x00][..],
+                &[0x00; 17],
+                &[0x90, 0x00],
+            ]
+            .concat();
+            response[..card_response.len()].copy_from_slice(&card_response);
+            Ok(card_response.len())
+        }
+
+        fn generation(&self) -> u64 {
+            self.generation.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn initializes_again_once_the_module_reconnects() {
+        let module = Arc::new(ResettingCasModule::default());
+        let mut descrambler = B25Descrambler::init(module.clone(), None, &KEY_CACHE).unwrap();
+        let initial_settings = || module.initial_settings.load(Ordering::Relaxed);
+
+        // The ECMs differ from those of the other tests, which share the key cache of the process.
+        descrambler.push_ecm(&[0xA1; 8]).unwrap();
+        assert_eq!(initial_settings(), 1);
+
+        // The ECM the reset interrupts is sent again once the card is set up.
+        module.reset.store(true, Ordering::Relaxed);
+        descrambler.push_ecm(&[0xA2; 8]).unwrap();
+        assert_eq!(initial_settings(), 2);
+
+        // A reconnection between ECMs is caught up on before the next one.
+        module.generation.fetch_add(1, Ordering::Relaxed);
+        descrambler.push_ecm(&[0xA3; 8]).unwrap();
+        assert_eq!(initial_settings(), 3);
+
+        // A key the card has already given is not asked for again.
+        module.generation.fetch_add(1, Ordering::Relaxed);
+        descrambler.push_ecm(&[0xA3; 8]).unwrap();
+        assert_eq!(initial_settings(), 3);
+    }
+
+    /// Counts the EMMs it is given, after making the initial setting as [`FakeCasModule`].
+    #[derive(Default)]
+    struct EmmCountingCasModule {
+        emms: AtomicUsize,
+    }
+
+    impl CasModule for EmmCountingCasModule {
+        fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize> {
+            if command[1] == 0x30 {
+                return FakeCasModule.transmit(command, response);
+            }
+
+            assert_eq!(&command[..4], &[0x90, 0x36, 0x00, 0x02]);
+            self.emms.fetch_add(1, Ordering::Relaxed);
+
+            let card_response = [0x00, 0x04, 0x00, 0x36, 0x21, 0x00, 0x90, 0x00];
+            response[..card_response.len()].copy_from_slice(&card_response);
+            Ok(card_response.len())
+        }
+    }
+
+    #[test]
+    fn gives_the_card_the_new_emms_addressed_to_it() {
+        let module = Arc::new(EmmCountingCasModule::default());
+        let mut descrambler = B25Descrambler::init(module.clone(), None, &KEY_CACHE).unwrap();
+        let emm = |card_id: [u8; 6], update_number: u8| {
+            [
+                &card_id[..],
+                &[0x06, 0x10, 0x20, 0x00, update_number, 0x12, 0x34],
+            ]
+            .concat()
+        };
+
+        let section = [emm([0x00; 6], 1), emm([0x01; 6], 1)].concat();
+        descrambler.push_emm(&section).unwrap();
+        assert_eq!(module.emms.load(Ordering::Relaxed), 1);
+
+        // The broadcast repeats the EMM until its card is updated again.
+        descrambler.push_emm(&section).unwrap();
+        assert_eq!(module.emms.load(Ordering::Relaxed), 1);
+
+        descrambler.push_emm(&emm([0x00; 6], 2)).unwrap();
+        assert_eq!(module.emms.load(Ordering::Relaxed), 2);
+    }
+
+    /// Keeps what it is given, and gives it back as a store.
+    #[derive(Default)]
+    struct MemoryKeyLog {
+        settings: Mutex<Option<SystemSettings>>,
+        keys: Mutex<Vec<(Vec<u8>, DecryptionKey)>>,
+    }
+
+    impl KeyLog for MemoryKeyLog {
+        fn log_system_settings(&self, settings: &SystemSettings) {
+            *self.settings.lock().unwrap() = Some(*settings);
+        }
+
+        fn log_key(&self, ecm: &[u8], key: &DecryptionKey) {
+            self.keys.lock().unwrap().push((ecm.to_vec(), *key));
+        }
+    }
+
+    impl KeyStore for MemoryKeyLog {
+        fn system_settings(&self) -> Option<SystemSettings> {
+            *self.settings.lock().unwrap()
+        }
+
+        fn key(&self, ecm: &[u8]) -> Option<DecryptionKey> {
+            let keys = self.keys.lock().unwrap();
+            keys.iter().find(|(e, _)| e == ecm).map(|(_, key)| *key)
+        }
+    }
+
+    #[test]
+    fn descrambles_with_the_keys_of_the_log_without_a_card() {
+        let key_log = Arc::new(MemoryKeyLog::default());
+        let mut descrambler = B25Descrambler::init(
+            Arc::new(ResettingCasModule::default()),
+            Some(key_log.clone()),
+            &KEY_CACHE,
+        )
+        .unwrap();
+        descrambler.push_ecm(&[0xB1; 8]).unwrap();
+        assert_eq!(key_log.keys.lock().unwrap().len(), 1);
+
+        // A key the card never gave, which the key cache cannot answer either.
+        let key = DecryptionKey {
+            odd: [0x01; 8],
+            even: [0x02; 8],
+        };
+        key_log.log_key(&[0xB2; 8], &key);
+
+        let mut keyless = B25Descrambler::init_keyless(key_log, &KEY_CACHE).unwrap();
+        assert_eq!(keyless.ca_system_id(), 0x1234);
+        assert!(keyless.push_ecm(&[0xB1; 8]).is_ok());
+        assert!(keyless.push_ecm(&[0xB2; 8]).is_ok());
+        assert!(keyless.push_ecm(&[0xB3; 8]).is_err());
+    }
 }
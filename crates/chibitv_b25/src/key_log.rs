@@ -0,0 +1,40 @@
+//! Exporting the keys a descrambler is given by the card, and descrambling with them later
+//! without one.
+
+/// What the initial setting of a card tells a descrambler besides the keys of the ECMs.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SystemSettings {
+    pub ca_system_id: u16,
+    pub system_key: [u8; 32],
+    pub init_cbc: [u8; 8],
+}
+
+/// The odd and even scramble keys an ECM carries.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DecryptionKey {
+    pub odd: [u8; 8],
+    pub even: [u8; 8],
+}
+
+impl DecryptionKey {
+    pub(crate) fn to_scramble_key(self) -> [u8; 16] {
+        let mut scramble_key = [0u8; 16];
+        scramble_key[..8].copy_from_slice(&self.odd);
+        scramble_key[8..].copy_from_slice(&self.even);
+        scramble_key
+    }
+}
+
+/// Receives everything a descrambler is given by the card, in the spirit of `SSLKEYLOGFILE`.
+pub trait KeyLog: Send + Sync {
+    fn log_system_settings(&self, settings: &SystemSettings);
+
+    fn log_key(&self, ecm: &[u8], key: &DecryptionKey);
+}
+
+/// Gives a keyless descrambler what a [`KeyLog`] has received, in place of the card.
+pub trait KeyStore: Send + Sync {
+    fn system_settings(&self) -> Option<SystemSettings>;
+
+    fn key(&self, ecm: &[u8]) -> Option<DecryptionKey>;
+}
@@ -2,12 +2,20 @@
 
 mod cas;
 mod descrambler;
+mod key_log;
 mod multi2;
 
-pub use cas::{EcmReceptionResponse, InitialSettingConditionResponse};
+pub use cas::{EcmReceptionResponse, EmmReceptionResponse, InitialSettingConditionResponse};
 pub use descrambler::{B25Descrambler, NoDecryptionKeyError};
+pub use key_log::{DecryptionKey, KeyLog, KeyStore, SystemSettings};
 
 /// A physical CAS module capable of executing ARIB STD-B25 commands.
 pub trait CasModule: Send + Sync {
     fn transmit(&self, command: &[u8], response: &mut [u8]) -> anyhow::Result<usize>;
+
+    /// Counts the times the module has been reconnected to its card, which forgets the initial
+    /// setting. Modules that never reconnect stay at zero.
+    fn generation(&self) -> u64 {
+        0
+    }
 }
@@ -1,5 +1,5 @@
-use std::io::Result;
-use std::net::Ipv6Addr;
+use std::io::{ErrorKind, Result};
+use std::net::{Ipv4Addr, Ipv6Addr};
 
 use bytes::{Buf, Bytes};
 use strum::FromRepr;
@@ -15,6 +15,61 @@ pub enum HcfbHeaderType {
     NoCompressedHeader = 0x61,
 }
 
+#[derive(Clone, Debug)]
+pub struct PartialIpv4UdpHeader {
+    pub type_of_service: u8,
+    pub identification: u16,
+    pub flags: u8,
+    pub fragment_offset: u16,
+    pub time_to_live: u8,
+    pub protocol: u8,
+    pub source_address: Ipv4Addr,
+    pub destination_address: Ipv4Addr,
+    pub source_port: u16,
+    pub destination_port: u16,
+}
+
+impl PartialIpv4UdpHeader {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        // IPv4 (without total length and header checksum)
+        let head = bytes.try_get_u8()?;
+        let version = (head & 0xF0) >> 4;
+        let header_length = usize::from(head & 0x0F) * 4;
+        if version != 4 || header_length < 20 {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        let type_of_service = bytes.try_get_u8()?;
+        let identification = bytes.try_get_u16()?;
+        let head = bytes.try_get_u16()?;
+        let flags = ((head & 0xE000) >> 13) as u8;
+        let fragment_offset = head & 0x1FFF;
+        let time_to_live = bytes.try_get_u8()?;
+        let protocol = bytes.try_get_u8()?;
+        let source_address = bytes.try_get_ipv4_addr()?;
+        let destination_address = bytes.try_get_ipv4_addr()?;
+        // Options
+        bytes.try_split_to(header_length - 20)?;
+
+        // UDP (without payload length and checksum)
+        let source_port = bytes.try_get_u16()?;
+        let destination_port = bytes.try_get_u16()?;
+
+        Ok(Self {
+            type_of_service,
+            identification,
+            flags,
+            fragment_offset,
+            time_to_live,
+            protocol,
+            source_address,
+            destination_address,
+            source_port,
+            destination_port,
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PartialIpv6UdpHeader {
     pub traffic_class: u8,
@@ -30,20 +85,22 @@ pub struct PartialIpv6UdpHeader {
 impl PartialIpv6UdpHeader {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
         // IPv6 (without payload length)
-        let head = bytes.get_u32();
+        let head = bytes.try_get_u32()?;
         let version = ((head & 0xF000_0000) >> 28) as u8;
         let traffic_class = ((head & 0x0FF0_0000) >> 20) as u8;
         let flow_label = head & 0x000F_FFFF;
-        assert_eq!(version, 6);
+        if version != 6 {
+            return Err(ErrorKind::InvalidData.into());
+        }
 
-        let next_header = bytes.get_u8();
-        let hop_limit = bytes.get_u8();
-        let source_address = bytes.get_ipv6_addr();
-        let destination_address = bytes.get_ipv6_addr();
+        let next_header = bytes.try_get_u8()?;
+        let hop_limit = bytes.try_get_u8()?;
+        let source_address = bytes.try_get_ipv6_addr()?;
+        let destination_address = bytes.try_get_ipv6_addr()?;
 
         // UDP (without payload length and checksum)
-        let source_port = bytes.get_u16();
-        let destination_port = bytes.get_u16();
+        let source_port = bytes.try_get_u16()?;
+        let destination_port = bytes.try_get_u16()?;
 
         Ok(Self {
             traffic_class,
@@ -58,9 +115,15 @@ impl PartialIpv6UdpHeader {
     }
 }
 
+/// The header of a packet compressed against the context of its `context_id`, which only the
+/// first packets of a context carry in part.
 #[derive(Clone, Debug)]
 pub enum HcfbHeader {
-    // TODO
+    PartialIpv4UdpHeader(PartialIpv4UdpHeader),
+    /// The identification of the IPv4 header, the rest being that of the context.
+    Ipv4HeaderIdentifier {
+        identification: u16,
+    },
     PartialIpv6UdpHeader(PartialIpv6UdpHeader),
     NoCompressedHeader,
 }
@@ -74,17 +137,23 @@ pub struct HcfbPacket {
 
 impl HcfbPacket {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let context_id = (head & 0xFFF0) >> 4;
         let sequence_number = (head & 0x000F) as u8;
-        let header_type = HcfbHeaderType::from_repr(bytes.get_u8()).unwrap();
+        let header_type =
+            HcfbHeaderType::from_repr(bytes.try_get_u8()?).ok_or(ErrorKind::InvalidData)?;
 
         let header = match header_type {
+            HcfbHeaderType::PartialIpv4UdpHeader => {
+                HcfbHeader::PartialIpv4UdpHeader(PartialIpv4UdpHeader::read(bytes)?)
+            }
+            HcfbHeaderType::Ipv4HeaderIdentifier => HcfbHeader::Ipv4HeaderIdentifier {
+                identification: bytes.try_get_u16()?,
+            },
             HcfbHeaderType::PartialIpv6UdpHeader => {
                 HcfbHeader::PartialIpv6UdpHeader(PartialIpv6UdpHeader::read(bytes)?)
             }
             HcfbHeaderType::NoCompressedHeader => HcfbHeader::NoCompressedHeader,
-            _ => unimplemented!("Sorry, not implemented yet!"),
         };
 
         Ok(Self {
@@ -94,3 +163,44 @@ impl HcfbPacket {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_ipv4_forms() {
+        let mut bytes = Bytes::from_static(&[
+            0x00, 0x13, // context_id, sequence_number
+            0x20, // header_type
+            0x45, 0x00, 0x12, 0x34, 0x40, 0x00, 0x40, 0x11, // IPv4 header
+            0xC0, 0xA8, 0x00, 0x01, 0xEF, 0x00, 0x00, 0x01, // source and destination address
+            0x04, 0xD2, 0x16, 0x2E, // source and destination port
+            0xAA, // MMTP packet
+        ]);
+        let packet = HcfbPacket::read(&mut bytes).unwrap();
+        assert_eq!(packet.context_id, 0x001);
+        assert_eq!(packet.sequence_number, 3);
+        let HcfbHeader::PartialIpv4UdpHeader(header) = packet.header else {
+            panic!("header must be a partial IPv4 header");
+        };
+        assert_eq!(header.identification, 0x1234);
+        assert_eq!(header.flags, 0b010);
+        assert_eq!(header.protocol, 17);
+        assert_eq!(header.source_address, Ipv4Addr::new(192, 168, 0, 1));
+        assert_eq!(header.destination_address, Ipv4Addr::new(239, 0, 0, 1));
+        assert_eq!(header.destination_port, 5678);
+        assert_eq!(bytes.as_ref(), &[0xAA]);
+
+        let mut bytes = Bytes::from_static(&[0x00, 0x14, 0x21, 0x12, 0x35]);
+        assert!(matches!(
+            HcfbPacket::read(&mut bytes).unwrap().header,
+            HcfbHeader::Ipv4HeaderIdentifier {
+                identification: 0x1235
+            }
+        ));
+
+        let mut bytes = Bytes::from_static(&[0x00, 0x13, 0x20, 0x45, 0x00, 0x12]);
+        assert!(HcfbPacket::read(&mut bytes).is_err());
+    }
+}
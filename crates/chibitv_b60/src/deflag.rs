@@ -29,7 +29,7 @@ impl Defragmenter {
                 self.state = State::Skip;
                 self.last_sequence_number = sequence_number;
             }
-            _ if sequence_number == self.last_sequence_number + 1 => {
+            _ if sequence_number == self.last_sequence_number.wrapping_add(1) => {
                 self.last_sequence_number = sequence_number;
             }
             _ if sequence_number != self.last_sequence_number => {
@@ -53,6 +53,9 @@ impl Defragmenter {
 
     /// Push a fragment and try assembling fragments into a buffer.
     /// Returns a completed buffer if the current fragment completed the buffer.
+    ///
+    /// Fragments out of order, which a corrupted input sends without a sequence number jump, drop
+    /// the fragment they break instead of failing.
     pub fn push(
         &mut self,
         fragmentation_indicator: FragmentationIndicator,
@@ -60,8 +63,8 @@ impl Defragmenter {
     ) -> Option<Vec<u8>> {
         match fragmentation_indicator {
             FragmentationIndicator::NotFragmented => {
-                // Non-fragment packet can't be accepted while in the middle of a fragment.
-                assert_ne!(self.state, State::InFragment);
+                // Non-fragment packet ends the fragment it comes in the middle of.
+                self.drop_unfinished();
 
                 self.state = State::NotStarted;
 
@@ -69,8 +72,8 @@ impl Defragmenter {
                 Some(buf.to_vec())
             }
             FragmentationIndicator::FragmentHead => {
-                // Head packet can't be accepted while in the middle of a fragment.
-                assert_ne!(self.state, State::InFragment);
+                // Head packet starts over the fragment it comes in the middle of.
+                self.drop_unfinished();
 
                 // Copies the buf.
                 self.state = State::InFragment;
@@ -80,13 +83,11 @@ impl Defragmenter {
                 None
             }
             FragmentationIndicator::FragmentBody => {
-                if self.state == State::Skip {
-                    // We can do nothing on a skipped fragment.
+                if self.state != State::InFragment {
+                    // We can do nothing on a skipped fragment, nor on one without its head.
                     warn!("Packet dropped!");
+                    self.state = State::Skip;
                 } else {
-                    // It must be in the middle of a fragment.
-                    assert_eq!(self.state, State::InFragment);
-
                     // Copies the buf.
                     self.buf.extend_from_slice(buf);
                 }
@@ -95,15 +96,13 @@ impl Defragmenter {
                 None
             }
             FragmentationIndicator::FragmentTail => {
-                if self.state == State::Skip {
+                if self.state != State::InFragment {
                     warn!("Packet dropped!");
+                    self.state = State::Skip;
 
                     // Not yet completed.
                     None
                 } else {
-                    // It must be in the middle of a fragment.
-                    assert_eq!(self.state, State::InFragment);
-
                     // Copies the buf.
                     self.state = State::NotStarted;
                     self.buf.extend_from_slice(buf);
@@ -114,4 +113,70 @@ impl Defragmenter {
             }
         }
     }
+
+    fn drop_unfinished(&mut self) {
+        if self.state == State::InFragment {
+            warn!("Drop {} octets of an unfinished fragment.", self.buf.len());
+        }
+        self.buf.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_fragments_out_of_order() {
+        let mut defragmenter = Defragmenter::default();
+        defragmenter.sync(0);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentBody, &[0x01]),
+            None
+        );
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::NotFragmented, &[0x02]),
+            Some(vec![0x02])
+        );
+
+        // A body without its head.
+        defragmenter.sync(1);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentTail, &[0x03]),
+            None
+        );
+
+        // A head in the middle of a fragment starts it over.
+        defragmenter.sync(2);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentHead, &[0x04]),
+            None
+        );
+        defragmenter.sync(3);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentHead, &[0x05]),
+            None
+        );
+        defragmenter.sync(4);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentTail, &[0x06]),
+            Some(vec![0x05, 0x06])
+        );
+    }
+
+    #[test]
+    fn keeps_a_fragment_across_the_wraparound_of_the_sequence_number() {
+        let mut defragmenter = Defragmenter::default();
+        defragmenter.sync(u32::MAX - 1);
+        defragmenter.sync(u32::MAX);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentHead, &[0x01]),
+            None
+        );
+        defragmenter.sync(0);
+        assert_eq!(
+            defragmenter.push(FragmentationIndicator::FragmentTail, &[0x02]),
+            Some(vec![0x01, 0x02])
+        );
+    }
 }
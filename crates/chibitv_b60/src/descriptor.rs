@@ -1,6 +1,7 @@
-use std::io::Result;
+use std::io::{ErrorKind, Result};
 
 use crate::read_ext::BytesExt;
+use crate::table::MmtGeneralLocation;
 use bytes::{Buf, Bytes};
 use strum::FromRepr;
 
@@ -12,10 +13,8 @@ pub struct MpuTimestamp {
 
 impl MpuTimestamp {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        assert!(bytes.remaining() >= 12);
-
-        let mpu_sequence_number = bytes.get_u32();
-        let mpu_presentation_time = bytes.get_u64();
+        let mpu_sequence_number = bytes.try_get_u32()?;
+        let mpu_presentation_time = bytes.try_get_u64()?;
 
         Ok(Self {
             mpu_sequence_number,
@@ -52,11 +51,11 @@ impl MpuTimestampOffset {
         pts_offset_type: u8,
         default_pts_offset: Option<u16>,
     ) -> Result<Self> {
-        let pts_dts_offset = bytes.get_u16();
-        let pts_offset = (pts_offset_type == 2)
-            .then(|| bytes.get_u16())
-            .or(default_pts_offset)
-            .unwrap();
+        let pts_dts_offset = bytes.try_get_u16()?;
+        let pts_offset = match pts_offset_type {
+            2 => bytes.try_get_u16()?,
+            _ => default_pts_offset.ok_or(ErrorKind::InvalidData)?,
+        };
 
         Ok(Self {
             pts_dts_offset,
@@ -80,10 +79,10 @@ impl MpuExtendedTimestamp {
         pts_offset_type: u8,
         default_pts_offset: Option<u16>,
     ) -> Result<Self> {
-        let mpu_sequence_number = bytes.get_u32();
-        let mpu_presentation_time_leap_indicator = (bytes.get_u8() & 0b1100_0000) >> 6;
-        let mpu_decoding_time_offset = bytes.get_u16();
-        let num_of_au = bytes.get_u8();
+        let mpu_sequence_number = bytes.try_get_u32()?;
+        let mpu_presentation_time_leap_indicator = (bytes.try_get_u8()? & 0b1100_0000) >> 6;
+        let mpu_decoding_time_offset = bytes.try_get_u16()?;
+        let num_of_au = bytes.try_get_u8()?;
 
         let mut offsets = Vec::with_capacity(num_of_au as usize);
         for _ in 0..num_of_au {
@@ -113,12 +112,14 @@ pub struct MpuExtendedTimestampDescriptor {
 
 impl MpuExtendedTimestampDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let pts_offset_type = (head & 0b0000_0110) >> 1;
         let timescale_flag = (head & 0b0000_0001) == 1;
 
-        let timescale = timescale_flag.then(|| bytes.get_u32());
-        let default_pts_offset = (pts_offset_type == 1).then(|| bytes.get_u16());
+        let timescale = timescale_flag.then(|| bytes.try_get_u32()).transpose()?;
+        let default_pts_offset = (pts_offset_type == 1)
+            .then(|| bytes.try_get_u16())
+            .transpose()?;
 
         let mut timestamps = Vec::new();
         while bytes.has_remaining() {
@@ -146,13 +147,13 @@ pub struct MhShortEventDescriptor {
 
 impl MhShortEventDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let iso_639_language_code = bytes.get_byte_array::<3>();
+        let iso_639_language_code = bytes.try_get_byte_array::<3>()?;
 
-        let event_name_length = bytes.get_u8();
-        let event_name = bytes.split_to(event_name_length as usize).into();
+        let event_name_length = bytes.try_get_u8()?;
+        let event_name = bytes.try_split_to(event_name_length as usize)?.into();
 
-        let text_length = bytes.get_u8();
-        let text = bytes.split_to(text_length as usize).into();
+        let text_length = bytes.try_get_u8()?;
+        let text = bytes.try_split_to(text_length as usize)?.into();
 
         Ok(Self {
             iso_639_language_code,
@@ -170,11 +171,11 @@ pub struct ExtendedEventItem {
 
 impl ExtendedEventItem {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let item_description_length = bytes.get_u8();
-        let item_description = bytes.split_to(item_description_length as usize).into();
+        let item_description_length = bytes.try_get_u8()?;
+        let item_description = bytes.try_split_to(item_description_length as usize)?.into();
 
-        let item_length = bytes.get_u16();
-        let item = bytes.split_to(item_length as usize).into();
+        let item_length = bytes.try_get_u16()?;
+        let item = bytes.try_split_to(item_length as usize)?.into();
 
         Ok(Self {
             item_description,
@@ -194,15 +195,15 @@ pub struct MhExtendedEventDescriptor {
 
 impl MhExtendedEventDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let descriptor_number = (head & 0xF0) >> 4;
         let last_descriptor_number = head & 0x0F;
 
-        let iso_639_language_code = bytes.get_byte_array::<3>();
+        let iso_639_language_code = bytes.try_get_byte_array::<3>()?;
 
         let items = {
-            let length_of_items = bytes.get_u16();
-            let mut bytes = bytes.split_to(length_of_items as usize);
+            let length_of_items = bytes.try_get_u16()?;
+            let mut bytes = bytes.try_split_to(length_of_items as usize)?;
             let mut items = Vec::new();
             while bytes.has_remaining() {
                 items.push(ExtendedEventItem::read(&mut bytes)?);
@@ -211,8 +212,8 @@ impl MhExtendedEventDescriptor {
             items
         };
 
-        let text_length = bytes.get_u16();
-        let text = bytes.split_to(text_length as usize).into();
+        let text_length = bytes.try_get_u16()?;
+        let text = bytes.try_split_to(text_length as usize)?.into();
 
         Ok(Self {
             descriptor_number,
@@ -246,13 +247,15 @@ pub struct MhServiceDescriptor {
 
 impl MhServiceDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_type = bytes.get_u8();
+        let service_type = bytes.try_get_u8()?;
 
-        let service_provider_name_length = bytes.get_u8();
-        let service_provider_name = bytes.split_to(service_provider_name_length as usize).into();
+        let service_provider_name_length = bytes.try_get_u8()?;
+        let service_provider_name = bytes
+            .try_split_to(service_provider_name_length as usize)?
+            .into();
 
-        let service_name_length = bytes.get_u8();
-        let service_name = bytes.split_to(service_name_length as usize).into();
+        let service_name_length = bytes.try_get_u8()?;
+        let service_name = bytes.try_split_to(service_name_length as usize)?.into();
 
         Ok(Self {
             service_type,
@@ -272,10 +275,10 @@ pub struct MhBroadcastIdDescriptor {
 
 impl MhBroadcastIdDescriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let original_network_id = bytes.get_u16();
-        let tlv_stream_id = bytes.get_u16();
-        let event_id = bytes.get_u16();
-        let broadcaster_id = bytes.get_u8();
+        let original_network_id = bytes.try_get_u16()?;
+        let tlv_stream_id = bytes.try_get_u16()?;
+        let event_id = bytes.try_get_u16()?;
+        let broadcaster_id = bytes.try_get_u8()?;
 
         Ok(Self {
             original_network_id,
@@ -286,10 +289,33 @@ impl MhBroadcastIdDescriptor {
     }
 }
 
+/// Access control descriptor, which tells where the ECMs of the package or the asset are sent.
+#[derive(Clone, Debug)]
+pub struct AccessControlDescriptor {
+    pub ca_system_id: u16,
+    pub location: MmtGeneralLocation,
+    pub private_data: Vec<u8>,
+}
+
+impl AccessControlDescriptor {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let ca_system_id = bytes.try_get_u16()?;
+        let location = MmtGeneralLocation::read(bytes)?;
+        let private_data = bytes.to_vec();
+
+        Ok(Self {
+            ca_system_id,
+            location,
+            private_data,
+        })
+    }
+}
+
 #[derive(Clone, Debug, FromRepr)]
 #[repr(u16)]
 pub enum DescriptorTag {
     MpuTimestampDescriptor = 0x0001,
+    AccessControlDescriptor = 0x8004,
     MpuExtendedTimestampDescriptor = 0x8026,
     MhBroadcasterNameDescriptor = 0x8018,
     MhServiceDescriptor = 0x8019,
@@ -301,6 +327,7 @@ pub enum DescriptorTag {
 #[derive(Clone, Debug)]
 pub enum Descriptor {
     MpuTimestamp(MpuTimestampDescriptor),
+    AccessControl(AccessControlDescriptor),
     MpuExtendedTimestamp(MpuExtendedTimestampDescriptor),
     MhBroadcasterName(MhBroadcasterNameDescriptor),
     MhService(MhServiceDescriptor),
@@ -312,20 +339,20 @@ pub enum Descriptor {
 
 impl Descriptor {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let descriptor_tag = bytes.get_u16();
+        let descriptor_tag = bytes.try_get_u16()?;
         let descriptor_length = if descriptor_tag <= 0x3FFF {
-            bytes.get_u8() as usize
+            bytes.try_get_u8()? as usize
         } else if descriptor_tag <= 0x6FFF {
-            bytes.get_u16() as usize
+            bytes.try_get_u16()? as usize
         } else if descriptor_tag <= 0x7FFF {
-            bytes.get_u32() as usize
+            bytes.try_get_u32()? as usize
         } else if descriptor_tag <= 0xEFFF {
-            bytes.get_u8() as usize
+            bytes.try_get_u8()? as usize
         } else {
-            bytes.get_u16() as usize
+            bytes.try_get_u16()? as usize
         };
 
-        let mut bytes = bytes.split_to(descriptor_length);
+        let mut bytes = bytes.try_split_to(descriptor_length)?;
         let Some(descriptor_tag) = DescriptorTag::from_repr(descriptor_tag) else {
             return Ok(Self::Unknown(descriptor_tag, bytes.into()));
         };
@@ -334,6 +361,9 @@ impl Descriptor {
             DescriptorTag::MpuTimestampDescriptor => {
                 Self::MpuTimestamp(MpuTimestampDescriptor::read(&mut bytes)?)
             }
+            DescriptorTag::AccessControlDescriptor => {
+                Self::AccessControl(AccessControlDescriptor::read(&mut bytes)?)
+            }
             DescriptorTag::MpuExtendedTimestampDescriptor => {
                 Self::MpuExtendedTimestamp(MpuExtendedTimestampDescriptor::read(&mut bytes)?)
             }
@@ -0,0 +1,168 @@
+//! The IPv4 and IPv6 packets of TLV packets sent without header compression, which carry UDP
+//! datagrams other than those of MMTP, such as the NTP time and data broadcasting.
+
+use std::io::{ErrorKind, Result};
+use std::net::{IpAddr, SocketAddr};
+
+use bytes::{Buf, Bytes};
+
+use crate::read_ext::BytesExt;
+
+const HOP_BY_HOP_OPTIONS: u8 = 0;
+const UDP: u8 = 17;
+const ROUTING: u8 = 43;
+const FRAGMENT: u8 = 44;
+const DESTINATION_OPTIONS: u8 = 60;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UdpDatagram {
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Bytes,
+}
+
+impl UdpDatagram {
+    /// Reads the datagram of an IPv4 packet, which is `None` for a packet of another protocol.
+    pub fn read_ipv4(bytes: &mut Bytes) -> Result<Option<Self>> {
+        let head = bytes.try_get_u8()?;
+        let version = (head & 0xF0) >> 4;
+        let header_length = usize::from(head & 0x0F) * 4;
+        if version != 4 || header_length < 20 {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        let _type_of_service = bytes.try_get_u8()?;
+        let total_length = usize::from(bytes.try_get_u16()?);
+        let _identification = bytes.try_get_u16()?;
+        let flags_and_fragment_offset = bytes.try_get_u16()?;
+        let _time_to_live = bytes.try_get_u8()?;
+        let protocol = bytes.try_get_u8()?;
+        let _header_checksum = bytes.try_get_u16()?;
+        let source = bytes.try_get_ipv4_addr()?;
+        let destination = bytes.try_get_ipv4_addr()?;
+        // Options
+        bytes.try_split_to(header_length - 20)?;
+
+        let payload_length = total_length
+            .checked_sub(header_length)
+            .ok_or(ErrorKind::InvalidData)?;
+        let mut payload = bytes.try_split_to(payload_length)?;
+        if protocol != UDP {
+            return Ok(None);
+        }
+        // More fragments, or a fragment other than the first.
+        if flags_and_fragment_offset & 0x3FFF != 0 {
+            return Err(ErrorKind::Unsupported.into());
+        }
+
+        Self::read(source.into(), destination.into(), &mut payload).map(Some)
+    }
+
+    /// Reads the datagram of an IPv6 packet, which is `None` for a packet of another protocol.
+    pub fn read_ipv6(bytes: &mut Bytes) -> Result<Option<Self>> {
+        let head = bytes.try_get_u32()?;
+        let version = ((head & 0xF000_0000) >> 28) as u8;
+        if version != 6 {
+            return Err(ErrorKind::InvalidData.into());
+        }
+
+        let payload_length = usize::from(bytes.try_get_u16()?);
+        let mut next_header = bytes.try_get_u8()?;
+        let _hop_limit = bytes.try_get_u8()?;
+        let source = bytes.try_get_ipv6_addr()?;
+        let destination = bytes.try_get_ipv6_addr()?;
+
+        let mut payload = bytes.try_split_to(payload_length)?;
+        loop {
+            match next_header {
+                UDP => break,
+                HOP_BY_HOP_OPTIONS | ROUTING | DESTINATION_OPTIONS => {
+                    next_header = payload.try_get_u8()?;
+                    let length = usize::from(payload.try_get_u8()?) * 8 + 6;
+                    payload.try_split_to(length)?;
+                }
+                FRAGMENT => return Err(ErrorKind::Unsupported.into()),
+                _ => return Ok(None),
+            }
+        }
+
+        Self::read(source.into(), destination.into(), &mut payload).map(Some)
+    }
+
+    fn read(source: IpAddr, destination: IpAddr, bytes: &mut Bytes) -> Result<Self> {
+        let source_port = bytes.try_get_u16()?;
+        let destination_port = bytes.try_get_u16()?;
+        let length = usize::from(bytes.try_get_u16()?);
+        let _checksum = bytes.try_get_u16()?;
+        let payload_length = length.checked_sub(8).ok_or(ErrorKind::InvalidData)?;
+        let payload = bytes.try_split_to(payload_length)?;
+
+        Ok(Self {
+            source: SocketAddr::new(source, source_port),
+            destination: SocketAddr::new(destination, destination_port),
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn reads_the_udp_datagram_of_an_ipv4_packet() {
+        let mut bytes = Bytes::from_static(&[
+            0x45, 0x00, 0x00, 0x1E, // version, IHL, type of service, total length
+            0x00, 0x00, 0x40, 0x00, // identification, flags, fragment offset
+            0x40, 0x11, 0x00, 0x00, // time to live, protocol, header checksum
+            0xC0, 0xA8, 0x00, 0x01, 0xEF, 0x00, 0x00, 0x01, // source and destination
+            0x04, 0xD2, 0x16, 0x2E, 0x00, 0x0A, 0x00, 0x00, // UDP header
+            0xAA, 0xBB, // payload
+        ]);
+        assert_eq!(
+            UdpDatagram::read_ipv4(&mut bytes).unwrap(),
+            Some(UdpDatagram {
+                source: SocketAddr::new(Ipv4Addr::new(192, 168, 0, 1).into(), 1234),
+                destination: SocketAddr::new(Ipv4Addr::new(239, 0, 0, 1).into(), 5678),
+                payload: Bytes::from_static(&[0xAA, 0xBB]),
+            })
+        );
+
+        // The same packet as TCP.
+        let mut bytes = Bytes::from_static(&[
+            0x45, 0x00, 0x00, 0x14, 0x00, 0x00, 0x40, 0x00, 0x40, 0x06, 0x00, 0x00, 0xC0, 0xA8,
+            0x00, 0x01, 0xEF, 0x00, 0x00, 0x01,
+        ]);
+        assert_eq!(UdpDatagram::read_ipv4(&mut bytes).unwrap(), None);
+    }
+
+    #[test]
+    fn reads_the_udp_datagram_of_an_ipv6_packet() {
+        let mut packet = vec![0x60, 0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x01];
+        packet.extend(Ipv6Addr::LOCALHOST.octets());
+        packet.extend(Ipv6Addr::new(0xFF0E, 0, 0, 0, 0, 0, 0, 0x0101).octets());
+        // A hop-by-hop options header, followed by the UDP header.
+        packet.extend([0x11, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00]);
+        packet.extend([0x00, 0x7B, 0x00, 0x7B, 0x00, 0x0A, 0x00, 0x00, 0xAA, 0xBB]);
+
+        let datagram = UdpDatagram::read_ipv6(&mut Bytes::from(packet.clone()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            datagram.destination,
+            SocketAddr::new(Ipv6Addr::new(0xFF0E, 0, 0, 0, 0, 0, 0, 0x0101).into(), 123)
+        );
+        assert_eq!(datagram.payload.as_ref(), &[0xAA, 0xBB]);
+
+        // The UDP length runs past the end of the packet.
+        packet[53] = 0x10;
+        assert_eq!(
+            UdpDatagram::read_ipv6(&mut Bytes::from(packet))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -1,10 +1,13 @@
 pub mod compressed_ip;
 pub mod deflag;
 pub mod descriptor;
+pub mod ip;
 pub mod message;
 pub mod mfu;
 pub mod mmtp;
+pub mod ntp;
 pub mod table;
 pub mod tlv;
+pub mod tlv_si;
 
 mod read_ext;
@@ -1,4 +1,4 @@
-use std::io::{Read, Result};
+use std::io::{ErrorKind, Read, Result};
 
 use byteorder::{BE, ReadBytesExt};
 use bytes::{Buf, Bytes};
@@ -14,11 +14,16 @@ pub struct PaMessage {
 
 impl PaMessage {
     pub fn read(mut reader: impl Read) -> Result<Self> {
-        let version = reader.read_u8().unwrap();
-        let length = reader.read_u32::<BE>().unwrap();
-
-        let mut buf = vec![0; length as usize];
-        reader.read_exact(&mut buf)?;
+        let version = reader.read_u8()?;
+        let length = reader.read_u32::<BE>()?;
+
+        // A corrupted length can be far longer than the message, so the buffer grows only as long
+        // as there is something to read.
+        let mut buf = Vec::new();
+        reader.take(u64::from(length)).read_to_end(&mut buf)?;
+        if buf.len() != length as usize {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
 
         let mut bytes = Bytes::from(buf);
 
@@ -29,12 +34,12 @@ impl PaMessage {
             table_length: u16,
         }
 
-        let number_of_tables = bytes.get_u8() as usize;
+        let number_of_tables = bytes.try_get_u8()? as usize;
         let mut table_meta = Vec::with_capacity(number_of_tables);
         for _ in 0..number_of_tables {
-            let table_id = bytes.get_u8();
-            let table_version = bytes.get_u8();
-            let table_length = bytes.get_u16_ne();
+            let table_id = bytes.try_get_u8()?;
+            let table_version = bytes.try_get_u8()?;
+            let table_length = bytes.try_get_u16_ne()?;
 
             table_meta.push(TableMeta {
                 table_id,
@@ -73,17 +78,41 @@ impl M2SectionMessage {
     }
 }
 
+/// CA message, which carries the tables of the conditional access system.
+#[derive(Clone, Debug)]
+pub struct CaMessage {
+    pub version: u8,
+    pub table: Table,
+}
+
+impl CaMessage {
+    pub fn read(mut reader: impl Read) -> Result<Self> {
+        let version = reader.read_u8()?;
+        let length = reader.read_u16::<BE>()?;
+
+        let mut buf = vec![0; length as usize];
+        reader.read_exact(&mut buf)?;
+
+        let mut bytes = Bytes::from(buf);
+        let table = Table::read(&mut bytes)?;
+
+        Ok(Self { version, table })
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, FromRepr, PartialEq)]
 #[repr(u16)]
 pub enum MessageId {
     Pa = 0x0000,
     M2Section = 0x8000,
+    Ca = 0x8001,
 }
 
 #[derive(Clone, Debug)]
 pub enum Message {
     Pa(PaMessage),
     M2Section(M2SectionMessage),
+    Ca(CaMessage),
     Unknown(u16, Vec<u8>),
 }
 
@@ -99,6 +128,7 @@ impl Message {
         Ok(match message_id {
             MessageId::Pa => Self::Pa(PaMessage::read(&mut reader)?),
             MessageId::M2Section => Self::M2Section(M2SectionMessage::read(&mut reader)?),
+            MessageId::Ca => Self::Ca(CaMessage::read(&mut reader)?),
         })
     }
 }
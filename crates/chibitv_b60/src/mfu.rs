@@ -1,4 +1,4 @@
-use std::io::{Cursor, Read};
+use std::io::{Cursor, ErrorKind, Read};
 
 use byteorder::{BE, ReadBytesExt};
 
@@ -58,12 +58,11 @@ impl TryFrom<&MpuFragment> for MfuPayload {
                 let mut data = Vec::new();
 
                 while index < value.payload.len() {
-                    let data_unit_length = reader.read_u16::<BE>()?;
+                    let data_unit_length = usize::from(reader.read_u16::<BE>()?);
                     let remaining_len = value.payload.len() - index;
-                    assert!(
-                        usize::from(data_unit_length) <= remaining_len,
-                        "insufficient buffer size: {data_unit_length} > {remaining_len}"
-                    );
+                    if data_unit_length > remaining_len {
+                        return Err(ErrorKind::UnexpectedEof.into());
+                    }
 
                     let movie_fragment_sequence_number = reader.read_u32::<BE>()?;
                     let sample_number = reader.read_u32::<BE>()?;
@@ -71,7 +70,9 @@ impl TryFrom<&MpuFragment> for MfuPayload {
                     let priority = reader.read_u8()?;
                     let dependency_counter = reader.read_u8()?;
 
-                    let buf_len = (data_unit_length - 14) as usize;
+                    let buf_len = data_unit_length
+                        .checked_sub(14)
+                        .ok_or(ErrorKind::InvalidData)?;
                     let mut buf = vec![0u8; buf_len];
                     reader.read_exact(&mut buf)?;
 
@@ -84,7 +85,7 @@ impl TryFrom<&MpuFragment> for MfuPayload {
                         data: buf,
                     });
 
-                    index += usize::from(data_unit_length + 2);
+                    index += data_unit_length + 2;
                 }
 
                 Ok(MfuPayload::TimedAggregated(data))
@@ -100,21 +101,23 @@ impl TryFrom<&MpuFragment> for MfuPayload {
             let mut data = Vec::new();
 
             while index < value.payload.len() {
-                let data_unit_length = reader.read_u16::<BE>()?;
+                let data_unit_length = usize::from(reader.read_u16::<BE>()?);
                 let remaining_len = value.payload.len() - index;
-                assert!(
-                    usize::from(data_unit_length) <= remaining_len,
-                    "insufficient buffer size: {data_unit_length} > {remaining_len}"
-                );
+                if data_unit_length > remaining_len {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
 
                 let item_id = reader.read_u32::<BE>()?;
 
-                let mut buf = vec![0u8; (data_unit_length - 4) as usize];
+                let buf_len = data_unit_length
+                    .checked_sub(4)
+                    .ok_or(ErrorKind::InvalidData)?;
+                let mut buf = vec![0u8; buf_len];
                 reader.read_exact(&mut buf)?;
 
                 data.push(MfuNonTimedData { item_id, data: buf });
 
-                index += usize::from(data_unit_length + 2);
+                index += data_unit_length + 2;
             }
 
             Ok(MfuPayload::Aggregated(data))
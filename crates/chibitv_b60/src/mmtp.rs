@@ -4,6 +4,8 @@ use byteorder::{BE, ReadBytesExt};
 use bytes::{Buf, Bytes};
 use strum::FromRepr;
 
+use crate::read_ext::BytesExt;
+
 #[derive(Copy, Clone, Debug, Eq, FromRepr, PartialEq)]
 #[repr(u8)]
 #[allow(clippy::enum_variant_names)]
@@ -34,28 +36,31 @@ pub struct MmtpPacket {
 
 impl MmtpPacket {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let version = (head & 0b1100_0000) >> 6;
         let packet_counter_flag = ((head & 0b0010_0000) >> 5) == 1;
-        let fec_type = FecType::from_repr((head & 0b0001_1000) >> 3).unwrap();
+        let fec_type =
+            FecType::from_repr((head & 0b0001_1000) >> 3).ok_or(ErrorKind::InvalidData)?;
         let extension_header_flag = ((head & 0b0000_0010) >> 1) == 1;
         let rap_flag = (head & 0b0000_0001) == 1;
-        assert_eq!(version, 0b00);
+        if version != 0b00 {
+            return Err(ErrorKind::InvalidData.into());
+        }
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let payload_type = head & 0b0011_1111;
-        let packet_id = bytes.get_u16();
-        let delivery_timestamp = bytes.get_u32();
-        let packet_sequence_number = bytes.get_u32();
+        let packet_id = bytes.try_get_u16()?;
+        let delivery_timestamp = bytes.try_get_u32()?;
+        let packet_sequence_number = bytes.try_get_u32()?;
         let packet_counter = if packet_counter_flag {
-            Some(bytes.get_u32())
+            Some(bytes.try_get_u32()?)
         } else {
             None
         };
         let extension_header = if extension_header_flag {
-            let header_type = bytes.get_u16();
-            let data_length = bytes.get_u16();
-            let data = bytes.split_to(data_length as usize);
+            let header_type = bytes.try_get_u16()?;
+            let data_length = bytes.try_get_u16()?;
+            let data = bytes.try_split_to(data_length as usize)?;
             Some(MmtpExtensionHeader { header_type, data })
         } else {
             None
@@ -106,7 +111,8 @@ impl MpuFragment {
     pub fn read(mut reader: impl Read) -> Result<Self> {
         let payload_length = reader.read_u16::<BE>()?;
         let head = reader.read_u8()?;
-        let fragment_type = MpuFragmentType::from_repr((head & 0b1111_0000) >> 4).unwrap();
+        let fragment_type =
+            MpuFragmentType::from_repr((head & 0b1111_0000) >> 4).ok_or(ErrorKind::InvalidData)?;
         let timed_flag = ((head & 0b0000_1000) >> 3) == 1;
         let fragmentation_indicator = FragmentationIndicator::from_repr((head & 0b0000_0110) >> 1)
             .ok_or(ErrorKind::InvalidData)?;
@@ -114,7 +120,10 @@ impl MpuFragment {
         let fragment_counter = reader.read_u8()?;
         let mpu_sequence_number = reader.read_u32::<BE>()?;
 
-        let mut payload = vec![0u8; (payload_length - 6) as usize];
+        let payload_length = payload_length
+            .checked_sub(6)
+            .ok_or(ErrorKind::InvalidData)?;
+        let mut payload = vec![0u8; payload_length as usize];
         reader.read_exact(&mut payload)?;
 
         Ok(Self {
@@ -164,10 +173,9 @@ impl SignalingMessage {
                 };
 
                 let remaining_len = buf.len() - (reader.position() as usize);
-                assert!(
-                    message_length <= remaining_len,
-                    "insufficient buffer size: {message_length} > {remaining_len}"
-                );
+                if message_length > remaining_len {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
 
                 let mut payload = vec![0u8; message_length];
                 reader.read_exact(&mut payload)?;
@@ -217,7 +225,30 @@ impl TryFrom<&MmtpPacket> for MmtpPayload {
             MmtpPayloadType::ControlMessage => {
                 Self::SignalingMessage(SignalingMessage::read(&value.payload)?)
             }
-            _ => todo!(),
+            _ => return Err(ErrorKind::Unsupported.into()),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_truncated_packets() {
+        // The header ends in the middle of the delivery timestamp.
+        let mut bytes = Bytes::from_static(&[0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00]);
+        assert!(MmtpPacket::read(&mut bytes).is_err());
+
+        // The extension header is longer than the packet.
+        let mut bytes = Bytes::from_static(&[
+            0x02, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x08, 0x01,
+        ]);
+        assert!(MmtpPacket::read(&mut bytes).is_err());
+
+        // The payload length does not even cover the MPU header.
+        let fragment = [0x00, 0x02, 0x20, 0x00, 0x00, 0x00, 0x00, 0x01];
+        assert!(MpuFragment::read(Cursor::new(&fragment)).is_err());
+    }
+}
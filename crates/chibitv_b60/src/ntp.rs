@@ -0,0 +1,127 @@
+//! The NTP packets the broadcaster sends in IP packets of their own, to synchronise the time of
+//! receivers.
+
+use std::io::Result;
+
+use bytes::{Buf, Bytes};
+use chrono::{DateTime, Utc};
+
+use crate::read_ext::BytesExt;
+
+/// The UDP port NTP packets are sent to.
+pub const NTP_PORT: u16 = 123;
+
+/// Seconds from the NTP epoch, 1900-01-01, to the Unix one.
+const UNIX_EPOCH_OFFSET: i64 = 2_208_988_800;
+
+/// A time in seconds since 1900-01-01 UTC, with a 32-bit fraction.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct NtpTimestamp {
+    pub seconds: u32,
+    pub fraction: u32,
+}
+
+impl NtpTimestamp {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let seconds = bytes.try_get_u32()?;
+        let fraction = bytes.try_get_u32()?;
+
+        Ok(Self { seconds, fraction })
+    }
+
+    /// The time of the timestamp, which is `None` when it is unset.
+    pub fn to_date_time(self) -> Option<DateTime<Utc>> {
+        if self.seconds == 0 && self.fraction == 0 {
+            return None;
+        }
+
+        let seconds = i64::from(self.seconds) - UNIX_EPOCH_OFFSET;
+        let nanoseconds = ((u64::from(self.fraction) * 1_000_000_000) >> 32) as u32;
+
+        DateTime::from_timestamp(seconds, nanoseconds)
+    }
+}
+
+/// NTP packet (RFC 5905), without the extension fields and the MAC.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NtpPacket {
+    pub leap_indicator: u8,
+    pub version_number: u8,
+    pub mode: u8,
+    pub stratum: u8,
+    pub poll: i8,
+    pub precision: i8,
+    pub root_delay: u32,
+    pub root_dispersion: u32,
+    pub reference_identification: [u8; 4],
+    pub reference_timestamp: NtpTimestamp,
+    pub originate_timestamp: NtpTimestamp,
+    pub receive_timestamp: NtpTimestamp,
+    pub transmit_timestamp: NtpTimestamp,
+}
+
+impl NtpPacket {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let head = bytes.try_get_u8()?;
+        let leap_indicator = (head & 0b1100_0000) >> 6;
+        let version_number = (head & 0b0011_1000) >> 3;
+        let mode = head & 0b0000_0111;
+        let stratum = bytes.try_get_u8()?;
+        let poll = bytes.try_get_i8()?;
+        let precision = bytes.try_get_i8()?;
+        let root_delay = bytes.try_get_u32()?;
+        let root_dispersion = bytes.try_get_u32()?;
+        let reference_identification = bytes.try_get_byte_array::<4>()?;
+        let reference_timestamp = NtpTimestamp::read(bytes)?;
+        let originate_timestamp = NtpTimestamp::read(bytes)?;
+        let receive_timestamp = NtpTimestamp::read(bytes)?;
+        let transmit_timestamp = NtpTimestamp::read(bytes)?;
+
+        Ok(Self {
+            leap_indicator,
+            version_number,
+            mode,
+            stratum,
+            poll,
+            precision,
+            root_delay,
+            root_dispersion,
+            reference_identification,
+            reference_timestamp,
+            originate_timestamp,
+            receive_timestamp,
+            transmit_timestamp,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn reads_the_time_of_an_ntp_packet() {
+        let mut packet = vec![0x25, 0x01, 0x04, 0xEC];
+        packet.extend([0; 8]);
+        packet.extend(*b"GPS\0");
+        packet.extend([0; 24]);
+        // 2026-10-16 12:00:00.5 UTC
+        packet.extend([0xEE, 0x7C, 0x90, 0x40, 0x80, 0x00, 0x00, 0x00]);
+
+        let packet = NtpPacket::read(&mut Bytes::from(packet)).unwrap();
+        assert_eq!(packet.version_number, 4);
+        assert_eq!(packet.mode, 5);
+        assert_eq!(packet.precision, -20);
+        assert_eq!(packet.reference_identification, *b"GPS\0");
+        assert_eq!(packet.receive_timestamp.to_date_time(), None);
+        assert_eq!(
+            packet.transmit_timestamp.to_date_time(),
+            Some(
+                Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap()
+                    + chrono::Duration::milliseconds(500)
+            )
+        );
+    }
+}
@@ -8,6 +8,7 @@ use strum::FromRepr;
 
 use crate::descriptor::Descriptor;
 use crate::read_ext::BytesExt;
+use crate::tlv_si::verify_crc_32;
 
 #[derive(Copy, Clone, Debug, Eq, FromRepr, PartialEq)]
 #[repr(u8)]
@@ -65,19 +66,19 @@ impl MmtGeneralLocation {
 impl MmtGeneralLocation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
         let location_type =
-            MmtLocationType::from_repr(bytes.get_u8()).ok_or(ErrorKind::InvalidData)?;
+            MmtLocationType::from_repr(bytes.try_get_u8()?).ok_or(ErrorKind::InvalidData)?;
 
         Ok(match location_type {
             MmtLocationType::None => {
-                let packet_id = bytes.get_u16();
+                let packet_id = bytes.try_get_u16()?;
 
                 Self::None { packet_id }
             }
             MmtLocationType::Ipv4 => {
-                let src_addr = bytes.get_ipv4_addr();
-                let dst_addr = bytes.get_ipv4_addr();
-                let dst_port = bytes.get_u16();
-                let packet_id = bytes.get_u16();
+                let src_addr = bytes.try_get_ipv4_addr()?;
+                let dst_addr = bytes.try_get_ipv4_addr()?;
+                let dst_port = bytes.try_get_u16()?;
+                let packet_id = bytes.try_get_u16()?;
 
                 Self::Ipv4 {
                     src_addr,
@@ -87,10 +88,10 @@ impl MmtGeneralLocation {
                 }
             }
             MmtLocationType::Ipv6 => {
-                let src_addr = bytes.get_ipv6_addr();
-                let dst_addr = bytes.get_ipv6_addr();
-                let dst_port = bytes.get_u16();
-                let packet_id = bytes.get_u16();
+                let src_addr = bytes.try_get_ipv6_addr()?;
+                let dst_addr = bytes.try_get_ipv6_addr()?;
+                let dst_port = bytes.try_get_u16()?;
+                let packet_id = bytes.try_get_u16()?;
 
                 Self::Ipv6 {
                     src_addr,
@@ -100,9 +101,9 @@ impl MmtGeneralLocation {
                 }
             }
             MmtLocationType::M2ts => {
-                let network_id = bytes.get_u16();
-                let m2_transport_stream_id = bytes.get_u16();
-                let m2_pid = bytes.get_u16() & 0b0001_1111_1111_1111;
+                let network_id = bytes.try_get_u16()?;
+                let m2_transport_stream_id = bytes.try_get_u16()?;
+                let m2_pid = bytes.try_get_u16()? & 0b0001_1111_1111_1111;
 
                 Self::M2ts {
                     network_id,
@@ -111,10 +112,10 @@ impl MmtGeneralLocation {
                 }
             }
             MmtLocationType::M2Ipv6 => {
-                let src_addr = bytes.get_ipv6_addr();
-                let dst_addr = bytes.get_ipv6_addr();
-                let dst_port = bytes.get_u16();
-                let m2_pid = bytes.get_u16() & 0b0001_1111_1111_1111;
+                let src_addr = bytes.try_get_ipv6_addr()?;
+                let dst_addr = bytes.try_get_ipv6_addr()?;
+                let dst_port = bytes.try_get_u16()?;
+                let m2_pid = bytes.try_get_u16()? & 0b0001_1111_1111_1111;
 
                 Self::M2Ipv6 {
                     src_addr,
@@ -124,8 +125,8 @@ impl MmtGeneralLocation {
                 }
             }
             MmtLocationType::Url => {
-                let url_length = bytes.get_u8();
-                let url = bytes.split_to(url_length as usize).to_vec();
+                let url_length = bytes.try_get_u8()?;
+                let url = bytes.try_split_to(url_length as usize)?.to_vec();
 
                 Self::Url(url)
             }
@@ -151,13 +152,13 @@ pub enum IpDeliveryLocation {
 impl IpDeliveryLocation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
         let location_type =
-            MmtLocationType::from_repr(bytes.get_u8()).ok_or(ErrorKind::InvalidData)?;
+            MmtLocationType::from_repr(bytes.try_get_u8()?).ok_or(ErrorKind::InvalidData)?;
 
         Ok(match location_type {
             MmtLocationType::Ipv4 => {
-                let src_addr = bytes.get_ipv4_addr();
-                let dst_addr = bytes.get_ipv4_addr();
-                let dst_port = bytes.get_u16();
+                let src_addr = bytes.try_get_ipv4_addr()?;
+                let dst_addr = bytes.try_get_ipv4_addr()?;
+                let dst_port = bytes.try_get_u16()?;
 
                 Self::Ipv4 {
                     src_addr,
@@ -166,9 +167,9 @@ impl IpDeliveryLocation {
                 }
             }
             MmtLocationType::Ipv6 => {
-                let src_addr = bytes.get_ipv6_addr();
-                let dst_addr = bytes.get_ipv6_addr();
-                let dst_port = bytes.get_u16();
+                let src_addr = bytes.try_get_ipv6_addr()?;
+                let dst_addr = bytes.try_get_ipv6_addr()?;
+                let dst_port = bytes.try_get_u16()?;
 
                 Self::Ipv6 {
                     src_addr,
@@ -177,12 +178,12 @@ impl IpDeliveryLocation {
                 }
             }
             MmtLocationType::Url => {
-                let url_length = bytes.get_u8();
-                let url = bytes.split_to(url_length as usize).to_vec();
+                let url_length = bytes.try_get_u8()?;
+                let url = bytes.try_split_to(url_length as usize)?.to_vec();
 
                 Self::Url(url)
             }
-            _ => unreachable!(),
+            _ => return Err(ErrorKind::InvalidData.into()),
         })
     }
 }
@@ -196,10 +197,10 @@ pub struct MmtIpDelivery {
 
 impl MmtIpDelivery {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let transport_file_id = bytes.get_u32();
+        let transport_file_id = bytes.try_get_u32()?;
         let location = IpDeliveryLocation::read(bytes)?;
 
-        let descriptor_loop_length = bytes.get_u16();
+        let descriptor_loop_length = bytes.try_get_u16()?;
         let mut descriptors = Vec::with_capacity(descriptor_loop_length as usize);
         for _ in 0..descriptor_loop_length {
             let descriptor = Descriptor::read(bytes)?;
@@ -224,21 +225,21 @@ pub struct Plt {
 
 impl Plt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let version = bytes.get_u8();
-        let _length = bytes.get_u16();
+        let version = bytes.try_get_u8()?;
+        let _length = bytes.try_get_u16()?;
 
-        let num_of_package = bytes.get_u8();
+        let num_of_package = bytes.try_get_u8()?;
         let mut packages = Vec::with_capacity(num_of_package as usize);
         for _ in 0..num_of_package {
-            let mmt_package_id_length = bytes.get_u8();
-            let mmt_package_id = bytes.split_to(mmt_package_id_length as usize).to_vec();
+            let mmt_package_id_length = bytes.try_get_u8()?;
+            let mmt_package_id = bytes.try_split_to(mmt_package_id_length as usize)?.to_vec();
 
             let mmt_general_location = MmtGeneralLocation::read(bytes)?;
 
             packages.push((mmt_package_id, mmt_general_location));
         }
 
-        let num_of_ip_delivery = bytes.get_u8();
+        let num_of_ip_delivery = bytes.try_get_u8()?;
         let mut ip_deliveries = Vec::with_capacity(num_of_ip_delivery as usize);
         for _ in 0..num_of_ip_delivery {
             ip_deliveries.push(MmtIpDelivery::read(bytes)?);
@@ -265,27 +266,25 @@ pub struct MmtAsset {
 
 impl MmtAsset {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let identifier_type = bytes.get_u8();
-        let asset_id_scheme = bytes.get_byte_array::<4>();
+        let identifier_type = bytes.try_get_u8()?;
+        let asset_id_scheme = bytes.try_get_byte_array::<4>()?;
 
-        let asset_id_length = bytes.get_u8();
-        let asset_id = bytes.split_to(asset_id_length as usize).to_vec();
+        let asset_id_length = bytes.try_get_u8()?;
+        let asset_id = bytes.try_split_to(asset_id_length as usize)?.to_vec();
 
-        let asset_type = bytes.get_byte_array::<4>();
+        let asset_type = bytes.try_get_byte_array::<4>()?;
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let asset_clock_relation_flag = (head & 0b0000_0001) == 1;
 
-        let location_count = bytes.get_u8();
+        let location_count = bytes.try_get_u8()?;
         let mut locations = Vec::with_capacity(location_count as usize);
         for _ in 0..location_count {
             locations.push(MmtGeneralLocation::read(bytes)?);
         }
 
-        let asset_descriptors_length = bytes.get_u16();
-        assert!(bytes.remaining() >= asset_descriptors_length as usize);
-
-        let mut bytes = bytes.split_to(asset_descriptors_length as usize);
+        let asset_descriptors_length = bytes.try_get_u16()?;
+        let mut bytes = bytes.try_split_to(asset_descriptors_length as usize)?;
         let mut asset_descriptors = Vec::new();
         while bytes.has_remaining() {
             asset_descriptors.push(Descriptor::read(&mut bytes)?);
@@ -317,26 +316,36 @@ pub struct Mpt {
     pub version: u8,
     pub mpt_mode: MptMode,
     pub mmt_package_id: Vec<u8>,
-    pub mmt_descriptors: Vec<u8>,
+    pub mmt_descriptors: Vec<Descriptor>,
     pub assets: Vec<MmtAsset>,
 }
 
 impl Mpt {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let version = bytes.get_u8();
-        let length = bytes.get_u16();
-        assert_eq!(bytes.remaining(), length as usize);
+        let version = bytes.try_get_u8()?;
+        let length = bytes.try_get_u16()?;
+        if bytes.remaining() != length as usize {
+            return Err(ErrorKind::InvalidData.into());
+        }
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let mpt_mode = MptMode::from_repr(head & 0b0000_0011).ok_or(ErrorKind::InvalidData)?;
 
-        let mmt_package_id_length = bytes.get_u8();
-        let mmt_package_id = bytes.split_to(mmt_package_id_length as usize).into();
+        let mmt_package_id_length = bytes.try_get_u8()?;
+        let mmt_package_id = bytes.try_split_to(mmt_package_id_length as usize)?.into();
 
-        let mmt_descriptors_length = bytes.get_u16();
-        let mmt_descriptors = bytes.split_to(mmt_descriptors_length as usize).into();
+        let mmt_descriptors_length = bytes.try_get_u16()?;
+        let mmt_descriptors = {
+            let mut bytes = bytes.try_split_to(mmt_descriptors_length as usize)?;
+            let mut descriptors = Vec::new();
+            while bytes.has_remaining() {
+                descriptors.push(Descriptor::read(&mut bytes)?);
+            }
 
-        let number_of_assets = bytes.get_u8();
+            descriptors
+        };
+
+        let number_of_assets = bytes.try_get_u8()?;
         let mut assets = Vec::with_capacity(number_of_assets as usize);
         for _ in 0..number_of_assets {
             assets.push(MmtAsset::read(bytes)?);
@@ -374,16 +383,17 @@ pub struct EventInformation {
 
 impl EventInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let event_id = bytes.get_u16();
-        let start_time = parse_start_time(bytes.get_byte_array::<5>());
-        let duration = parse_duration(bytes.get_byte_array::<3>());
+        let event_id = bytes.try_get_u16()?;
+        let start_time = parse_start_time(bytes.try_get_byte_array::<5>()?)?;
+        let duration = parse_duration(bytes.try_get_byte_array::<3>()?);
 
-        let head = bytes.get_u16();
-        let running_status = EventRunningStatus::from_repr(((head & 0xE000) >> 13) as u8).unwrap();
+        let head = bytes.try_get_u16()?;
+        let running_status = EventRunningStatus::from_repr(((head & 0xE000) >> 13) as u8)
+            .ok_or(ErrorKind::InvalidData)?;
         let free_ca_mode = ((head & 0x1000) >> 12) == 1;
         let descriptors_loop_length = head & 0x0FFF;
 
-        let mut bytes = bytes.split_to(descriptors_loop_length as usize);
+        let mut bytes = bytes.try_split_to(descriptors_loop_length as usize)?;
         let mut descriptors = Vec::new();
         while bytes.has_remaining() {
             descriptors.push(Descriptor::read(&mut bytes)?);
@@ -400,9 +410,9 @@ impl EventInformation {
     }
 }
 
-fn parse_start_time(start_time: [u8; 5]) -> Option<NaiveDateTime> {
+fn parse_start_time(start_time: [u8; 5]) -> Result<Option<NaiveDateTime>> {
     if start_time == [0xFF, 0xFF, 0xFF, 0xFF, 0xFF] {
-        return None;
+        return Ok(None);
     }
 
     let mjd = u16::from_be_bytes([start_time[0], start_time[1]]);
@@ -411,9 +421,9 @@ fn parse_start_time(start_time: [u8; 5]) -> Option<NaiveDateTime> {
     let hour = parse_bcd(start_time[2]) as u32;
     let minute = parse_bcd(start_time[3]) as u32;
     let second = parse_bcd(start_time[4]) as u32;
-    let time = NaiveTime::from_hms_opt(hour, minute, second).unwrap();
+    let time = NaiveTime::from_hms_opt(hour, minute, second).ok_or(ErrorKind::InvalidData)?;
 
-    Some(NaiveDateTime::new(date, time))
+    Ok(Some(NaiveDateTime::new(date, time)))
 }
 
 fn parse_duration(duration: [u8; 3]) -> Option<Duration> {
@@ -453,21 +463,21 @@ pub struct MhEit {
 
 impl MhEit {
     pub fn read(table_id: u8, bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
         let section_length = head & 0x0FFF;
-        let service_id = bytes.get_u16();
+        let service_id = bytes.try_get_u16()?;
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let version_number = (head & 0b0011_1110) >> 1;
         let current_next_indicator = (head & 0b0000_0001) == 1;
 
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let tlv_stream_id = bytes.get_u16();
-        let original_network_id = bytes.get_u16();
-        let segment_last_section_number = bytes.get_u8();
-        let last_table_id = bytes.get_u8();
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let tlv_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
+        let segment_last_section_number = bytes.try_get_u8()?;
+        let last_table_id = bytes.try_get_u8()?;
 
         let mut events = Vec::new();
         while bytes.remaining() > 4 {
@@ -475,7 +485,7 @@ impl MhEit {
         }
 
         // TODO: Verify CRC
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             table_id,
@@ -504,10 +514,10 @@ pub struct BroadcasterInformation {
 
 impl BroadcasterInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let broadcaster_id = bytes.get_u8();
+        let broadcaster_id = bytes.try_get_u8()?;
 
-        let broadcaster_descriptors_length = bytes.get_u16() & 0xFFF;
-        let mut bytes = bytes.split_to(broadcaster_descriptors_length as usize);
+        let broadcaster_descriptors_length = bytes.try_get_u16()? & 0xFFF;
+        let mut bytes = bytes.try_split_to(broadcaster_descriptors_length as usize)?;
         let mut descriptors = Vec::new();
         while bytes.has_remaining() {
             descriptors.push(Descriptor::read(&mut bytes)?);
@@ -538,24 +548,24 @@ pub struct MhBit {
 
 impl MhBit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
         let section_length = head & 0x0FFF;
-        let original_network_id = bytes.get_u16();
+        let original_network_id = bytes.try_get_u16()?;
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let version_number = (head & 0b0011_1110) >> 1;
         let current_next_indicator = (head & 0b0000_0001) == 1;
 
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let broadcast_view_propriety = ((head & 0x1000) >> 12) == 1;
 
         let descriptors = {
             let first_descriptors_length = head & 0x0FFF;
-            let mut bytes = bytes.split_to(first_descriptors_length as usize);
+            let mut bytes = bytes.try_split_to(first_descriptors_length as usize)?;
             let mut descriptors = Vec::new();
             while bytes.has_remaining() {
                 descriptors.push(Descriptor::read(&mut bytes)?);
@@ -570,7 +580,7 @@ impl MhBit {
         }
 
         // TODO: Verify CRC
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -601,19 +611,19 @@ pub struct ServiceInformation {
 
 impl ServiceInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_id = bytes.get_u16();
+        let service_id = bytes.try_get_u16()?;
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let eit_user_defined_flags = (head & 0b0001_1100) >> 2;
         let eit_schedule_flag = ((head & 0b0000_0010) >> 1) == 1;
         let eit_present_following_flag = (head & 0b0000_0001) == 1;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let running_status = ((head & 0xE000) >> 13) as u8;
         let free_ca_mode = ((head & 0x1000) >> 12) == 1;
         let descriptors_loop_length = head & 0x0FFF;
 
-        let mut bytes = bytes.split_to(descriptors_loop_length as usize);
+        let mut bytes = bytes.try_split_to(descriptors_loop_length as usize)?;
         let mut descriptors = Vec::new();
         while bytes.has_remaining() {
             descriptors.push(Descriptor::read(&mut bytes)?);
@@ -634,6 +644,7 @@ impl ServiceInformation {
 /// MH-SDT (Service Description Table).
 #[derive(Clone, Debug)]
 pub struct MhSdt {
+    pub table_id: u8,
     pub section_syntax_indicator: bool,
     pub section_length: u16,
     pub tlv_stream_id: u16,
@@ -647,21 +658,21 @@ pub struct MhSdt {
 }
 
 impl MhSdt {
-    pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u16();
+    pub fn read(table_id: u8, bytes: &mut Bytes) -> Result<Self> {
+        let head = bytes.try_get_u16()?;
         let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
         let section_length = head & 0x0FFF;
-        let tlv_stream_id = bytes.get_u16();
+        let tlv_stream_id = bytes.try_get_u16()?;
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let version_number = (head & 0b0011_1110) >> 1;
         let current_next_indicator = (head & 0b0000_0001) == 1;
 
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
-        let original_network_id = bytes.get_u16();
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+        let original_network_id = bytes.try_get_u16()?;
 
-        _ = bytes.get_u8(); // reserved_future_use
+        _ = bytes.try_get_u8()?; // reserved_future_use
 
         let mut services = Vec::new();
         while bytes.remaining() > 4 {
@@ -669,9 +680,10 @@ impl MhSdt {
         }
 
         // TODO: Verify CRC
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
+            table_id,
             section_syntax_indicator,
             section_length,
             tlv_stream_id,
@@ -695,13 +707,13 @@ pub struct SelectionInformation {
 
 impl SelectionInformation {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let service_id = bytes.get_u16();
+        let service_id = bytes.try_get_u16()?;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let running_status = ((head & 0x7000) >> 12) as u8;
         let service_loop_length = head & 0x0FFF;
 
-        let mut bytes = bytes.split_to(service_loop_length as usize);
+        let mut bytes = bytes.try_split_to(service_loop_length as usize)?;
         let mut descriptors = Vec::new();
         while bytes.has_remaining() {
             descriptors.push(Descriptor::read(&mut bytes)?)
@@ -731,24 +743,24 @@ pub struct MhSit {
 
 impl MhSit {
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
         let section_length = head & 0x0FFF;
 
-        _ = bytes.get_u16(); // reserved_future_use
+        _ = bytes.try_get_u16()?; // reserved_future_use
 
-        let head = bytes.get_u8();
+        let head = bytes.try_get_u8()?;
         let version_number = (head & 0b0011_1110) >> 1;
         let current_next_indicator = (head & 0b0000_0001) == 1;
 
-        let section_number = bytes.get_u8();
-        let last_section_number = bytes.get_u8();
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
 
-        let head = bytes.get_u16();
+        let head = bytes.try_get_u16()?;
         let transmission_info_loop_length = head & 0xFFF;
 
         let descriptors = {
-            let mut bytes = bytes.split_to(transmission_info_loop_length as usize);
+            let mut bytes = bytes.try_split_to(transmission_info_loop_length as usize)?;
             let mut descriptors = Vec::new();
             while bytes.has_remaining() {
                 descriptors.push(Descriptor::read(&mut bytes)?);
@@ -763,7 +775,7 @@ impl MhSit {
         }
 
         // TODO: Verify CRC
-        let crc_32 = bytes.get_u32();
+        let crc_32 = bytes.try_get_u32()?;
 
         Ok(Self {
             section_syntax_indicator,
@@ -779,8 +791,112 @@ impl MhSit {
     }
 }
 
+/// ECM (Entitlement Control Message) table, carried in the CA message.
+#[derive(Clone, Debug)]
+pub struct Ecm {
+    pub section_syntax_indicator: bool,
+    pub section_length: u16,
+    pub table_id_extension: u16,
+    pub version_number: u8,
+    pub current_next_indicator: bool,
+    pub section_number: u8,
+    pub last_section_number: u8,
+    /// The ECM, which the CAS module reads as it is.
+    pub ecm_data: Vec<u8>,
+    pub crc_32: u32,
+}
+
+impl Ecm {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let head = bytes.try_get_u16()?;
+        let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
+        let section_length = head & 0x0FFF;
+        let table_id_extension = bytes.try_get_u16()?;
+
+        let head = bytes.try_get_u8()?;
+        let version_number = (head & 0b0011_1110) >> 1;
+        let current_next_indicator = (head & 0b0000_0001) == 1;
+
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+
+        if bytes.remaining() < 4 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let ecm_data = bytes.split_to(bytes.remaining() - 4).to_vec();
+
+        let crc_32 = bytes.try_get_u32()?;
+
+        Ok(Self {
+            section_syntax_indicator,
+            section_length,
+            table_id_extension,
+            version_number,
+            current_next_indicator,
+            section_number,
+            last_section_number,
+            ecm_data,
+            crc_32,
+        })
+    }
+}
+
+/// EMM (Entitlement Management Message) table, carried in the CA message.
+#[derive(Clone, Debug)]
+pub struct Emm {
+    pub section_syntax_indicator: bool,
+    pub section_length: u16,
+    pub table_id_extension: u16,
+    pub version_number: u8,
+    pub current_next_indicator: bool,
+    pub section_number: u8,
+    pub last_section_number: u8,
+    /// The EMMs of the section, each led by the id of the card it is addressed to, which the
+    /// CAS module reads as they are.
+    pub emm_data: Vec<u8>,
+    pub crc_32: u32,
+}
+
+impl Emm {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let head = bytes.try_get_u16()?;
+        let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
+        let section_length = head & 0x0FFF;
+        let table_id_extension = bytes.try_get_u16()?;
+
+        let head = bytes.try_get_u8()?;
+        let version_number = (head & 0b0011_1110) >> 1;
+        let current_next_indicator = (head & 0b0000_0001) == 1;
+
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+
+        if bytes.remaining() < 4 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let emm_data = bytes.split_to(bytes.remaining() - 4).to_vec();
+
+        // TODO: Verify CRC
+        let crc_32 = bytes.try_get_u32()?;
+
+        Ok(Self {
+            section_syntax_indicator,
+            section_length,
+            table_id_extension,
+            version_number,
+            current_next_indicator,
+            section_number,
+            last_section_number,
+            emm_data,
+            crc_32,
+        })
+    }
+}
+
 const MPT_ID: u8 = 0x20;
 const PLT_ID: u8 = 0x80;
+const ECM_ID: u8 = 0x82;
+const EMM_ID: u8 = 0x84;
 const MH_EIT_ID: u8 = 0x8B;
 const MH_EIT_SCHEDULE_ID_START: u8 = 0x8C;
 const MH_EIT_SCHEDULE_ID_END: u8 = 0x9B;
@@ -797,12 +913,19 @@ pub enum Table {
     MhBit(MhBit),
     MhSdt(MhSdt),
     MhSit(MhSit),
+    Ecm(Ecm),
+    Emm(Emm),
     Unknown(u8, Vec<u8>),
 }
 
 impl Table {
+    /// Reads a table, after checking the CRC_32 of the ECMs, which the CAS module is given as
+    /// they are.
     pub fn read(bytes: &mut Bytes) -> Result<Self> {
-        let table_id = bytes.get_u8();
+        if bytes.first() == Some(&ECM_ID) {
+            verify_crc_32(bytes)?;
+        }
+        let table_id = bytes.try_get_u8()?;
 
         Ok(match table_id {
             MPT_ID => Self::Mpt(Mpt::read(bytes)?),
@@ -811,8 +934,10 @@ impl Table {
                 Self::MhEit(MhEit::read(table_id, bytes)?)
             }
             MH_BIT_ID => Self::MhBit(MhBit::read(bytes)?),
-            MH_SDT_ID | MH_SDT_OTHER_ID => Self::MhSdt(MhSdt::read(bytes)?),
+            MH_SDT_ID | MH_SDT_OTHER_ID => Self::MhSdt(MhSdt::read(table_id, bytes)?),
             MH_SIT_ID => Self::MhSit(MhSit::read(bytes)?),
+            ECM_ID => Self::Ecm(Ecm::read(bytes)?),
+            EMM_ID => Self::Emm(Emm::read(bytes)?),
             _ => Self::Unknown(table_id, bytes.to_vec()),
         })
     }
@@ -826,7 +951,7 @@ mod tests {
     #[test]
     fn test_parse_start_time() {
         assert_eq!(
-            parse_start_time([0xC0, 0x79, 0x12, 0x45, 0x00]),
+            parse_start_time([0xC0, 0x79, 0x12, 0x45, 0x00]).unwrap(),
             Some(NaiveDateTime::new(
                 NaiveDate::from_ymd_opt(1993, 10, 13).unwrap(),
                 NaiveTime::from_hms_opt(12, 45, 0).unwrap()
@@ -834,6 +959,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_ecm() {
+        let mut section = [
+            &[0x82, 0xB0, 0x0D, 0x00, 0x00, 0xC1, 0x00, 0x00][..],
+            &[0x93, 0x2D, 0x1E, 0x01],
+        ]
+        .concat();
+        let crc_32 = chibitv_b10::crc_32(&section);
+        section.extend(crc_32.to_be_bytes());
+
+        let Table::Ecm(ecm) = Table::read(&mut Bytes::from(section.clone())).unwrap() else {
+            panic!("not an ECM");
+        };
+        assert_eq!(ecm.ecm_data, [0x93, 0x2D, 0x1E, 0x01]);
+        assert_eq!(ecm.crc_32, crc_32);
+
+        // The same ECM with a bit flipped on the way.
+        section[9] ^= 0x01;
+        let error = Table::read(&mut Bytes::from(section)).unwrap_err();
+        assert!(
+            error
+                .get_ref()
+                .and_then(|error| error.downcast_ref::<chibitv_b10::Error>())
+                .is_some_and(chibitv_b10::Error::is_crc_mismatch)
+        );
+    }
+
     #[test]
     fn test_parse_duration() {
         let duration = parse_duration([0x01, 0x45, 0x30]).unwrap();
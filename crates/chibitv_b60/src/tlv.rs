@@ -1,4 +1,4 @@
-use std::io::{Read, Result};
+use std::io::{ErrorKind, Read, Result};
 
 use byteorder::{BE, ReadBytesExt};
 use bytes::Bytes;
@@ -23,7 +23,9 @@ pub struct TlvPacket {
 impl TlvPacket {
     pub fn try_read(mut reader: impl Read) -> Result<Option<Self>> {
         let head = reader.read_u8()?;
-        assert_eq!(head, 0x7F);
+        if head != 0x7F {
+            return Err(ErrorKind::InvalidData.into());
+        }
 
         let packet_type = reader.read_u8()?;
         let Some(packet_type) = TlvPacketType::from_repr(packet_type) else {
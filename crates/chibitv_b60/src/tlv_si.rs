@@ -0,0 +1,402 @@
+//! The TLV-SI, the tables of the TLV stream itself sent in transmission control signal TLV
+//! packets, one section each. Their descriptors are those of ARIB STD-B10.
+
+use std::io::{Error, ErrorKind, Result};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use bytes::{Buf, Bytes};
+use chibitv_b10::descriptor::Descriptor;
+
+use crate::read_ext::BytesExt;
+
+fn read_descriptors(bytes: &mut Bytes, length: usize) -> Result<Vec<Descriptor>> {
+    let mut bytes = bytes.try_split_to(length)?;
+    let mut descriptors = Vec::new();
+    while bytes.has_remaining() {
+        descriptors.push(
+            Descriptor::read(&mut bytes)
+                .map_err(|error| Error::new(ErrorKind::InvalidData, error))?,
+        );
+    }
+
+    Ok(descriptors)
+}
+
+#[derive(Clone, Debug)]
+pub struct TlvStreamInformation {
+    pub tlv_stream_id: u16,
+    pub original_network_id: u16,
+    pub descriptors: Vec<Descriptor>,
+}
+
+impl TlvStreamInformation {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let tlv_stream_id = bytes.try_get_u16()?;
+        let original_network_id = bytes.try_get_u16()?;
+        let tlv_stream_descriptors_length = bytes.try_get_u16()? & 0x0FFF;
+        let descriptors = read_descriptors(bytes, tlv_stream_descriptors_length as usize)?;
+
+        Ok(Self {
+            tlv_stream_id,
+            original_network_id,
+            descriptors,
+        })
+    }
+}
+
+/// TLV-NIT (Network Information Table), describing the TLV streams of a network and where they
+/// are transmitted.
+#[derive(Clone, Debug)]
+pub struct TlvNit {
+    pub table_id: u8,
+    pub section_syntax_indicator: bool,
+    pub section_length: u16,
+    pub network_id: u16,
+    pub version_number: u8,
+    pub current_next_indicator: bool,
+    pub section_number: u8,
+    pub last_section_number: u8,
+    pub network_descriptors: Vec<Descriptor>,
+    pub tlv_streams: Vec<TlvStreamInformation>,
+    pub crc_32: u32,
+}
+
+impl TlvNit {
+    pub fn read(table_id: u8, bytes: &mut Bytes) -> Result<Self> {
+        let head = bytes.try_get_u16()?;
+        let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
+        let section_length = head & 0x0FFF;
+        let network_id = bytes.try_get_u16()?;
+
+        let head = bytes.try_get_u8()?;
+        let version_number = (head & 0b0011_1110) >> 1;
+        let current_next_indicator = (head & 0b0000_0001) == 1;
+
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+
+        let network_descriptors_length = bytes.try_get_u16()? & 0x0FFF;
+        let network_descriptors = read_descriptors(bytes, network_descriptors_length as usize)?;
+
+        let tlv_stream_loop_length = bytes.try_get_u16()? & 0x0FFF;
+        let mut stream_bytes = bytes.try_split_to(tlv_stream_loop_length as usize)?;
+        let mut tlv_streams = Vec::new();
+        while stream_bytes.has_remaining() {
+            tlv_streams.push(TlvStreamInformation::read(&mut stream_bytes)?);
+        }
+
+        let crc_32 = bytes.try_get_u32()?;
+
+        Ok(Self {
+            table_id,
+            section_syntax_indicator,
+            section_length,
+            network_id,
+            version_number,
+            current_next_indicator,
+            section_number,
+            last_section_number,
+            network_descriptors,
+            tlv_streams,
+            crc_32,
+        })
+    }
+}
+
+/// The IP flows of a service, as source and destination addresses with their prefix lengths.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IpAddressInformation {
+    Ipv4 {
+        src_addr: Ipv4Addr,
+        src_mask: u8,
+        dst_addr: Ipv4Addr,
+        dst_mask: u8,
+    },
+    Ipv6 {
+        src_addr: Ipv6Addr,
+        src_mask: u8,
+        dst_addr: Ipv6Addr,
+        dst_mask: u8,
+    },
+}
+
+impl IpAddressInformation {
+    pub fn read(bytes: &mut Bytes, ip_version: bool) -> Result<Self> {
+        Ok(if ip_version {
+            let src_addr = bytes.try_get_ipv6_addr()?;
+            let src_mask = bytes.try_get_u8()?;
+            let dst_addr = bytes.try_get_ipv6_addr()?;
+            let dst_mask = bytes.try_get_u8()?;
+
+            Self::Ipv6 {
+                src_addr,
+                src_mask,
+                dst_addr,
+                dst_mask,
+            }
+        } else {
+            let src_addr = bytes.try_get_ipv4_addr()?;
+            let src_mask = bytes.try_get_u8()?;
+            let dst_addr = bytes.try_get_ipv4_addr()?;
+            let dst_mask = bytes.try_get_u8()?;
+
+            Self::Ipv4 {
+                src_addr,
+                src_mask,
+                dst_addr,
+                dst_mask,
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AddressMapService {
+    pub service_id: u16,
+    pub address: IpAddressInformation,
+    pub private_data: Vec<u8>,
+}
+
+impl AddressMapService {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let service_id = bytes.try_get_u16()?;
+        let head = bytes.try_get_u16()?;
+        let ip_version = ((head & 0x8000) >> 15) == 1;
+        let service_loop_length = head & 0x03FF;
+
+        let mut service_bytes = bytes.try_split_to(service_loop_length as usize)?;
+        let address = IpAddressInformation::read(&mut service_bytes, ip_version)?;
+        let private_data = service_bytes.to_vec();
+
+        Ok(Self {
+            service_id,
+            address,
+            private_data,
+        })
+    }
+}
+
+/// AMT (Address Map Table), telling which IP flows carry each service of the TLV stream.
+#[derive(Clone, Debug)]
+pub struct Amt {
+    pub section_syntax_indicator: bool,
+    pub section_length: u16,
+    pub table_id_extension: u16,
+    pub version_number: u8,
+    pub current_next_indicator: bool,
+    pub section_number: u8,
+    pub last_section_number: u8,
+    pub services: Vec<AddressMapService>,
+    pub crc_32: u32,
+}
+
+impl Amt {
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        let head = bytes.try_get_u16()?;
+        let section_syntax_indicator = ((head & 0x8000) >> 15) == 1;
+        let section_length = head & 0x0FFF;
+        let table_id_extension = bytes.try_get_u16()?;
+
+        let head = bytes.try_get_u8()?;
+        let version_number = (head & 0b0011_1110) >> 1;
+        let current_next_indicator = (head & 0b0000_0001) == 1;
+
+        let section_number = bytes.try_get_u8()?;
+        let last_section_number = bytes.try_get_u8()?;
+
+        let num_of_service_id = (bytes.try_get_u16()? & 0xFFC0) >> 6;
+        let mut services = Vec::new();
+        for _ in 0..num_of_service_id {
+            services.push(AddressMapService::read(bytes)?);
+        }
+
+        let crc_32 = bytes.try_get_u32()?;
+
+        Ok(Self {
+            section_syntax_indicator,
+            section_length,
+            table_id_extension,
+            version_number,
+            current_next_indicator,
+            section_number,
+            last_section_number,
+            services,
+            crc_32,
+        })
+    }
+}
+
+const TLV_NIT_ACTUAL_ID: u8 = 0x40;
+const TLV_NIT_OTHER_ID: u8 = 0x41;
+const AMT_ID: u8 = 0xFE;
+
+#[derive(Clone, Debug)]
+pub enum TlvSiTable {
+    TlvNit(TlvNit),
+    Amt(Amt),
+    Unknown(u8, Vec<u8>),
+}
+
+impl TlvSiTable {
+    /// Reads a table, after checking the CRC_32 of the TLV-NITs and AMTs.
+    pub fn read(bytes: &mut Bytes) -> Result<Self> {
+        if let Some(&(TLV_NIT_ACTUAL_ID | TLV_NIT_OTHER_ID | AMT_ID)) = bytes.first() {
+            verify_crc_32(bytes)?;
+        }
+        let table_id = bytes.try_get_u8()?;
+
+        Ok(match table_id {
+            TLV_NIT_ACTUAL_ID | TLV_NIT_OTHER_ID => Self::TlvNit(TlvNit::read(table_id, bytes)?),
+            AMT_ID => Self::Amt(Amt::read(bytes)?),
+            _ => Self::Unknown(table_id, bytes.to_vec()),
+        })
+    }
+}
+
+/// Checks the CRC_32 at the end of the section the bytes start with, as far as its
+/// section_length reaches.
+pub(crate) fn verify_crc_32(bytes: &[u8]) -> Result<()> {
+    let Some(&[_, high, low]) = bytes.first_chunk::<3>() else {
+        return Err(ErrorKind::UnexpectedEof.into());
+    };
+    let section_length = usize::from(u16::from_be_bytes([high, low]) & 0x0FFF);
+    let section = bytes
+        .get(..3 + section_length)
+        .ok_or(ErrorKind::UnexpectedEof)?;
+    let Some((body, crc_32)) = section.split_last_chunk::<4>() else {
+        return Err(ErrorKind::InvalidData.into());
+    };
+    let expected = chibitv_b10::crc_32(body);
+    let actual = u32::from_be_bytes(*crc_32);
+    if expected != actual {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            chibitv_b10::Error::CrcMismatch { expected, actual },
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chibitv_b10::descriptor::{
+        NetworkNameDescriptor, SatelliteDeliverySystemDescriptor, ServiceListDescriptor,
+        ServiceListItem,
+    };
+
+    use super::*;
+
+    #[test]
+    fn reads_the_tlv_nit() {
+        let section = [
+            &[0x40, 0xF0, 0x2A, 0x00, 0x04, 0xC1, 0x00, 0x00][..],
+            // network_descriptors_length, network name descriptor
+            &[0xF0, 0x05, 0x40, 0x03, b'B', b'S', b'4'],
+            // TLV_stream_loop_length, TLV_stream_id, original_network_id
+            &[0xF0, 0x18, 0x00, 0x01, 0x00, 0x04],
+            // TLV_stream_descriptors_length, satellite delivery system descriptor
+            &[0xF0, 0x12, 0x43, 0x0B],
+            &[
+                0x01, 0x17, 0x27, 0x48, 0x11, 0x00, 0xC9, 0x03, 0x37, 0x60, 0x03,
+            ],
+            // service list descriptor
+            &[0x41, 0x03, 0x00, 0x65, 0x01],
+            &[0xE1, 0xE0, 0x97, 0x23],
+        ]
+        .concat();
+
+        let TlvSiTable::TlvNit(nit) = TlvSiTable::read(&mut Bytes::from(section)).unwrap() else {
+            panic!("not a TLV-NIT");
+        };
+        assert_eq!(nit.network_id, 0x0004);
+        assert_eq!(
+            nit.network_descriptors,
+            [Descriptor::NetworkName(NetworkNameDescriptor {
+                network_name: b"BS4".to_vec(),
+            })]
+        );
+        assert_eq!(nit.tlv_streams.len(), 1);
+        assert_eq!(nit.tlv_streams[0].tlv_stream_id, 0x0001);
+        assert_eq!(
+            nit.tlv_streams[0].descriptors,
+            [
+                Descriptor::SatelliteDeliverySystem(SatelliteDeliverySystemDescriptor {
+                    frequency: 1_172_748,
+                    orbital_position: 1100,
+                    west_east_flag: true,
+                    polarisation: 2,
+                    modulation: 9,
+                    symbol_rate: 337_600,
+                    fec_inner: 3,
+                }),
+                Descriptor::ServiceList(ServiceListDescriptor {
+                    services: vec![ServiceListItem {
+                        service_id: 0x0065,
+                        service_type: 0x01,
+                    }],
+                }),
+            ]
+        );
+        assert_eq!(nit.crc_32, 0xE1E0_9723);
+    }
+
+    #[test]
+    fn reads_the_amt() {
+        let mut section = vec![0xFE, 0xB0, 0x40, 0xFF, 0xFF, 0xC1, 0x00, 0x00];
+        // num_of_service_id
+        section.extend([0x00, 0x80]);
+        // service_id, IP_version, service_loop_length
+        section.extend([0x00, 0x65, 0x80, 0x22]);
+        section.extend(Ipv6Addr::new(0x2001, 0xDB8, 0, 0, 0, 0, 0, 1).octets());
+        section.push(128);
+        section.extend(Ipv6Addr::new(0xFF0E, 0, 0, 0, 0, 0, 0, 0x0101).octets());
+        section.push(128);
+        // service_id, IP_version, service_loop_length
+        section.extend([0x00, 0x66, 0x00, 0x0B]);
+        section.extend([192, 168, 0, 1, 32, 239, 0, 0, 1, 32, 0xAA]);
+        section.extend([0x7E, 0xC2, 0x5D, 0x5D]);
+
+        let TlvSiTable::Amt(amt) = TlvSiTable::read(&mut Bytes::from(section.clone())).unwrap()
+        else {
+            panic!("not an AMT");
+        };
+        assert_eq!(
+            amt.services,
+            [
+                AddressMapService {
+                    service_id: 0x0065,
+                    address: IpAddressInformation::Ipv6 {
+                        src_addr: Ipv6Addr::new(0x2001, 0xDB8, 0, 0, 0, 0, 0, 1),
+                        src_mask: 128,
+                        dst_addr: Ipv6Addr::new(0xFF0E, 0, 0, 0, 0, 0, 0, 0x0101),
+                        dst_mask: 128,
+                    },
+                    private_data: vec![],
+                },
+                AddressMapService {
+                    service_id: 0x0066,
+                    address: IpAddressInformation::Ipv4 {
+                        src_addr: Ipv4Addr::new(192, 168, 0, 1),
+                        src_mask: 32,
+                        dst_addr: Ipv4Addr::new(239, 0, 0, 1),
+                        dst_mask: 32,
+                    },
+                    private_data: vec![0xAA],
+                },
+            ]
+        );
+        assert_eq!(amt.crc_32, 0x7EC2_5D5D);
+
+        // A bit flipped on the way.
+        section[20] ^= 0x01;
+        let error = TlvSiTable::read(&mut Bytes::from(section)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(
+            error
+                .get_ref()
+                .and_then(|error| error.downcast_ref::<chibitv_b10::Error>())
+                .is_some_and(chibitv_b10::Error::is_crc_mismatch)
+        );
+    }
+}
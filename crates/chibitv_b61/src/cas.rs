@@ -2,7 +2,7 @@
 //! with the module.
 
 use std::fmt::{Debug, Formatter};
-use std::io::{Cursor, ErrorKind, Read, Result};
+use std::io::{Cursor, Error, ErrorKind, Read, Result};
 use std::sync::Arc;
 
 use apdu_core::{Command, Response};
@@ -144,6 +144,55 @@ impl EcmReceptionResponse {
     }
 }
 
+#[derive(Clone, Debug)]
+pub(crate) struct EmmReceptionCommand {
+    pub(crate) emm: Vec<u8>,
+}
+
+impl EmmReceptionCommand {
+    fn write(&self, buf: &mut [u8]) -> usize {
+        let cmd = Command::new_with_payload_le(0x90, 0x36, 0x00, 0x01, 0x00, &self.emm);
+        cmd.write(buf);
+        cmd.len()
+    }
+}
+
+#[derive(Clone, Debug)]
+#[allow(dead_code)]
+pub(crate) struct EmmReceptionResponse {
+    pub(crate) unit_length: u8,
+    pub(crate) cas_module_instruction: u16,
+    pub(crate) return_code: u16,
+}
+
+impl EmmReceptionResponse {
+    fn read(buf: &[u8]) -> Result<Self> {
+        let response = Response::from(buf);
+        if !response.is_ok() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "CAS module did not complete the EMM reception",
+            ));
+        }
+
+        let mut reader = Cursor::new(response.payload);
+
+        let protocol_unit_number = reader.read_u8()?;
+        if protocol_unit_number != 0x00 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "EMM reception response is of an unknown protocol unit",
+            ));
+        }
+
+        Ok(Self {
+            unit_length: reader.read_u8()?,
+            cas_module_instruction: reader.read_u16::<BE>()?,
+            return_code: reader.read_u16::<BE>()?,
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ScramblingKeyProtectionSettingCommand {
     pub(crate) setting_data: Vec<u8>,
@@ -195,6 +244,10 @@ impl ScramblingKeyProtectionSettingResponse {
 /// ARIB STD-B61 commands executed on a physical CAS module.
 pub(crate) struct CasClient {
     module: Arc<dyn CasModule>,
+    /// The generation of the module the initial setting was last made on.
+    generation: u64,
+    /// The id of the module the initial setting was last made on.
+    cas_module_id: [u8; 6],
     tx_buf: Vec<u8>,
     rx_buf: Vec<u8>,
 }
@@ -208,18 +261,47 @@ impl Debug for CasClient {
 impl CasClient {
     pub fn new(module: Arc<dyn CasModule>) -> Self {
         Self {
+            generation: module.generation(),
             module,
+            cas_module_id: [0; 6],
             tx_buf: vec![0u8; 2048],
             rx_buf: vec![0u8; 4096],
         }
     }
 
+    /// Whether the module has been reconnected since the initial setting was made.
+    pub fn is_reconnected(&self) -> bool {
+        self.module.generation() != self.generation
+    }
+
     pub fn initial_setting_condition(&mut self) -> anyhow::Result<InitialSettingConditionResponse> {
+        self.generation = self.module.generation();
         let len = InitialSettingConditionCommand.write(&mut self.tx_buf);
         let response_len = self
             .module
             .transmit(&self.tx_buf[..len], &mut self.rx_buf)?;
         let response = InitialSettingConditionResponse::read(&self.rx_buf[..response_len])?;
+        self.cas_module_id = response.cas_module_id;
+
+        Ok(response)
+    }
+
+    pub fn cas_module_id(&self) -> [u8; 6] {
+        self.cas_module_id
+    }
+
+    /// The A0init the module asks the scrambling key of the ECM to be protected with, if any.
+    pub fn a0_init(&self, ecm: &[u8]) -> Option<[u8; 8]> {
+        self.module.a0_init(ecm)
+    }
+
+    pub fn emm_reception(&mut self, emm: &[u8]) -> anyhow::Result<EmmReceptionResponse> {
+        let cmd = EmmReceptionCommand { emm: emm.to_vec() };
+        let len = cmd.write(&mut self.tx_buf);
+        let response_len = self
+            .module
+            .transmit(&self.tx_buf[..len], &mut self.rx_buf)?;
+        let response = EmmReceptionResponse::read(&self.rx_buf[..response_len])?;
 
         Ok(response)
     }
@@ -306,4 +388,18 @@ mod tests {
         assert_eq!(setting.setting_response_data, [0x01, 0x02]);
         assert_eq!(ecm.ks, [0x03; 32]);
     }
+
+    #[test]
+    fn fails_on_an_emm_the_module_did_not_complete() {
+        let response = [0x00, 0x04, 0x00, 0x36, 0x21, 0x00, 0x90, 0x00];
+        assert_eq!(
+            EmmReceptionResponse::read(&response).unwrap().return_code,
+            0x2100
+        );
+
+        // A status word other than 90 00, and a response of another protocol unit.
+        assert!(EmmReceptionResponse::read(&[0x6A, 0x86]).is_err());
+        let response = [0x01, 0x04, 0x00, 0x36, 0x21, 0x00, 0x90, 0x00];
+        assert!(EmmReceptionResponse::read(&response).is_err());
+    }
 }
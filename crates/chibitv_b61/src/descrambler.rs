@@ -3,17 +3,19 @@ use std::fmt::{Debug, Display, Formatter};
 use std::sync::{Arc, Mutex, mpsc};
 
 use aes::Aes128;
-use anyhow::{Result, anyhow};
-use bytes::{Buf, Bytes};
+use anyhow::{Result, anyhow, ensure};
 use chibitv_b60::mmtp::MmtpPacket;
+use chibitv_cas::{EmmHistory, KeyCache};
 use ctr::Ctr128BE;
 use ctr::cipher::{KeyIvInit, StreamCipher};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use sha2::{Digest, Sha256};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::cas::CasClient;
+use crate::key_log::{DecryptionKey, KeyLog, KeyStore};
+use crate::scrambling::ScramblingControl;
 use crate::{CasModule, EncryptionFlag};
 
 #[derive(Copy, Clone, Debug)]
@@ -27,20 +29,17 @@ impl Display for NoDecryptionKeyError {
 
 impl Error for NoDecryptionKeyError {}
 
-#[derive(Clone, Debug)]
-struct DecryptionKey {
-    odd: [u8; 16],
-    even: [u8; 16],
-}
-
 fn decrypt_ecm(
     cas: &mut CasClient,
     master_key: &[u8; 32],
     rng: &mut StdRng,
     ecm: [u8; 148],
 ) -> Result<DecryptionKey> {
-    let mut a0_init = [0u8; 8];
-    rng.fill_bytes(&mut a0_init);
+    let a0_init = cas.a0_init(&ecm).unwrap_or_else(|| {
+        let mut a0_init = [0u8; 8];
+        rng.fill_bytes(&mut a0_init);
+        a0_init
+    });
 
     let setting_data = [
         &[0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x8A, 0xF7],
@@ -53,7 +52,10 @@ fn decrypt_ecm(
     let (a0_response, a0_hash) = setting_response.setting_response_data.split_at(8);
     let kcl = Sha256::digest([&master_key[..], &a0_init[..], a0_response].concat());
     let hash = Sha256::digest([&kcl, &a0_init[..]].concat());
-    assert_eq!(hash.as_slice(), a0_hash);
+    ensure!(
+        hash.as_slice() == a0_hash,
+        "CAS module answered the scrambling key protection setting with a wrong A0hash"
+    );
 
     let ecm_init = &ecm[0x04..0x1B];
     let mut hash = Sha256::digest([&kcl, ecm_init].concat()).to_vec();
@@ -74,49 +76,213 @@ fn decrypt_ecm(
     })
 }
 
-type EcmSender = mpsc::SyncSender<[u8; 148]>;
+/// What a descrambler asks of its CAS worker.
+enum CasRequest {
+    Ecm([u8; 148]),
+    /// The payload of an EMM section.
+    Emm(Vec<u8>),
+}
+
+type RequestSender = mpsc::SyncSender<CasRequest>;
 type KeyReceiver = mpsc::Receiver<([u8; 148], Result<DecryptionKey>)>;
 
+/// Decrypts the ECM, making the initial setting again whenever the module has been reconnected
+/// to its card, and sending an ECM the reconnection interrupted once more.
+fn decrypt_ecm_reconnecting(
+    cas: &mut CasClient,
+    master_key: &[u8; 32],
+    rng: &mut StdRng,
+    ecm: [u8; 148],
+) -> Result<DecryptionKey> {
+    if cas.is_reconnected() {
+        reinitialize(cas)?;
+    }
+
+    match decrypt_ecm(cas, master_key, rng, ecm) {
+        Err(error) if cas.is_reconnected() => {
+            warn!(%error, "ECM was interrupted by the CAS module reconnecting");
+            reinitialize(cas)?;
+            decrypt_ecm(cas, master_key, rng, ecm)
+        }
+        result => result,
+    }
+}
+
+fn reinitialize(cas: &mut CasClient) -> Result<()> {
+    let response = cas.initial_setting_condition()?;
+    info!(
+        cas_module_id = ?response.cas_module_id,
+        "CAS module was reconnected and initialized again"
+    );
+
+    Ok(())
+}
+
+/// Gives the module the EMMs of the section addressed to it that it has not been given yet.
+fn receive_emms(cas: &mut CasClient, history: &mut EmmHistory, payload: &[u8]) -> Result<()> {
+    if cas.is_reconnected() {
+        reinitialize(cas)?;
+    }
+
+    for emm in chibitv_cas::read_emms(payload) {
+        if emm.card_id != cas.cas_module_id() || !history.is_new(&emm) {
+            continue;
+        }
+
+        let response = cas.emm_reception(emm.data)?;
+        debug!(
+            broadcaster_group_id = emm.broadcaster_group_id,
+            update_number = emm.update_number,
+            return_code = response.return_code,
+            "EMM was received by the CAS module"
+        );
+        history.record(&emm);
+    }
+
+    Ok(())
+}
+
+/// Where the CAS worker of a descrambler gets the keys of the ECMs.
+enum KeySource {
+    Module {
+        cas: CasClient,
+        master_key: [u8; 32],
+        rng: StdRng,
+        emm_history: EmmHistory,
+        key_log: Option<Arc<dyn KeyLog>>,
+    },
+    Store(Arc<dyn KeyStore>),
+}
+
+impl KeySource {
+    fn decrypt_ecm(&mut self, ecm: [u8; 148]) -> Result<DecryptionKey> {
+        match self {
+            Self::Module {
+                cas,
+                master_key,
+                rng,
+                key_log,
+                ..
+            } => {
+                let key = decrypt_ecm_reconnecting(cas, master_key, rng, ecm)?;
+                if let Some(key_log) = key_log {
+                    key_log.log_key(&ecm, &key);
+                }
+
+                Ok(key)
+            }
+            Self::Store(store) => store
+                .key(&ecm)
+                .ok_or_else(|| anyhow!("No key of the ECM is stored")),
+        }
+    }
+
+    fn receive_emms(&mut self, payload: &[u8]) -> Result<()> {
+        match self {
+            Self::Module {
+                cas, emm_history, ..
+            } => receive_emms(cas, emm_history, payload),
+            // A keyless descrambler has no module to give them to.
+            Self::Store(_) => Ok(()),
+        }
+    }
+}
+
 /// High-level decoder implementation for descrambling payloads.
 #[derive(Clone, Debug)]
 pub struct Descrambler {
-    ecm_tx: EcmSender,
+    request_tx: RequestSender,
     key_rx: Arc<Mutex<KeyReceiver>>,
+    key_cache: &'static KeyCache<DecryptionKey>,
     key: Option<([u8; 148], DecryptionKey)>,
     is_async: bool,
 }
 
 impl Descrambler {
-    pub fn init(module: Arc<dyn CasModule>, master_key: [u8; 32], is_async: bool) -> Result<Self> {
+    /// Initializes the CAS module, writing every key decrypted with it to the key log and sharing
+    /// the keys with the other descramblers of the key cache.
+    pub fn init(
+        module: Arc<dyn CasModule>,
+        master_key: [u8; 32],
+        key_log: Option<Arc<dyn KeyLog>>,
+        key_cache: &'static KeyCache<DecryptionKey>,
+        is_async: bool,
+    ) -> Result<Self> {
         let mut cas = CasClient::new(module);
         let response = cas.initial_setting_condition()?;
         debug!("CAS module initialized: {:?}", response.cas_module_id);
-        let mut rng = StdRng::from_rng(&mut rand::rng());
 
-        let (ecm_tx, ecm_rx) = mpsc::sync_channel(16);
+        Ok(Self::spawn(
+            KeySource::Module {
+                cas,
+                master_key,
+                rng: StdRng::from_rng(&mut rand::rng()),
+                emm_history: EmmHistory::default(),
+                key_log,
+            },
+            key_cache,
+            is_async,
+        ))
+    }
+
+    /// Descrambles with the keys of the store instead of decrypting the ECMs.
+    pub fn init_keyless(
+        store: Arc<dyn KeyStore>,
+        key_cache: &'static KeyCache<DecryptionKey>,
+        is_async: bool,
+    ) -> Self {
+        Self::spawn(KeySource::Store(store), key_cache, is_async)
+    }
+
+    fn spawn(
+        mut source: KeySource,
+        key_cache: &'static KeyCache<DecryptionKey>,
+        is_async: bool,
+    ) -> Self {
+        let (request_tx, request_rx) = mpsc::sync_channel(16);
         let (key_tx, key_rx) = mpsc::sync_channel(16);
 
         std::thread::spawn(move || {
-            for ecm in ecm_rx {
-                let key = decrypt_ecm(&mut cas, &master_key, &mut rng, ecm);
-                if key_tx.send((ecm, key)).is_err() {
-                    break;
+            for request in request_rx {
+                match request {
+                    CasRequest::Ecm(ecm) => {
+                        let key = source.decrypt_ecm(ecm);
+                        if let Ok(key) = &key {
+                            key_cache.insert(&ecm, key.clone());
+                        }
+                        if key_tx.send((ecm, key)).is_err() {
+                            break;
+                        }
+                    }
+                    CasRequest::Emm(payload) => {
+                        if let Err(error) = source.receive_emms(&payload) {
+                            error!(%error, "Could not give the EMM to the CAS module");
+                        }
+                    }
                 }
             }
         });
 
-        Ok(Self {
-            ecm_tx,
+        Self {
+            request_tx,
             key_rx: Arc::new(Mutex::new(key_rx)),
+            key_cache,
             key: None,
             is_async,
-        })
+        }
     }
 
     /// Push an encrypted ECM to the decoder.
     /// The decoder attempts to decrypt the ECM using the CAS module.
     /// At least one ECM must be pushed before decrypting payloads.
+    ///
+    /// A key another descrambler of the key cache has decrypted from the same ECM is taken at once.
     pub fn push_ecm(&mut self, ecm: [u8; 148]) -> Result<()> {
+        if let Some(key) = self.key_cache.get(&ecm) {
+            self.key = Some((ecm, key));
+            return Ok(());
+        }
+
         if let Some((e, _)) = self.key.as_ref()
             && *e == ecm
         {
@@ -124,7 +290,7 @@ impl Descrambler {
         }
 
         if self.is_async {
-            match self.ecm_tx.try_send(ecm) {
+            match self.request_tx.try_send(CasRequest::Ecm(ecm)) {
                 Ok(()) | Err(mpsc::TrySendError::Full(_)) => {}
                 Err(mpsc::TrySendError::Disconnected(_)) => {
                     return Err(anyhow!("CAS worker is not running"));
@@ -134,48 +300,39 @@ impl Descrambler {
             return Ok(());
         }
 
-        self.ecm_tx
-            .send(ecm)
+        self.request_tx
+            .send(CasRequest::Ecm(ecm))
             .map_err(|_| anyhow!("CAS worker is not running"))?;
 
         self.recv_key(true)
     }
 
+    /// Push the payload of an EMM section to the decoder.
+    /// The CAS module is given the EMMs addressed to it in the background, and an EMM the worker
+    /// is too busy for is left to the next time it is broadcast.
+    pub fn push_emm(&mut self, payload: &[u8]) -> Result<()> {
+        match self.request_tx.try_send(CasRequest::Emm(payload.to_vec())) {
+            Ok(()) | Err(mpsc::TrySendError::Full(_)) => Ok(()),
+            Err(mpsc::TrySendError::Disconnected(_)) => Err(anyhow!("CAS worker is not running")),
+        }
+    }
+
+    /// Descrambles the payload of the packet in place, failing with [`NoDecryptionKeyError`] before
+    /// the key arrives and with [`ScramblingExtensionError`](crate::ScramblingExtensionError) when
+    /// its scrambling control cannot be read.
     pub fn descramble(&mut self, mmtp_packet: &MmtpPacket, data: &mut [u8]) -> Result<()> {
         if self.is_async {
             self.recv_key(false)?;
         }
 
-        let encryption_flag = mmtp_packet
-            .extension_header
-            .as_ref()
-            .and_then(|header| {
-                assert_eq!(header.header_type, 0x0000);
-
-                let mut reader = Bytes::copy_from_slice(&header.data);
-                let extension_type = reader.get_u16();
-                if (extension_type & 0x7FFF) != 0x0001 {
-                    return None;
-                }
-
-                let extension_length = reader.get_u16();
-                assert_eq!(extension_length, 1);
-
-                let extension_payload = reader.get_u8();
-                assert_eq!(extension_payload & 0b0000_0010, 0); // MAC
-                assert_eq!(extension_payload & 0b0000_0001, 0); // SICV
-
-                EncryptionFlag::from_repr((extension_payload & 0b0001_1000) >> 3)
-            })
-            .unwrap_or(EncryptionFlag::Unscrambled);
-
-        let key = match encryption_flag {
+        let control = ScramblingControl::read(mmtp_packet.extension_header.as_ref())?;
+        let key = match control.encryption_flag {
             EncryptionFlag::Even | EncryptionFlag::Odd => {
                 let Some((_, key)) = self.key.clone() else {
                     return Err(NoDecryptionKeyError.into());
                 };
 
-                match encryption_flag {
+                match control.encryption_flag {
                     EncryptionFlag::Even => key.even,
                     EncryptionFlag::Odd => key.odd,
                     _ => unreachable!(),
@@ -184,12 +341,15 @@ impl Descrambler {
             _ => return Ok(()),
         };
 
-        let iv = [
-            &mmtp_packet.packet_id.to_be_bytes()[..],
-            &mmtp_packet.packet_sequence_number.to_be_bytes()[..],
-            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-        ]
-        .concat();
+        let iv = match control.scrambling_initial_counter_value {
+            Some(sicv) => sicv.to_vec(),
+            None => [
+                &mmtp_packet.packet_id.to_be_bytes()[..],
+                &mmtp_packet.packet_sequence_number.to_be_bytes()[..],
+                &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            ]
+            .concat(),
+        };
 
         let mut ctr = Ctr128BE::<Aes128>::new_from_slices(&key, &iv)?;
 
@@ -0,0 +1,19 @@
+//! Exporting the keys a descrambler decrypts from the ECMs, and descrambling with them later
+//! without a CAS module.
+
+/// The odd and even keys an ECM carries.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DecryptionKey {
+    pub odd: [u8; 16],
+    pub even: [u8; 16],
+}
+
+/// Receives every key a descrambler decrypts, in the spirit of `SSLKEYLOGFILE`.
+pub trait KeyLog: Send + Sync {
+    fn log_key(&self, ecm: &[u8; 148], key: &DecryptionKey);
+}
+
+/// Gives a keyless descrambler the keys a [`KeyLog`] has received, in place of the CAS module.
+pub trait KeyStore: Send + Sync {
+    fn key(&self, ecm: &[u8; 148]) -> Option<DecryptionKey>;
+}
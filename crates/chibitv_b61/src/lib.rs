@@ -2,8 +2,12 @@
 
 mod cas;
 mod descrambler;
+mod key_log;
+mod scrambling;
 
 pub use descrambler::{Descrambler, NoDecryptionKeyError};
+pub use key_log::{DecryptionKey, KeyLog, KeyStore};
+pub use scrambling::{ScramblingControl, ScramblingExtensionError};
 
 /// A physical CAS module capable of executing ARIB STD-B61 commands.
 pub trait CasModule: Send + Sync {
@@ -12,6 +16,19 @@ pub trait CasModule: Send + Sync {
 
     /// Locks the module for a sequence of commands that must not be interleaved.
     fn lock(&self) -> anyhow::Result<Box<dyn CasModuleGuard + '_>>;
+
+    /// Counts the times the module has been reconnected to its card, which forgets the initial
+    /// setting. Modules that never reconnect stay at zero.
+    fn generation(&self) -> u64 {
+        0
+    }
+
+    /// The A0init to protect the scrambling key of the ECM with, which the descrambler draws at
+    /// random unless the module asks for one. A module replaying recorded commands asks for the
+    /// one they were recorded with, since the responses of the card depend on it.
+    fn a0_init(&self, _ecm: &[u8]) -> Option<[u8; 8]> {
+        None
+    }
 }
 
 /// Exclusive access to a CAS module for an arbitrary command sequence.
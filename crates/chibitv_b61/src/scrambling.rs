@@ -0,0 +1,224 @@
+//! The scrambling control of MMTP packets, carried in their header extension.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use bytes::{Buf, Bytes};
+use chibitv_b60::mmtp::MmtpExtensionHeader;
+
+use crate::EncryptionFlag;
+
+/// The header extension type holding a sequence of typed extensions.
+const MULTI_EXTENSION_HEADER_TYPE: u16 = 0x0000;
+const SCRAMBLING_EXTENSION_TYPE: u16 = 0x0001;
+
+const MAC_LENGTH: usize = 32;
+const SICV_LENGTH: usize = 16;
+
+/// The scrambling control extension of an MMTP packet (ARIB STD-B60).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScramblingControl {
+    pub encryption_flag: EncryptionFlag,
+    /// Authenticates the payload, which is descrambled without verifying it.
+    pub message_authentication_code: Option<[u8; MAC_LENGTH]>,
+    /// The counter the payload is scrambled from, in place of the one derived from the packet.
+    pub scrambling_initial_counter_value: Option<[u8; SICV_LENGTH]>,
+}
+
+impl ScramblingControl {
+    const UNSCRAMBLED: Self = Self {
+        encryption_flag: EncryptionFlag::Unscrambled,
+        message_authentication_code: None,
+        scrambling_initial_counter_value: None,
+    };
+
+    /// Reads the scrambling control of a packet, which is unscrambled without one.
+    pub fn read(
+        extension_header: Option<&MmtpExtensionHeader>,
+    ) -> Result<Self, ScramblingExtensionError> {
+        let Some(header) = extension_header else {
+            return Ok(Self::UNSCRAMBLED);
+        };
+        if header.header_type != MULTI_EXTENSION_HEADER_TYPE {
+            return Err(ScramblingExtensionError::UnknownHeaderType(
+                header.header_type,
+            ));
+        }
+
+        let mut reader = header.data.clone();
+        while reader.has_remaining() {
+            if reader.remaining() < 4 {
+                return Err(ScramblingExtensionError::Truncated);
+            }
+            let extension_type = reader.get_u16();
+            let extension_length = reader.get_u16() as usize;
+            if reader.remaining() < extension_length {
+                return Err(ScramblingExtensionError::Truncated);
+            }
+            let payload = reader.split_to(extension_length);
+
+            if extension_type & 0x7FFF == SCRAMBLING_EXTENSION_TYPE {
+                return Self::read_payload(payload);
+            }
+            // The last extension has the most significant bit of its type set.
+            if extension_type & 0x8000 != 0 {
+                break;
+            }
+        }
+
+        Ok(Self::UNSCRAMBLED)
+    }
+
+    fn read_payload(mut payload: Bytes) -> Result<Self, ScramblingExtensionError> {
+        if !payload.has_remaining() {
+            return Err(ScramblingExtensionError::Truncated);
+        }
+        let flags = payload.get_u8();
+        let encryption_flag = match EncryptionFlag::from_repr((flags & 0b0001_1000) >> 3) {
+            Some(EncryptionFlag::Reserved) | None => {
+                return Err(ScramblingExtensionError::ReservedEncryptionFlag);
+            }
+            Some(encryption_flag) => encryption_flag,
+        };
+        let has_mac = flags & 0b0000_0010 != 0;
+        let has_sicv = flags & 0b0000_0001 != 0;
+
+        let expected = usize::from(has_mac) * MAC_LENGTH + usize::from(has_sicv) * SICV_LENGTH;
+        if payload.remaining() != expected {
+            return Err(ScramblingExtensionError::LengthMismatch {
+                expected: expected + 1,
+                actual: payload.remaining() + 1,
+            });
+        }
+
+        let message_authentication_code = has_mac.then(|| {
+            let mut mac = [0; MAC_LENGTH];
+            payload.copy_to_slice(&mut mac);
+            mac
+        });
+        let scrambling_initial_counter_value = has_sicv.then(|| {
+            let mut sicv = [0; SICV_LENGTH];
+            payload.copy_to_slice(&mut sicv);
+            sicv
+        });
+
+        Ok(Self {
+            encryption_flag,
+            message_authentication_code,
+            scrambling_initial_counter_value,
+        })
+    }
+}
+
+/// The scrambling control of a packet could not be read, so its payload is left as it is.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScramblingExtensionError {
+    /// The header extension is of a type other than the one the scrambling control is carried in.
+    UnknownHeaderType(u16),
+    /// An extension runs past the end of the header extension.
+    Truncated,
+    /// The scrambling extension is not as long as its flags say.
+    LengthMismatch { expected: usize, actual: usize },
+    /// The encryption flag has its reserved value.
+    ReservedEncryptionFlag,
+}
+
+impl Display for ScramblingExtensionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownHeaderType(header_type) => {
+                write!(f, "Unknown MMTP header extension type {header_type:#06x}")
+            }
+            Self::Truncated => write!(f, "MMTP header extension is truncated"),
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "Scrambling extension is {actual} bytes long where {expected} bytes are expected"
+            ),
+            Self::ReservedEncryptionFlag => write!(f, "Encryption flag has the reserved value"),
+        }
+    }
+}
+
+impl Error for ScramblingExtensionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(header_type: u16, data: &[u8]) -> MmtpExtensionHeader {
+        MmtpExtensionHeader {
+            header_type,
+            data: Bytes::copy_from_slice(data),
+        }
+    }
+
+    #[test]
+    fn reads_the_scrambling_extension() {
+        assert_eq!(
+            ScramblingControl::read(None),
+            Ok(ScramblingControl::UNSCRAMBLED)
+        );
+
+        let even = header(0x0000, &[0x80, 0x01, 0x00, 0x01, 0b0001_0000]);
+        assert_eq!(
+            ScramblingControl::read(Some(&even))
+                .unwrap()
+                .encryption_flag,
+            EncryptionFlag::Even
+        );
+
+        // An extension of another type comes first, and the scrambling one has both a MAC and a
+        // SICV.
+        let mut data = vec![0x00, 0x02, 0x00, 0x02, 0xAA, 0xBB];
+        data.extend([0x80, 0x01, 0x00, 49, 0b0001_1011]);
+        data.extend([0x11; MAC_LENGTH]);
+        data.extend([0x22; SICV_LENGTH]);
+        let control = ScramblingControl::read(Some(&header(0x0000, &data))).unwrap();
+        assert_eq!(
+            control,
+            ScramblingControl {
+                encryption_flag: EncryptionFlag::Odd,
+                message_authentication_code: Some([0x11; MAC_LENGTH]),
+                scrambling_initial_counter_value: Some([0x22; SICV_LENGTH]),
+            }
+        );
+
+        let other = header(0x0000, &[0x80, 0x02, 0x00, 0x01, 0xFF]);
+        assert_eq!(
+            ScramblingControl::read(Some(&other)),
+            Ok(ScramblingControl::UNSCRAMBLED)
+        );
+    }
+
+    #[test]
+    fn rejects_what_cannot_be_read() {
+        let read = |header_type, data: &[u8]| {
+            ScramblingControl::read(Some(&header(header_type, data))).unwrap_err()
+        };
+
+        assert_eq!(
+            read(0x0001, &[]),
+            ScramblingExtensionError::UnknownHeaderType(0x0001)
+        );
+        assert_eq!(
+            read(0x0000, &[0x80, 0x01, 0x00]),
+            ScramblingExtensionError::Truncated
+        );
+        assert_eq!(
+            read(0x0000, &[0x80, 0x01, 0x00, 0x02, 0b0001_0000]),
+            ScramblingExtensionError::Truncated
+        );
+        // The SICV flag is set but the counter is missing.
+        assert_eq!(
+            read(0x0000, &[0x80, 0x01, 0x00, 0x01, 0b0001_0001]),
+            ScramblingExtensionError::LengthMismatch {
+                expected: 17,
+                actual: 1
+            }
+        );
+        assert_eq!(
+            read(0x0000, &[0x80, 0x01, 0x00, 0x01, 0b0000_1000]),
+            ScramblingExtensionError::ReservedEncryptionFlag
+        );
+    }
+}
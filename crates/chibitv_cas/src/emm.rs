@@ -0,0 +1,97 @@
+//! EMMs, which bring the card or CAS module they are addressed to the contracts and keys it is
+//! entitled to. Both standards carry them alike.
+
+use std::collections::BTreeMap;
+
+/// The length of the fields preceding the associated information of an EMM.
+const EMM_HEADER_LENGTH: usize = 7;
+/// The fields every EMM carries in its associated information.
+const EMM_FIXED_PART_LENGTH: usize = 6;
+
+/// An EMM of an EMM section.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Emm<'a> {
+    /// The id of the card, or of the CAS module of STD-B61, the EMM is addressed to.
+    pub card_id: [u8; 6],
+    pub protocol_number: u8,
+    pub broadcaster_group_id: u8,
+    pub update_number: u16,
+    /// The whole EMM, which is what the EMM reception command takes.
+    pub data: &'a [u8],
+}
+
+/// Splits the payload of an EMM section into its EMMs, stopping at one cut short.
+pub fn read_emms(mut payload: &[u8]) -> impl Iterator<Item = Emm<'_>> {
+    std::iter::from_fn(move || {
+        let associated_information_length = usize::from(*payload.get(6)?);
+        if associated_information_length < EMM_FIXED_PART_LENGTH {
+            return None;
+        }
+
+        let (data, rest) =
+            payload.split_at_checked(EMM_HEADER_LENGTH + associated_information_length)?;
+        payload = rest;
+
+        Some(Emm {
+            card_id: data[..6].try_into().unwrap(),
+            protocol_number: data[7],
+            broadcaster_group_id: data[8],
+            update_number: u16::from_be_bytes([data[9], data[10]]),
+            data,
+        })
+    })
+}
+
+/// The EMMs a card has been given, since each is broadcast over and over until it expires.
+#[derive(Debug, Default)]
+pub struct EmmHistory {
+    update_numbers: BTreeMap<([u8; 6], u8, u8), u16>,
+}
+
+impl EmmHistory {
+    /// Whether the EMM is one the card has not been given since it was last updated by its
+    /// broadcaster group.
+    pub fn is_new(&self, emm: &Emm) -> bool {
+        self.update_numbers.get(&Self::key(emm)) != Some(&emm.update_number)
+    }
+
+    pub fn record(&mut self, emm: &Emm) {
+        self.update_numbers
+            .insert(Self::key(emm), emm.update_number);
+    }
+
+    fn key(emm: &Emm) -> ([u8; 6], u8, u8) {
+        (emm.card_id, emm.protocol_number, emm.broadcaster_group_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_emms_of_a_section() {
+        let first = [
+            &[0x01; 6][..],
+            &[0x07, 0x10, 0x20, 0x00, 0x05, 0x12, 0x34, 0xFF],
+        ]
+        .concat();
+        let second = [&[0x02; 6][..], &[0x06, 0x10, 0x21, 0x00, 0x06, 0x12, 0x34]].concat();
+        let truncated = [&[0x03; 6][..], &[0x06, 0x10]].concat();
+        let payload = [&first[..], &second, &truncated].concat();
+
+        let emms = read_emms(&payload).collect::<Vec<_>>();
+        assert_eq!(emms.len(), 2);
+        assert_eq!(emms[0].card_id, [0x01; 6]);
+        assert_eq!(emms[0].broadcaster_group_id, 0x20);
+        assert_eq!(emms[0].update_number, 5);
+        assert_eq!(emms[0].data, first);
+        assert_eq!(emms[1].data, second);
+
+        let mut history = EmmHistory::default();
+        assert!(history.is_new(&emms[0]));
+        history.record(&emms[0]);
+        assert!(!history.is_new(&emms[0]));
+        assert!(history.is_new(&emms[1]));
+    }
+}
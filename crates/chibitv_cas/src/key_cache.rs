@@ -0,0 +1,121 @@
+//! Keys of the ECMs a card has answered, shared by the descramblers of a standard throughout the
+//! process so that a stream joining a network already being received starts with its key instead
+//! of a card transaction.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How long an ECM has to go unseen before its key period is taken to have rolled over.
+const KEY_PERIOD_GRACE: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+struct Entry<K> {
+    key: K,
+    last_seen: Instant,
+}
+
+#[derive(Debug)]
+struct Entries<K> {
+    entries: BTreeMap<Vec<u8>, Entry<K>>,
+}
+
+impl<K: Clone> Entries<K> {
+    fn get(&mut self, ecm: &[u8], now: Instant) -> Option<K> {
+        self.evict(now);
+        let entry = self.entries.get_mut(ecm)?;
+        entry.last_seen = now;
+
+        Some(entry.key.clone())
+    }
+
+    fn insert(&mut self, ecm: &[u8], key: K, now: Instant) {
+        self.evict(now);
+        self.entries.insert(
+            ecm.to_vec(),
+            Entry {
+                key,
+                last_seen: now,
+            },
+        );
+    }
+
+    /// Forgets the keys of the ECMs no longer broadcast.
+    fn evict(&mut self, now: Instant) {
+        self.entries
+            .retain(|_, entry| now.duration_since(entry.last_seen) < KEY_PERIOD_GRACE);
+    }
+}
+
+/// The keys of the ECMs descramblers have been given lately, which a process keeps one of for
+/// each standard in a `static`.
+#[derive(Debug)]
+pub struct KeyCache<K> {
+    entries: Mutex<Entries<K>>,
+}
+
+impl<K> KeyCache<K> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                entries: BTreeMap::new(),
+            }),
+        }
+    }
+}
+
+impl<K> Default for KeyCache<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone> KeyCache<K> {
+    /// The key of the ECM, if a descrambler has been given it lately.
+    pub fn get(&self, ecm: &[u8]) -> Option<K> {
+        self.entries().get(ecm, Instant::now())
+    }
+
+    pub fn insert(&self, ecm: &[u8], key: K) {
+        self.entries().insert(ecm, key, Instant::now());
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries<K>> {
+        self.entries.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_keys_once_their_ecm_is_no_longer_seen() {
+        let mut cache = Entries {
+            entries: BTreeMap::new(),
+        };
+        let start = Instant::now();
+
+        cache.insert(&[0x01], [0x11; 16], start);
+        cache.insert(&[0x02], [0x22; 16], start);
+        assert_eq!(
+            cache.get(&[0x01], start + Duration::from_secs(5)),
+            Some([0x11; 16])
+        );
+
+        let later = start + KEY_PERIOD_GRACE + Duration::from_secs(1);
+        assert_eq!(cache.get(&[0x01], later), Some([0x11; 16]));
+        assert_eq!(cache.get(&[0x02], later), None);
+        assert_eq!(cache.get(&[0x03], later), None);
+    }
+
+    #[test]
+    fn keeps_the_keys_of_each_cache_apart() {
+        let b25 = KeyCache::<[u8; 16]>::new();
+        let b61 = KeyCache::<[u8; 32]>::new();
+
+        b25.insert(&[0x01], [0x11; 16]);
+        assert_eq!(b25.get(&[0x01]), Some([0x11; 16]));
+        assert_eq!(b61.get(&[0x01]), None);
+    }
+}
@@ -0,0 +1,7 @@
+//! What the conditional access systems of ARIB STD-B25 and STD-B61 have in common.
+
+mod emm;
+mod key_cache;
+
+pub use emm::{Emm, EmmHistory, read_emms};
+pub use key_cache::KeyCache;
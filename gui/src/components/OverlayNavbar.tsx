@@ -4,6 +4,7 @@ import clsx from "clsx";
 import { type JSX, useState } from "react";
 
 import { useStream } from "../api/stream";
+import { Reception } from "../gen/chibitv/v1/chibitv_pb";
 import { chromeTransition, useChromeHold, usePlayerChrome } from "../player/chrome";
 
 interface OverlayNavbarProps {
@@ -27,10 +28,18 @@ export function OverlayNavbar({
   // The programme on air is known only once its SI has been received, so until
   // then the service names what is being watched.
   const title = event?.title || state?.service?.name;
+  const outage =
+    state?.reception === Reception.SIGNAL_LOST
+      ? "No signal"
+      : state?.reception === Reception.RECOVERING
+        ? "Retuning…"
+        : undefined;
 
   // The details are a dialog rather than a tooltip because a touch screen has
   // no hover to open one with, so the UI stays put while it is open.
   useChromeHold("event-details", areDetailsOpen);
+  // The picture freezes during an outage, so the reason stays on screen.
+  useChromeHold("outage", outage !== undefined);
 
   return (
     <nav
@@ -54,6 +63,11 @@ export function OverlayNavbar({
             keeps it legible over the picture, and a shadow on top of that only
             showed up as a smudge on an installed app for iOS. */}
         {title && <h1 className="truncate text-sm font-medium sm:text-base">{title}</h1>}
+        {outage && (
+          <span role="status" className="shrink-0 rounded bg-danger/80 px-2 py-0.5 text-xs font-medium">
+            {outage}
+          </span>
+        )}
         {description.length > 0 && (
           <Modal isOpen={areDetailsOpen} onOpenChange={setAreDetailsOpen}>
             <Button
@@ -10,7 +10,7 @@ import type { Message } from "@bufbuild/protobuf";
  * Describes the file chibitv/v1/chibitv.proto.
  */
 export const file_chibitv_v1_chibitv: GenFile = /*@__PURE__*/
-  fileDesc("ChhjaGliaXR2L3YxL2NoaWJpdHYucHJvdG8SCmNoaWJpdHYudjEiFQoTTGlzdENoYW5uZWxzUmVxdWVzdCI9ChRMaXN0Q2hhbm5lbHNSZXNwb25zZRIlCghjaGFubmVscxgBIAMoCzITLmNoaWJpdHYudjEuQ2hhbm5lbCJYCgdDaGFubmVsEgoKAmlkGAEgASgNEgwKBG5hbWUYAiABKAkSMwoPZGVsaXZlcnlfc3lzdGVtGAMgASgOMhouY2hpYml0di52MS5EZWxpdmVyeVN5c3RlbSIVChNMaXN0U2VydmljZXNSZXF1ZXN0Ij0KFExpc3RTZXJ2aWNlc1Jlc3BvbnNlEiUKCHNlcnZpY2VzGAEgAygLMhMuY2hpYml0di52MS5TZXJ2aWNlIk4KB1NlcnZpY2USCgoCaWQYASABKA0SDAoEbmFtZRgCIAEoCRIVCg1wcm92aWRlcl9uYW1lGAMgASgJEhIKCmNoYW5uZWxfaWQYBCABKA0iOwoRTGlzdEV2ZW50c1JlcXVlc3QSFwoKc2VydmljZV9pZBgBIAEoDUgAiAEBQg0KC19zZXJ2aWNlX2lkIjcKEkxpc3RFdmVudHNSZXNwb25zZRIhCgZldmVudHMYASADKAsyES5jaGliaXR2LnYxLkV2ZW50IjIKFFJlZnJlc2hFdmVudHNSZXF1ZXN0EhoKEmR3ZWxsX3RpbWVfc2Vjb25kcxgBIAEoDSIxChBFdmVudERlc2NyaXB0aW9uEgwKBG5hbWUYASABKAkSDwoHY29udGVudBgCIAEoCSIqCghEYXRlVGltZRIPCgdzZWNvbmRzGAEgASgDEg0KBW5hbm9zGAIgASgNIuEBCgVFdmVudBIKCgJpZBgBIAEoDRINCgV0aXRsZRgCIAEoCRIxCgtkZXNjcmlwdGlvbhgDIAMoCzIcLmNoaWJpdHYudjEuRXZlbnREZXNjcmlwdGlvbhItCgpzdGFydF90aW1lGAQgASgLMhQuY2hpYml0di52MS5EYXRlVGltZUgAiAEBEisKCGVuZF90aW1lGAUgASgLMhQuY2hpYml0di52MS5EYXRlVGltZUgBiAEBEhIKCnNlcnZpY2VfaWQYBiABKA1CDQoLX3N0YXJ0X3RpbWVCCwoJX2VuZF90aW1lIiMKDVN0cmVhbVJlcXVlc3QSEgoKc2VydmljZV9pZBgBIAEoDSJ1CgtTdHJlYW1TdGF0ZRIpCgdzZXJ2aWNlGAEgASgLMhMuY2hpYml0di52MS5TZXJ2aWNlSACIAQESJQoFZXZlbnQYAiABKAsyES5jaGliaXR2LnYxLkV2ZW50SAGIAQFCCgoIX3NlcnZpY2VCCAoGX2V2ZW50IlUKDlN0cmVhbVJlc3BvbnNlEigKBXN0YXRlGAEgASgLMhcuY2hpYml0di52MS5TdHJlYW1TdGF0ZUgAEg4KBGZtcDQYAiABKAxIAEIJCgdwYXlsb2FkKmkKDkRlbGl2ZXJ5U3lzdGVtEh8KG0RFTElWRVJZX1NZU1RFTV9VTlNQRUNJRklFRBAAEhoKFkRFTElWRVJZX1NZU1RFTV9JU0RCX1QQARIaChZERUxJVkVSWV9TWVNURU1fSVNEQl9TEAIyjgMKDkNoaWJpdHZTZXJ2aWNlElEKDExpc3RDaGFubmVscxIfLmNoaWJpdHYudjEuTGlzdENoYW5uZWxzUmVxdWVzdBogLmNoaWJpdHYudjEuTGlzdENoYW5uZWxzUmVzcG9uc2USUQoMTGlzdFNlcnZpY2VzEh8uY2hpYml0di52MS5MaXN0U2VydmljZXNSZXF1ZXN0GiAuY2hpYml0di52MS5MaXN0U2VydmljZXNSZXNwb25zZRJLCgpMaXN0RXZlbnRzEh0uY2hpYml0di52MS5MaXN0RXZlbnRzUmVxdWVzdBoeLmNoaWJpdHYudjEuTGlzdEV2ZW50c1Jlc3BvbnNlEkYKDVJlZnJlc2hFdmVudHMSIC5jaGliaXR2LnYxLlJlZnJlc2hFdmVudHNSZXF1ZXN0GhEuY2hpYml0di52MS5FdmVudDABEkEKBlN0cmVhbRIZLmNoaWJpdHYudjEuU3RyZWFtUmVxdWVzdBoaLmNoaWJpdHYudjEuU3RyZWFtUmVzcG9uc2UwAWIGcHJvdG8z");
+  fileDesc("ChhjaGliaXR2L3YxL2NoaWJpdHYucHJvdG8SCmNoaWJpdHYudjEiFQoTTGlzdENoYW5uZWxzUmVxdWVzdCI9ChRMaXN0Q2hhbm5lbHNSZXNwb25zZRIlCghjaGFubmVscxgBIAMoCzITLmNoaWJpdHYudjEuQ2hhbm5lbCJYCgdDaGFubmVsEgoKAmlkGAEgASgNEgwKBG5hbWUYAiABKAkSMwoPZGVsaXZlcnlfc3lzdGVtGAMgASgOMhouY2hpYml0di52MS5EZWxpdmVyeVN5c3RlbSIVChNMaXN0U2VydmljZXNSZXF1ZXN0Ij0KFExpc3RTZXJ2aWNlc1Jlc3BvbnNlEiUKCHNlcnZpY2VzGAEgAygLMhMuY2hpYml0di52MS5TZXJ2aWNlIk4KB1NlcnZpY2USCgoCaWQYASABKA0SDAoEbmFtZRgCIAEoCRIVCg1wcm92aWRlcl9uYW1lGAMgASgJEhIKCmNoYW5uZWxfaWQYBCABKA0iOwoRTGlzdEV2ZW50c1JlcXVlc3QSFwoKc2VydmljZV9pZBgBIAEoDUgAiAEBQg0KC19zZXJ2aWNlX2lkIjcKEkxpc3RFdmVudHNSZXNwb25zZRIhCgZldmVudHMYASADKAsyES5jaGliaXR2LnYxLkV2ZW50IjIKFFJlZnJlc2hFdmVudHNSZXF1ZXN0EhoKEmR3ZWxsX3RpbWVfc2Vjb25kcxgBIAEoDSIxChBFdmVudERlc2NyaXB0aW9uEgwKBG5hbWUYASABKAkSDwoHY29udGVudBgCIAEoCSIqCghEYXRlVGltZRIPCgdzZWNvbmRzGAEgASgDEg0KBW5hbm9zGAIgASgNIuEBCgVFdmVudBIKCgJpZBgBIAEoDRINCgV0aXRsZRgCIAEoCRIxCgtkZXNjcmlwdGlvbhgDIAMoCzIcLmNoaWJpdHYudjEuRXZlbnREZXNjcmlwdGlvbhItCgpzdGFydF90aW1lGAQgASgLMhQuY2hpYml0di52MS5EYXRlVGltZUgAiAEBEisKCGVuZF90aW1lGAUgASgLMhQuY2hpYml0di52MS5EYXRlVGltZUgBiAEBEhIKCnNlcnZpY2VfaWQYBiABKA1CDQoLX3N0YXJ0X3RpbWVCCwoJX2VuZF90aW1lIiMKDVN0cmVhbVJlcXVlc3QSEgoKc2VydmljZV9pZBgBIAEoDSKfAQoLU3RyZWFtU3RhdGUSKQoHc2VydmljZRgBIAEoCzITLmNoaWJpdHYudjEuU2VydmljZUgAiAEBEiUKBWV2ZW50GAIgASgLMhEuY2hpYml0di52MS5FdmVudEgBiAEBEigKCXJlY2VwdGlvbhgDIAEoDjIVLmNoaWJpdHYudjEuUmVjZXB0aW9uQgoKCF9zZXJ2aWNlQggKBl9ldmVudCJVCg5TdHJlYW1SZXNwb25zZRIoCgVzdGF0ZRgBIAEoCzIXLmNoaWJpdHYudjEuU3RyZWFtU3RhdGVIABIOCgRmbXA0GAIgASgMSABCCQoHcGF5bG9hZCITChFMaXN0VHVuZXJzUmVxdWVzdCI9ChJMaXN0VHVuZXJzUmVzcG9uc2USJwoGdHVuZXJzGAEgAygLMhcuY2hpYml0di52MS5UdW5lclN0YXR1cyIpChVHZXRUdW5lclN0YXR1c1JlcXVlc3QSEAoIdHVuZXJfaWQYASABKA0iTwoWR2V0VHVuZXJTdGF0dXNSZXNwb25zZRIrCgV0dW5lchgBIAEoCzIXLmNoaWJpdHYudjEuVHVuZXJTdGF0dXNIAIgBAUIICgZfdHVuZXIihwEKC1R1bmVyU3RhdHVzEgoKAmlkGAEgASgNEg4KBmxlYXNlZBgCIAEoCBIXCgpjaGFubmVsX2lkGAMgASgNSACIAQESKgoFc3RhdHMYBCABKAsyFi5jaGliaXR2LnYxLlR1bmVyU3RhdHNIAYgBAUINCgtfY2hhbm5lbF9pZEIICgZfc3RhdHMi5AEKClR1bmVyU3RhdHMSDgoGbG9ja2VkGAEgASgIEiAKE3NpZ25hbF9zdHJlbmd0aF9kYm0YAiABKAFIAIgBARITCgZjbnJfZGIYAyABKAFIAYgBARIUCgdwcmVfYmVyGAQgASgBSAKIAQESFQoIcG9zdF9iZXIYBSABKAFIA4gBARImCgZsYXllcnMYBiADKAsyFi5jaGliaXR2LnYxLkxheWVyU3RhdHNCFgoUX3NpZ25hbF9zdHJlbmd0aF9kYm1CCQoHX2Nucl9kYkIKCghfcHJlX2JlckILCglfcG9zdF9iZXIicgoKTGF5ZXJTdGF0cxITCgZjbnJfZGIYASABKAFIAIgBARIUCgdwcmVfYmVyGAIgASgBSAGIAQESFQoIcG9zdF9iZXIYAyABKAFIAogBAUIJCgdfY25yX2RiQgoKCF9wcmVfYmVyQgsKCV9wb3N0X2JlcippCg5EZWxpdmVyeVN5c3RlbRIfChtERUxJVkVSWV9TWVNURU1fVU5TUEVDSUZJRUQQABIaChZERUxJVkVSWV9TWVNURU1fSVNEQl9UEAESGgoWREVMSVZFUllfU1lTVEVNX0lTREJfUxACKnQKCVJlY2VwdGlvbhIZChVSRUNFUFRJT05fVU5TUEVDSUZJRUQQABIXChNSRUNFUFRJT05fUkVDRUlWSU5HEAESGQoVUkVDRVBUSU9OX1NJR05BTF9MT1NUEAISGAoUUkVDRVBUSU9OX1JFQ09WRVJJTkcQAzK0BAoOQ2hpYml0dlNlcnZpY2USUQoMTGlzdENoYW5uZWxzEh8uY2hpYml0di52MS5MaXN0Q2hhbm5lbHNSZXF1ZXN0GiAuY2hpYml0di52MS5MaXN0Q2hhbm5lbHNSZXNwb25zZRJRCgxMaXN0U2VydmljZXMSHy5jaGliaXR2LnYxLkxpc3RTZXJ2aWNlc1JlcXVlc3QaIC5jaGliaXR2LnYxLkxpc3RTZXJ2aWNlc1Jlc3BvbnNlEksKCkxpc3RFdmVudHMSHS5jaGliaXR2LnYxLkxpc3RFdmVudHNSZXF1ZXN0Gh4uY2hpYml0di52MS5MaXN0RXZlbnRzUmVzcG9uc2USRgoNUmVmcmVzaEV2ZW50cxIgLmNoaWJpdHYudjEuUmVmcmVzaEV2ZW50c1JlcXVlc3QaES5jaGliaXR2LnYxLkV2ZW50MAESQQoGU3RyZWFtEhkuY2hpYml0di52MS5TdHJlYW1SZXF1ZXN0GhouY2hpYml0di52MS5TdHJlYW1SZXNwb25zZTABEksKCkxpc3RUdW5lcnMSHS5jaGliaXR2LnYxLkxpc3RUdW5lcnNSZXF1ZXN0Gh4uY2hpYml0di52MS5MaXN0VHVuZXJzUmVzcG9uc2USVwoOR2V0VHVuZXJTdGF0dXMSIS5jaGliaXR2LnYxLkdldFR1bmVyU3RhdHVzUmVxdWVzdBoiLmNoaWJpdHYudjEuR2V0VHVuZXJTdGF0dXNSZXNwb25zZWIGcHJvdG8z");
 
 /**
  * @generated from message chibitv.v1.ListChannelsRequest
@@ -303,6 +303,14 @@ export type StreamState = Message<"chibitv.v1.StreamState"> & {
    * @generated from field: optional chibitv.v1.Event event = 2;
    */
   event?: Event | undefined;
+
+  /**
+   * How well the tuner of the stream is receiving. A state is sent whenever
+   * it changes, so a player can tell an outage from a stalled connection.
+   *
+   * @generated from field: chibitv.v1.Reception reception = 3;
+   */
+  reception: Reception;
 };
 
 /**
@@ -341,6 +349,186 @@ export type StreamResponse = Message<"chibitv.v1.StreamResponse"> & {
 export const StreamResponseSchema: GenMessage<StreamResponse> = /*@__PURE__*/
   messageDesc(file_chibitv_v1_chibitv, 14);
 
+/**
+ * @generated from message chibitv.v1.ListTunersRequest
+ */
+export type ListTunersRequest = Message<"chibitv.v1.ListTunersRequest"> & {
+};
+
+/**
+ * Describes the message chibitv.v1.ListTunersRequest.
+ * Use `create(ListTunersRequestSchema)` to create a new message.
+ */
+export const ListTunersRequestSchema: GenMessage<ListTunersRequest> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 15);
+
+/**
+ * @generated from message chibitv.v1.ListTunersResponse
+ */
+export type ListTunersResponse = Message<"chibitv.v1.ListTunersResponse"> & {
+  /**
+   * @generated from field: repeated chibitv.v1.TunerStatus tuners = 1;
+   */
+  tuners: TunerStatus[];
+};
+
+/**
+ * Describes the message chibitv.v1.ListTunersResponse.
+ * Use `create(ListTunersResponseSchema)` to create a new message.
+ */
+export const ListTunersResponseSchema: GenMessage<ListTunersResponse> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 16);
+
+/**
+ * @generated from message chibitv.v1.GetTunerStatusRequest
+ */
+export type GetTunerStatusRequest = Message<"chibitv.v1.GetTunerStatusRequest"> & {
+  /**
+   * @generated from field: uint32 tuner_id = 1;
+   */
+  tunerId: number;
+};
+
+/**
+ * Describes the message chibitv.v1.GetTunerStatusRequest.
+ * Use `create(GetTunerStatusRequestSchema)` to create a new message.
+ */
+export const GetTunerStatusRequestSchema: GenMessage<GetTunerStatusRequest> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 17);
+
+/**
+ * @generated from message chibitv.v1.GetTunerStatusResponse
+ */
+export type GetTunerStatusResponse = Message<"chibitv.v1.GetTunerStatusResponse"> & {
+  /**
+   * @generated from field: optional chibitv.v1.TunerStatus tuner = 1;
+   */
+  tuner?: TunerStatus | undefined;
+};
+
+/**
+ * Describes the message chibitv.v1.GetTunerStatusResponse.
+ * Use `create(GetTunerStatusResponseSchema)` to create a new message.
+ */
+export const GetTunerStatusResponseSchema: GenMessage<GetTunerStatusResponse> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 18);
+
+/**
+ * @generated from message chibitv.v1.TunerStatus
+ */
+export type TunerStatus = Message<"chibitv.v1.TunerStatus"> & {
+  /**
+   * @generated from field: uint32 id = 1;
+   */
+  id: number;
+
+  /**
+   * Whether a stream or an event refresh holds the tuner.
+   *
+   * @generated from field: bool leased = 2;
+   */
+  leased: boolean;
+
+  /**
+   * The channel the tuner is tuned to, while it is leased.
+   *
+   * @generated from field: optional uint32 channel_id = 3;
+   */
+  channelId?: number | undefined;
+
+  /**
+   * Absent when the tuner cannot measure its reception.
+   *
+   * @generated from field: optional chibitv.v1.TunerStats stats = 4;
+   */
+  stats?: TunerStats | undefined;
+};
+
+/**
+ * Describes the message chibitv.v1.TunerStatus.
+ * Use `create(TunerStatusSchema)` to create a new message.
+ */
+export const TunerStatusSchema: GenMessage<TunerStatus> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 19);
+
+/**
+ * TunerStats is the reception of a tuner. Each measurement is absent when the
+ * frontend does not report it.
+ *
+ * @generated from message chibitv.v1.TunerStats
+ */
+export type TunerStats = Message<"chibitv.v1.TunerStats"> & {
+  /**
+   * @generated from field: bool locked = 1;
+   */
+  locked: boolean;
+
+  /**
+   * @generated from field: optional double signal_strength_dbm = 2;
+   */
+  signalStrengthDbm?: number | undefined;
+
+  /**
+   * @generated from field: optional double cnr_db = 3;
+   */
+  cnrDb?: number | undefined;
+
+  /**
+   * The bit error rate before the inner code corrects the errors.
+   *
+   * @generated from field: optional double pre_ber = 4;
+   */
+  preBer?: number | undefined;
+
+  /**
+   * The bit error rate after the inner code has corrected what it could.
+   *
+   * @generated from field: optional double post_ber = 5;
+   */
+  postBer?: number | undefined;
+
+  /**
+   * The measurements of each hierarchical layer on ISDB-T, from layer A to C.
+   *
+   * @generated from field: repeated chibitv.v1.LayerStats layers = 6;
+   */
+  layers: LayerStats[];
+};
+
+/**
+ * Describes the message chibitv.v1.TunerStats.
+ * Use `create(TunerStatsSchema)` to create a new message.
+ */
+export const TunerStatsSchema: GenMessage<TunerStats> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 20);
+
+/**
+ * @generated from message chibitv.v1.LayerStats
+ */
+export type LayerStats = Message<"chibitv.v1.LayerStats"> & {
+  /**
+   * @generated from field: optional double cnr_db = 1;
+   */
+  cnrDb?: number | undefined;
+
+  /**
+   * @generated from field: optional double pre_ber = 2;
+   */
+  preBer?: number | undefined;
+
+  /**
+   * @generated from field: optional double post_ber = 3;
+   */
+  postBer?: number | undefined;
+};
+
+/**
+ * Describes the message chibitv.v1.LayerStats.
+ * Use `create(LayerStatsSchema)` to create a new message.
+ */
+export const LayerStatsSchema: GenMessage<LayerStats> = /*@__PURE__*/
+  messageDesc(file_chibitv_v1_chibitv, 21);
+
 /**
  * DeliverySystem is the broadcast wave a channel is carried on.
  *
@@ -373,6 +561,45 @@ export enum DeliverySystem {
 export const DeliverySystemSchema: GenEnum<DeliverySystem> = /*@__PURE__*/
   enumDesc(file_chibitv_v1_chibitv, 0);
 
+/**
+ * Reception is whether the tuner of a stream is receiving its channel.
+ *
+ * @generated from enum chibitv.v1.Reception
+ */
+export enum Reception {
+  /**
+   * @generated from enum value: RECEPTION_UNSPECIFIED = 0;
+   */
+  UNSPECIFIED = 0,
+
+  /**
+   * The channel is being received.
+   *
+   * @generated from enum value: RECEPTION_RECEIVING = 1;
+   */
+  RECEIVING = 1,
+
+  /**
+   * The frontend lost its lock or the input stalled, as in rain fade.
+   *
+   * @generated from enum value: RECEPTION_SIGNAL_LOST = 2;
+   */
+  SIGNAL_LOST = 2,
+
+  /**
+   * The tuner has been tuned again and is waiting for the input to resume.
+   *
+   * @generated from enum value: RECEPTION_RECOVERING = 3;
+   */
+  RECOVERING = 3,
+}
+
+/**
+ * Describes the enum chibitv.v1.Reception.
+ */
+export const ReceptionSchema: GenEnum<Reception> = /*@__PURE__*/
+  enumDesc(file_chibitv_v1_chibitv, 1);
+
 /**
  * ChibitvService exposes the backend operations used by the chibitv GUI.
  *
@@ -424,6 +651,25 @@ export const ChibitvService: GenService<{
     input: typeof StreamRequestSchema;
     output: typeof StreamResponseSchema;
   },
+  /**
+   * ListTuners reports every configured tuner, measuring the reception of
+   * each at the time of the call.
+   *
+   * @generated from rpc chibitv.v1.ChibitvService.ListTuners
+   */
+  listTuners: {
+    methodKind: "unary";
+    input: typeof ListTunersRequestSchema;
+    output: typeof ListTunersResponseSchema;
+  },
+  /**
+   * @generated from rpc chibitv.v1.ChibitvService.GetTunerStatus
+   */
+  getTunerStatus: {
+    methodKind: "unary";
+    input: typeof GetTunerStatusRequestSchema;
+    output: typeof GetTunerStatusResponseSchema;
+  },
 }> = /*@__PURE__*/
   serviceDesc(file_chibitv_v1_chibitv, 0);
 